@@ -1,71 +1,411 @@
+use crate::filesystem::CopyOptions;
+use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const LIST_WIDTH_MIN: i32 = 20;
+const LIST_WIDTH_MAX: i32 = 80;
+const TICK_RATE_MIN_MS: u64 = 10;
+const TICK_RATE_MAX_MS: u64 = 1000;
+
+/// Whether chrono understands every specifier in `pattern`.
+fn is_valid_date_format(pattern: &str) -> bool {
+    chrono::format::StrftimeItems::new(pattern).all(|item| !matches!(item, chrono::format::Item::Error))
+}
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub keybindings: Keybindings,
+    pub use_trash: bool,
+    /// Show Nerd Font file icons instead of the plain "[DIR]" prefix
+    pub use_icons: bool,
+    pub bookmarks: Vec<String>,
+    pub theme: Theme,
+    /// Where a deep search starts unless toggled for the session
+    pub search_scope: SearchScope,
+    /// Order of deep search results
+    pub search_sort: SearchSort,
+    /// Commands offered by "open with", keyed by lowercase extension. "*" applies to every file.
+    pub open_with: BTreeMap<String, Vec<String>>,
+    /// Directory Xplore was in when it last quit; the next session starts there
+    pub last_dir: Option<PathBuf>,
+    /// Directories visited lately, most recent first
+    pub recent_dirs: Vec<RecentDir>,
+    /// How many recent directories are kept; 0 turns the list off
+    pub recent_dirs_limit: usize,
+    /// A deep search stops after this many results
+    pub max_search_results: usize,
+    /// Deep search descends into symlinked folders. Each folder is still searched once, but
+    /// every folder has to be resolved to notice repeats, which slows down large searches.
+    pub search_follow_links: bool,
+    /// Lines of a text file shown in the details panel
+    pub preview_lines: usize,
+    /// Directories stay visible under a listing filter so navigation still works
+    pub filter_keeps_dirs: bool,
+    /// List folders before files; otherwise they interleave by the sort key
+    pub dirs_first: bool,
+    /// Draw a dim line between the folders and the files when folders come first
+    pub group_separator: bool,
+    /// chrono strftime pattern for the modified time in the details panel
+    pub date_format: String,
+    /// Share of the width, in percent, the file list gets next to the details panel
+    pub list_width: u16,
+    /// Show the home folder as "~" in the path bar and pane titles
+    pub abbreviate_home: bool,
+    /// Up on the first entry goes to the last one and down on the last to the first
+    pub wrap_navigation: bool,
+    /// Copies keep the modified time of the original
+    pub preserve_timestamps: bool,
+    /// Copies keep the permissions of the original
+    pub preserve_permissions: bool,
+    /// Copies keep the xattrs, and so the description, of the original
+    pub preserve_xattrs: bool,
+    /// Milliseconds between ticks while nothing is going on; background work and watcher
+    /// polling are checked once per tick
+    pub tick_rate_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchScope {
+    /// The filesystem root
+    Global,
+    /// The directory currently being browsed
+    Current,
+}
+
+impl SearchScope {
+    pub fn toggle(self) -> Self {
+        match self {
+            SearchScope::Global => SearchScope::Current,
+            SearchScope::Current => SearchScope::Global,
+        }
+    }
+}
+
+/// A directory on the recent list and when it was last entered, in seconds since the epoch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentDir {
+    pub path: PathBuf,
+    pub visited: i64,
+}
+
+/// How deep search results are ordered. A fuzzy search always puts the best matches first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSort {
+    /// Like the listing: by its sort key, with folders first if they are listed first
+    Listing,
+    /// Shallowest paths first, so results near the search root come up top
+    Depth,
+    /// In the order the search walks into them
+    Found,
+}
+
+/// UI colors. Each value is a color name ("cyan", "darkgray", ...), a hex string ("#00ffff")
+/// or a 256-color index ("208"). Unparseable values fall back to the default color.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub dir_fg: String,
+    pub symlink_fg: String,
+    pub selected_bg: String,
+    pub highlight_bg: String,
+    pub border: String,
+    pub search_active: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            dir_fg: "cyan".to_string(),
+            symlink_fg: "magenta".to_string(),
+            selected_bg: "#323232".to_string(),
+            highlight_bg: "darkgray".to_string(),
+            border: "reset".to_string(),
+            search_active: "yellow".to_string(),
+        }
+    }
+}
+
+impl Theme {
+    pub fn dir_fg(&self) -> Color {
+        parse_color(&self.dir_fg, Color::Cyan)
+    }
+
+    pub fn symlink_fg(&self) -> Color {
+        parse_color(&self.symlink_fg, Color::Magenta)
+    }
+
+    pub fn selected_bg(&self) -> Color {
+        parse_color(&self.selected_bg, Color::Rgb(50, 50, 50))
+    }
+
+    pub fn highlight_bg(&self) -> Color {
+        parse_color(&self.highlight_bg, Color::DarkGray)
+    }
+
+    pub fn border(&self) -> Color {
+        parse_color(&self.border, Color::Reset)
+    }
+
+    pub fn search_active(&self) -> Color {
+        parse_color(&self.search_active, Color::Yellow)
+    }
+}
+
+fn parse_color(value: &str, fallback: Color) -> Color {
+    Color::from_str(value.trim()).unwrap_or(fallback)
+}
+
+/// The keys bound to one action. config.toml may give a single string or a list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "KeyListRepr")]
+pub struct KeyList(pub Vec<String>);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyListRepr {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl From<KeyListRepr> for KeyList {
+    fn from(repr: KeyListRepr) -> Self {
+        match repr {
+            KeyListRepr::One(key) => KeyList(vec![key]),
+            KeyListRepr::Many(keys) => KeyList(keys),
+        }
+    }
+}
+
+impl KeyList {
+    pub fn new(keys: &[&str]) -> Self {
+        KeyList(keys.iter().map(|k| k.to_string()).collect())
+    }
+
+    pub fn matches(&self, key: &str) -> bool {
+        self.0.iter().any(|k| k == key)
+    }
+
+    /// Whether one of the keys is a sequence that begins with `key`.
+    pub fn starts_sequence(&self, key: &str) -> bool {
+        self.0.iter().any(|k| k.split_once(' ').is_some_and(|(first, _)| first == key))
+    }
+}
+
+impl From<&str> for KeyList {
+    fn from(key: &str) -> Self {
+        KeyList(vec![key.to_string()])
+    }
+}
+
+/// Lets key handlers keep comparing `event_str == keybindings.action`.
+impl PartialEq<KeyList> for String {
+    fn eq(&self, keys: &KeyList) -> bool {
+        keys.matches(self)
+    }
+}
+
+impl std::fmt::Display for KeyList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.join("/"))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Keybindings {
-    pub quit: String,
-    pub edit: String,
-    pub up: String,
-    pub down: String,
-    pub enter: String,
-    pub backspace: String,
-    pub settings: String,
-    pub search: String,
-    pub select: String,
-    pub copy: String,
-    pub cut: String,
-    pub paste: String,
-    pub new_folder: String,
-    pub delete: String,
-    pub help: String,
-    pub home: String,
-    pub end: String,
-    pub ctrl_home: String,
-    pub ctrl_end: String,
-    pub page_up: String,
-    pub page_down: String,
-    pub select_all: String,
-    pub deselect_all: String,
-    pub toggle_terminal: String,
-    pub terminal_prefix: String,
+    pub quit: KeyList,
+    pub edit: KeyList,
+    pub edit_dir_description: KeyList,
+    pub edit_external: KeyList,
+    pub up: KeyList,
+    pub down: KeyList,
+    pub enter: KeyList,
+    pub backspace: KeyList,
+    pub settings: KeyList,
+    pub search: KeyList,
+    pub select: KeyList,
+    pub visual_select: KeyList,
+    pub copy: KeyList,
+    pub cut: KeyList,
+    /// Put entries on the clipboard to be pasted as hard links
+    pub copy_link: KeyList,
+    pub paste: KeyList,
+    pub new_folder: KeyList,
+    pub delete: KeyList,
+    pub help: KeyList,
+    pub home: KeyList,
+    pub end: KeyList,
+    pub ctrl_home: KeyList,
+    pub ctrl_end: KeyList,
+    pub page_up: KeyList,
+    pub page_down: KeyList,
+    pub select_all: KeyList,
+    pub deselect_all: KeyList,
+    pub invert_selection: KeyList,
+    pub toggle_terminal: KeyList,
+    pub terminal_prefix: KeyList,
+    pub open_shell: KeyList,
+    pub rename: KeyList,
+    pub toggle_hidden: KeyList,
+    pub filter: KeyList,
+    pub cycle_sort: KeyList,
+    pub reverse_sort: KeyList,
+    pub toggle_dirs_first: KeyList,
+    pub grow_list: KeyList,
+    pub shrink_list: KeyList,
+    pub delete_permanent: KeyList,
+    pub calc_size: KeyList,
+    pub new_file: KeyList,
+    pub add_bookmark: KeyList,
+    pub bookmarks: KeyList,
+    pub recent_dirs: KeyList,
+    pub go_back: KeyList,
+    pub go_forward: KeyList,
+    pub cycle_search_mode: KeyList,
+    pub toggle_content_search: KeyList,
+    pub toggle_search_scope: KeyList,
+    pub new_tab: KeyList,
+    pub close_tab: KeyList,
+    pub next_tab: KeyList,
+    pub prev_tab: KeyList,
+    pub toggle_dual_pane: KeyList,
+    pub toggle_details: KeyList,
+    pub switch_pane: KeyList,
+    pub open_with: KeyList,
+    pub shell_command: KeyList,
+    pub copy_path: KeyList,
+    pub breadcrumbs: KeyList,
+    pub go_to_path: KeyList,
+    pub reveal: KeyList,
+    /// Start typing the beginning of a name to jump to it
+    pub jump_to_name: KeyList,
+    pub undo: KeyList,
+    pub permissions: KeyList,
+    pub symlink: KeyList,
+    pub archive: KeyList,
+    pub extract: KeyList,
+    pub clear_description: KeyList,
+    pub export_descriptions: KeyList,
+    pub import_descriptions: KeyList,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            keybindings: Keybindings {
-                quit: "q".to_string(),
-                edit: "e".to_string(),
-                up: "k".to_string(),
-                down: "j".to_string(),
-                enter: "enter".to_string(),
-                backspace: "backspace".to_string(),
-                settings: "s".to_string(),
-                search: "f3".to_string(),
-                select: "space".to_string(),
-                copy: "ctrl+c".to_string(),
-                cut: "ctrl+x".to_string(),
-                paste: "ctrl+v".to_string(),
-                new_folder: "ctrl+n".to_string(),
-                delete: "shift+delete".to_string(),
-                help: "f1".to_string(),
-                home: "home".to_string(),
-                end: "end".to_string(),
-                ctrl_home: "ctrl+home".to_string(),
-                ctrl_end: "ctrl+end".to_string(),
-                page_up: "pageup".to_string(),
-                page_down: "pagedown".to_string(),
-                select_all: "ctrl+a".to_string(),
-                deselect_all: "ctrl+d".to_string(),
-                toggle_terminal: "ctrl+t".to_string(),
-                terminal_prefix: "ctrl+b".to_string(),
-            },
+            keybindings: Keybindings::default(),
+            // The XDG trash is the only one implemented, so elsewhere deletes are permanent
+            use_trash: cfg!(all(unix, not(target_os = "macos"))),
+            use_icons: false,
+            bookmarks: Vec::new(),
+            theme: Theme::default(),
+            search_scope: SearchScope::Global,
+            search_sort: SearchSort::Listing,
+            open_with: BTreeMap::new(),
+            last_dir: None,
+            recent_dirs: Vec::new(),
+            recent_dirs_limit: 30,
+            max_search_results: 1000,
+            search_follow_links: false,
+            preview_lines: 100,
+            filter_keeps_dirs: true,
+            dirs_first: true,
+            group_separator: true,
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            abbreviate_home: true,
+            list_width: 50,
+            wrap_navigation: false,
+            preserve_timestamps: true,
+            preserve_permissions: true,
+            preserve_xattrs: true,
+            tick_rate_ms: 100,
+        }
+    }
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            quit: "q".into(),
+            edit: "e".into(),
+            edit_dir_description: "shift+e".into(),
+            edit_external: "alt+d".into(),
+            up: KeyList::new(&["k", "up"]),
+            down: KeyList::new(&["j", "down"]),
+            enter: "enter".into(),
+            backspace: "backspace".into(),
+            settings: "s".into(),
+            search: "f3".into(),
+            select: "space".into(),
+            visual_select: "v".into(),
+            copy: "ctrl+c".into(),
+            cut: "ctrl+x".into(),
+            copy_link: "alt+l".into(),
+            paste: "ctrl+v".into(),
+            new_folder: "ctrl+n".into(),
+            delete: "shift+delete".into(),
+            help: "f1".into(),
+            home: "home".into(),
+            end: "end".into(),
+            ctrl_home: KeyList::new(&["ctrl+home", "g g"]),
+            ctrl_end: KeyList::new(&["ctrl+end", "shift+g"]),
+            page_up: "pageup".into(),
+            page_down: "pagedown".into(),
+            select_all: "ctrl+a".into(),
+            deselect_all: "ctrl+d".into(),
+            invert_selection: "*".into(),
+            toggle_terminal: "ctrl+t".into(),
+            terminal_prefix: "ctrl+b".into(),
+            open_shell: "shift+s".into(),
+            rename: "r".into(),
+            toggle_hidden: ".".into(),
+            filter: "shift+f".into(),
+            cycle_sort: "o".into(),
+            reverse_sort: "shift+o".into(),
+            toggle_dirs_first: "shift+d".into(),
+            grow_list: ">".into(),
+            shrink_list: "<".into(),
+            delete_permanent: "ctrl+delete".into(),
+            calc_size: "z".into(),
+            new_file: "n".into(),
+            add_bookmark: "b".into(),
+            bookmarks: "shift+b".into(),
+            recent_dirs: "shift+h".into(),
+            go_back: "alt+left".into(),
+            go_forward: "alt+right".into(),
+            cycle_search_mode: "ctrl+r".into(),
+            toggle_content_search: "ctrl+g".into(),
+            toggle_search_scope: "ctrl+l".into(),
+            new_tab: "t".into(),
+            close_tab: "w".into(),
+            next_tab: "]".into(),
+            prev_tab: "[".into(),
+            toggle_dual_pane: "p".into(),
+            toggle_details: "i".into(),
+            switch_pane: "tab".into(),
+            open_with: "ctrl+o".into(),
+            shell_command: "!".into(),
+            copy_path: "y".into(),
+            breadcrumbs: "u".into(),
+            go_to_path: ":".into(),
+            reveal: "shift+r".into(),
+            jump_to_name: "f".into(),
+            undo: "ctrl+z".into(),
+            permissions: "m".into(),
+            symlink: "shift+l".into(),
+            archive: "shift+z".into(),
+            extract: "shift+x".into(),
+            clear_description: "ctrl+e".into(),
+            export_descriptions: "alt+e".into(),
+            import_descriptions: "alt+i".into(),
         }
     }
 }
@@ -89,63 +429,209 @@ impl Config {
         }
 
         if let Ok(content) = fs::read_to_string(config_path) {
-            toml::from_str(&content).unwrap_or_default()
+            let mut config: Config = toml::from_str(&content).unwrap_or_default();
+            // Single-string bindings date from before key lists, when the arrows, Enter, Backspace,
+            // Home/End and the Page keys were hard-wired on top of them
+            let legacy = toml::from_str::<toml::Table>(&content).ok()
+                .and_then(|table| table.get("keybindings")?.get("up").map(|up| up.is_str()))
+                .unwrap_or(false);
+            if legacy {
+                config.restore_navigation_keys();
+            }
+            config.resize_list(0);
+            config.tick_rate_ms = config.tick_rate_ms.clamp(TICK_RATE_MIN_MS, TICK_RATE_MAX_MS);
+            // An older config may ask for a trash this platform does not have
+            config.use_trash &= cfg!(all(unix, not(target_os = "macos")));
+            // Formatting with a broken pattern panics, so never let one reach the renderer
+            if !is_valid_date_format(&config.date_format) {
+                config.date_format = DEFAULT_DATE_FORMAT.to_string();
+            }
+            config
         } else {
             Self::default()
         }
     }
 
+    fn restore_navigation_keys(&mut self) {
+        for (action, key) in NAVIGATION_KEYS_ACTIONS.iter().zip(NAVIGATION_KEYS) {
+            if !self.is_key_taken(key, "")
+                && let Some(keys) = self.keys_mut(action)
+            {
+                keys.0.push(key.to_string());
+            }
+        }
+    }
+
+    /// Change list_width by `delta` percentage points, keeping both panes usable.
+    pub fn resize_list(&mut self, delta: i32) {
+        self.list_width = (self.list_width as i32 + delta).clamp(LIST_WIDTH_MIN, LIST_WIDTH_MAX) as u16;
+    }
+
+    /// How long the main loop waits for input between ticks when idle.
+    pub fn tick_rate(&self) -> Duration {
+        Duration::from_millis(self.tick_rate_ms)
+    }
+
+    /// Number of idle ticks that make up `period`, at least one.
+    pub fn ticks_per(&self, period: Duration) -> u64 {
+        (period.as_millis() as u64 / self.tick_rate_ms.max(1)).max(1)
+    }
+
+    /// What a paste in copy mode keeps of the originals.
+    pub fn copy_options(&self) -> CopyOptions {
+        CopyOptions {
+            preserve_timestamps: self.preserve_timestamps,
+            preserve_perms: self.preserve_permissions,
+            preserve_xattrs: self.preserve_xattrs,
+        }
+    }
+
     pub fn save(&self) -> std::io::Result<()> {
         let config_path = Self::get_path();
-        let toml = toml::to_string_pretty(self).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        let toml = toml::to_string_pretty(self).map_err(std::io::Error::other)?;
         fs::write(config_path, toml)
     }
 
     pub fn is_key_taken(&self, key: &str, exclude_action: &str) -> bool {
-        for (action, current_key) in self.get_actions() {
-            if action != exclude_action && current_key == key {
+        // A key that starts a sequence can't also be bound on its own, or the sequence could never be typed
+        let first = key.split_once(' ').map(|(first, _)| first);
+        for (action, current_keys) in self.get_actions() {
+            if action != exclude_action
+                && (current_keys.matches(key)
+                    || current_keys.starts_sequence(key)
+                    || first.is_some_and(|first| current_keys.matches(first)))
+            {
                 return true;
             }
         }
         false
     }
 
-    pub fn set_key(&mut self, action: &str, key: String) -> Result<(), &'static str> {
-        if self.is_key_taken(&key, action) {
+    /// Whether `key` is the first key of some bound sequence, so the next key has to be waited for.
+    pub fn is_sequence_prefix(&self, key: &str) -> bool {
+        self.get_actions().iter().any(|(_, keys)| keys.starts_sequence(key))
+    }
+
+    /// Check that `key` can be bound to `action`. On success, returns a warning if the key is
+    /// one that normally navigates the file list.
+    fn check_key(&self, action: &str, key: &str) -> Result<Option<&'static str>, &'static str> {
+        if !is_valid_key(key) {
+            return Err("Xplore can't detect that key; pick another");
+        }
+        if self.is_key_taken(key, action) {
             return Err("Key already assigned to another action");
         }
-        match action {
-            "quit" => self.keybindings.quit = key,
-            "edit" => self.keybindings.edit = key,
-            "up" => self.keybindings.up = key,
-            "down" => self.keybindings.down = key,
-            "enter" => self.keybindings.enter = key,
-            "backspace" => self.keybindings.backspace = key,
-            "settings" => self.keybindings.settings = key,
-            "search" => self.keybindings.search = key,
-            "select" => self.keybindings.select = key,
-            "copy" => self.keybindings.copy = key,
-            "cut" => self.keybindings.cut = key,
-            "paste" => self.keybindings.paste = key,
-            "new_folder" => self.keybindings.new_folder = key,
-            "delete" => self.keybindings.delete = key,
-            "help" => self.keybindings.help = key,
-            "home" => self.keybindings.home = key,
-            "end" => self.keybindings.end = key,
-            "ctrl_home" => self.keybindings.ctrl_home = key,
-            "ctrl_end" => self.keybindings.ctrl_end = key,
-            "page_up" => self.keybindings.page_up = key,
-            "page_down" => self.keybindings.page_down = key,
-            "select_all" => self.keybindings.select_all = key,
-            "deselect_all" => self.keybindings.deselect_all = key,
-            "toggle_terminal" => self.keybindings.toggle_terminal = key,
-            "terminal_prefix" => self.keybindings.terminal_prefix = key,
-            _ => return Err("Invalid action"),
+        Ok((NAVIGATION_KEYS.contains(&key) && !NAVIGATION_KEYS_ACTIONS.contains(&action))
+            .then_some("Warning: that key normally navigates the file list"))
+    }
+
+    /// Bind `action` to `key` alone, replacing its current keys.
+    pub fn set_key(&mut self, action: &str, key: String) -> Result<Option<&'static str>, &'static str> {
+        let warning = self.check_key(action, &key)?;
+        *self.keys_mut(action).ok_or("Invalid action")? = KeyList(vec![key]);
+        Ok(warning)
+    }
+
+    /// Bind `key` to `action` in addition to the keys it already has.
+    pub fn add_key(&mut self, action: &str, key: String) -> Result<Option<&'static str>, &'static str> {
+        let warning = self.check_key(action, &key)?;
+        let keys = self.keys_mut(action).ok_or("Invalid action")?;
+        if keys.matches(&key) {
+            return Err("Key already assigned to this action");
         }
+        keys.0.push(key);
+        Ok(warning)
+    }
+
+    /// Unbind the most recently added key of `action`, keeping at least one.
+    pub fn remove_key(&mut self, action: &str) -> Result<(), &'static str> {
+        let keys = self.keys_mut(action).ok_or("Invalid action")?;
+        if keys.0.len() <= 1 {
+            return Err("An action needs at least one key");
+        }
+        keys.0.pop();
         Ok(())
     }
 
-    pub fn get_actions(&self) -> Vec<(&'static str, String)> {
+    fn keys_mut(&mut self, action: &str) -> Option<&mut KeyList> {
+        Some(match action {
+            "quit" => &mut self.keybindings.quit,
+            "edit" => &mut self.keybindings.edit,
+            "edit_dir_description" => &mut self.keybindings.edit_dir_description,
+            "edit_external" => &mut self.keybindings.edit_external,
+            "clear_description" => &mut self.keybindings.clear_description,
+            "export_descriptions" => &mut self.keybindings.export_descriptions,
+            "import_descriptions" => &mut self.keybindings.import_descriptions,
+            "up" => &mut self.keybindings.up,
+            "down" => &mut self.keybindings.down,
+            "enter" => &mut self.keybindings.enter,
+            "backspace" => &mut self.keybindings.backspace,
+            "settings" => &mut self.keybindings.settings,
+            "search" => &mut self.keybindings.search,
+            "select" => &mut self.keybindings.select,
+            "visual_select" => &mut self.keybindings.visual_select,
+            "copy" => &mut self.keybindings.copy,
+            "cut" => &mut self.keybindings.cut,
+            "copy_link" => &mut self.keybindings.copy_link,
+            "paste" => &mut self.keybindings.paste,
+            "new_folder" => &mut self.keybindings.new_folder,
+            "delete" => &mut self.keybindings.delete,
+            "help" => &mut self.keybindings.help,
+            "home" => &mut self.keybindings.home,
+            "end" => &mut self.keybindings.end,
+            "ctrl_home" => &mut self.keybindings.ctrl_home,
+            "ctrl_end" => &mut self.keybindings.ctrl_end,
+            "page_up" => &mut self.keybindings.page_up,
+            "page_down" => &mut self.keybindings.page_down,
+            "select_all" => &mut self.keybindings.select_all,
+            "deselect_all" => &mut self.keybindings.deselect_all,
+            "invert_selection" => &mut self.keybindings.invert_selection,
+            "toggle_terminal" => &mut self.keybindings.toggle_terminal,
+            "terminal_prefix" => &mut self.keybindings.terminal_prefix,
+            "open_shell" => &mut self.keybindings.open_shell,
+            "rename" => &mut self.keybindings.rename,
+            "toggle_hidden" => &mut self.keybindings.toggle_hidden,
+            "filter" => &mut self.keybindings.filter,
+            "cycle_sort" => &mut self.keybindings.cycle_sort,
+            "reverse_sort" => &mut self.keybindings.reverse_sort,
+            "toggle_dirs_first" => &mut self.keybindings.toggle_dirs_first,
+            "grow_list" => &mut self.keybindings.grow_list,
+            "shrink_list" => &mut self.keybindings.shrink_list,
+            "delete_permanent" => &mut self.keybindings.delete_permanent,
+            "calc_size" => &mut self.keybindings.calc_size,
+            "new_file" => &mut self.keybindings.new_file,
+            "add_bookmark" => &mut self.keybindings.add_bookmark,
+            "bookmarks" => &mut self.keybindings.bookmarks,
+            "recent_dirs" => &mut self.keybindings.recent_dirs,
+            "go_back" => &mut self.keybindings.go_back,
+            "go_forward" => &mut self.keybindings.go_forward,
+            "new_tab" => &mut self.keybindings.new_tab,
+            "close_tab" => &mut self.keybindings.close_tab,
+            "next_tab" => &mut self.keybindings.next_tab,
+            "prev_tab" => &mut self.keybindings.prev_tab,
+            "toggle_dual_pane" => &mut self.keybindings.toggle_dual_pane,
+            "toggle_details" => &mut self.keybindings.toggle_details,
+            "switch_pane" => &mut self.keybindings.switch_pane,
+            "open_with" => &mut self.keybindings.open_with,
+            "shell_command" => &mut self.keybindings.shell_command,
+            "copy_path" => &mut self.keybindings.copy_path,
+            "breadcrumbs" => &mut self.keybindings.breadcrumbs,
+            "go_to_path" => &mut self.keybindings.go_to_path,
+            "reveal" => &mut self.keybindings.reveal,
+            "jump_to_name" => &mut self.keybindings.jump_to_name,
+            "undo" => &mut self.keybindings.undo,
+            "permissions" => &mut self.keybindings.permissions,
+            "symlink" => &mut self.keybindings.symlink,
+            "archive" => &mut self.keybindings.archive,
+            "extract" => &mut self.keybindings.extract,
+            "cycle_search_mode" => &mut self.keybindings.cycle_search_mode,
+            "toggle_content_search" => &mut self.keybindings.toggle_content_search,
+            "toggle_search_scope" => &mut self.keybindings.toggle_search_scope,
+            _ => return None,
+        })
+    }
+
+    pub fn get_actions(&self) -> Vec<(&'static str, KeyList)> {
         vec![
             // Navigation
             ("up", self.keybindings.up.clone()),
@@ -162,25 +648,73 @@ impl Config {
             ("ctrl_end", self.keybindings.ctrl_end.clone()),
             ("page_up", self.keybindings.page_up.clone()),
             ("page_down", self.keybindings.page_down.clone()),
+            ("add_bookmark", self.keybindings.add_bookmark.clone()),
+            ("bookmarks", self.keybindings.bookmarks.clone()),
+            ("recent_dirs", self.keybindings.recent_dirs.clone()),
+            ("go_back", self.keybindings.go_back.clone()),
+            ("go_forward", self.keybindings.go_forward.clone()),
+            ("breadcrumbs", self.keybindings.breadcrumbs.clone()),
+            ("go_to_path", self.keybindings.go_to_path.clone()),
+            ("reveal", self.keybindings.reveal.clone()),
+            ("jump_to_name", self.keybindings.jump_to_name.clone()),
+            ("new_tab", self.keybindings.new_tab.clone()),
+            ("close_tab", self.keybindings.close_tab.clone()),
+            ("next_tab", self.keybindings.next_tab.clone()),
+            ("prev_tab", self.keybindings.prev_tab.clone()),
             ("select_all", self.keybindings.select_all.clone()),
             ("deselect_all", self.keybindings.deselect_all.clone()),
+            ("invert_selection", self.keybindings.invert_selection.clone()),
+            // View
+            ("toggle_hidden", self.keybindings.toggle_hidden.clone()),
+            ("filter", self.keybindings.filter.clone()),
+            ("cycle_sort", self.keybindings.cycle_sort.clone()),
+            ("reverse_sort", self.keybindings.reverse_sort.clone()),
+            ("toggle_dirs_first", self.keybindings.toggle_dirs_first.clone()),
+            ("grow_list", self.keybindings.grow_list.clone()),
+            ("shrink_list", self.keybindings.shrink_list.clone()),
+            ("calc_size", self.keybindings.calc_size.clone()),
+            ("toggle_dual_pane", self.keybindings.toggle_dual_pane.clone()),
+            ("toggle_details", self.keybindings.toggle_details.clone()),
+            ("switch_pane", self.keybindings.switch_pane.clone()),
             // File Operations
             ("select", self.keybindings.select.clone()),
+            ("visual_select", self.keybindings.visual_select.clone()),
             ("copy", self.keybindings.copy.clone()),
             ("cut", self.keybindings.cut.clone()),
+            ("copy_link", self.keybindings.copy_link.clone()),
+            ("copy_path", self.keybindings.copy_path.clone()),
             ("paste", self.keybindings.paste.clone()),
             ("new_folder", self.keybindings.new_folder.clone()),
+            ("new_file", self.keybindings.new_file.clone()),
             ("delete", self.keybindings.delete.clone()),
+            ("delete_permanent", self.keybindings.delete_permanent.clone()),
+            ("rename", self.keybindings.rename.clone()),
+            ("undo", self.keybindings.undo.clone()),
+            ("permissions", self.keybindings.permissions.clone()),
+            ("symlink", self.keybindings.symlink.clone()),
+            ("archive", self.keybindings.archive.clone()),
+            ("extract", self.keybindings.extract.clone()),
             ("edit", self.keybindings.edit.clone()),
+            ("edit_dir_description", self.keybindings.edit_dir_description.clone()),
+            ("edit_external", self.keybindings.edit_external.clone()),
+            ("clear_description", self.keybindings.clear_description.clone()),
+            ("export_descriptions", self.keybindings.export_descriptions.clone()),
+            ("import_descriptions", self.keybindings.import_descriptions.clone()),
+            ("open_with", self.keybindings.open_with.clone()),
+            ("shell_command", self.keybindings.shell_command.clone()),
             // Search
             ("search", self.keybindings.search.clone()),
+            ("cycle_search_mode", self.keybindings.cycle_search_mode.clone()),
+            ("toggle_content_search", self.keybindings.toggle_content_search.clone()),
+            ("toggle_search_scope", self.keybindings.toggle_search_scope.clone()),
             // Terminal
             ("toggle_terminal", self.keybindings.toggle_terminal.clone()),
             ("terminal_prefix", self.keybindings.terminal_prefix.clone()),
+            ("open_shell", self.keybindings.open_shell.clone()),
         ]
     }
 
-    pub fn get_categorized_actions(&self) -> Vec<(&'static str, Vec<(&'static str, String)>)> {
+    pub fn get_categorized_actions(&self) -> Vec<(&'static str, Vec<(&'static str, KeyList)>)> {
         vec![
             ("Navigation", vec![
                 ("up", self.keybindings.up.clone()),
@@ -198,24 +732,73 @@ impl Config {
                 ("ctrl_end", self.keybindings.ctrl_end.clone()),
                 ("page_up", self.keybindings.page_up.clone()),
                 ("page_down", self.keybindings.page_down.clone()),
+                ("add_bookmark", self.keybindings.add_bookmark.clone()),
+                ("bookmarks", self.keybindings.bookmarks.clone()),
+                ("recent_dirs", self.keybindings.recent_dirs.clone()),
+                ("go_back", self.keybindings.go_back.clone()),
+                ("go_forward", self.keybindings.go_forward.clone()),
+                ("breadcrumbs", self.keybindings.breadcrumbs.clone()),
+                ("go_to_path", self.keybindings.go_to_path.clone()),
+                ("reveal", self.keybindings.reveal.clone()),
+                ("jump_to_name", self.keybindings.jump_to_name.clone()),
+                ("new_tab", self.keybindings.new_tab.clone()),
+                ("close_tab", self.keybindings.close_tab.clone()),
+                ("next_tab", self.keybindings.next_tab.clone()),
+                ("prev_tab", self.keybindings.prev_tab.clone()),
+            ]),
+            ("View", vec![
+                ("toggle_hidden", self.keybindings.toggle_hidden.clone()),
+                ("filter", self.keybindings.filter.clone()),
+                ("cycle_sort", self.keybindings.cycle_sort.clone()),
+                ("reverse_sort", self.keybindings.reverse_sort.clone()),
+                ("toggle_dirs_first", self.keybindings.toggle_dirs_first.clone()),
+                ("grow_list", self.keybindings.grow_list.clone()),
+                ("shrink_list", self.keybindings.shrink_list.clone()),
+                ("calc_size", self.keybindings.calc_size.clone()),
+                ("toggle_dual_pane", self.keybindings.toggle_dual_pane.clone()),
+                ("toggle_details", self.keybindings.toggle_details.clone()),
+                ("switch_pane", self.keybindings.switch_pane.clone()),
             ]),
             ("File Operations", vec![
                 ("select", self.keybindings.select.clone()),
+                ("visual_select", self.keybindings.visual_select.clone()),
                 ("select_all", self.keybindings.select_all.clone()),
                 ("deselect_all", self.keybindings.deselect_all.clone()),
+                ("invert_selection", self.keybindings.invert_selection.clone()),
                 ("copy", self.keybindings.copy.clone()),
                 ("cut", self.keybindings.cut.clone()),
+                ("copy_link", self.keybindings.copy_link.clone()),
+                ("copy_path", self.keybindings.copy_path.clone()),
                 ("paste", self.keybindings.paste.clone()),
                 ("new_folder", self.keybindings.new_folder.clone()),
+                ("new_file", self.keybindings.new_file.clone()),
                 ("delete", self.keybindings.delete.clone()),
+                ("delete_permanent", self.keybindings.delete_permanent.clone()),
+                ("rename", self.keybindings.rename.clone()),
+                ("undo", self.keybindings.undo.clone()),
+                ("permissions", self.keybindings.permissions.clone()),
+                ("symlink", self.keybindings.symlink.clone()),
+                ("archive", self.keybindings.archive.clone()),
+                ("extract", self.keybindings.extract.clone()),
                 ("edit", self.keybindings.edit.clone()),
+                ("edit_dir_description", self.keybindings.edit_dir_description.clone()),
+                ("edit_external", self.keybindings.edit_external.clone()),
+                ("clear_description", self.keybindings.clear_description.clone()),
+                ("export_descriptions", self.keybindings.export_descriptions.clone()),
+                ("import_descriptions", self.keybindings.import_descriptions.clone()),
+                ("open_with", self.keybindings.open_with.clone()),
+                ("shell_command", self.keybindings.shell_command.clone()),
             ]),
             ("Terminal", vec![
                 ("toggle_terminal", self.keybindings.toggle_terminal.clone()),
                 ("terminal_prefix", self.keybindings.terminal_prefix.clone()),
+                ("open_shell", self.keybindings.open_shell.clone()),
             ]),
             ("Search", vec![
                 ("search", self.keybindings.search.clone()),
+                ("cycle_search_mode", self.keybindings.cycle_search_mode.clone()),
+                ("toggle_content_search", self.keybindings.toggle_content_search.clone()),
+                ("toggle_search_scope", self.keybindings.toggle_search_scope.clone()),
             ]),
         ]
     }
@@ -224,6 +807,11 @@ impl Config {
         match action {
             "quit" => format!("[{}] Quit", self.keybindings.quit),
             "edit" => format!("[{}] Edit", self.keybindings.edit),
+            "edit_dir_description" => format!("[{}] Describe Folder", self.keybindings.edit_dir_description),
+            "edit_external" => format!("[{}] Edit in $EDITOR", self.keybindings.edit_external),
+            "clear_description" => format!("[{}] Clear Description", self.keybindings.clear_description),
+            "export_descriptions" => format!("[{}] Export Descriptions", self.keybindings.export_descriptions),
+            "import_descriptions" => format!("[{}] Import Descriptions", self.keybindings.import_descriptions),
             "up" => format!("[{}] Up", self.keybindings.up),
             "down" => format!("[{}] Down", self.keybindings.down),
             "enter" => format!("[{}] Open", self.keybindings.enter),
@@ -231,11 +819,51 @@ impl Config {
             "settings" => format!("[{}] Settings", self.keybindings.settings),
             "search" => format!("[{}] Search", self.keybindings.search),
             "select" => format!("[{}] Select", self.keybindings.select),
+            "visual_select" => format!("[{}] Select Range", self.keybindings.visual_select),
             "copy" => format!("[{}] Copy", self.keybindings.copy),
             "cut" => format!("[{}] Cut", self.keybindings.cut),
+            "copy_link" => format!("[{}] Copy as Hard Link", self.keybindings.copy_link),
             "paste" => format!("[{}] Paste", self.keybindings.paste),
             "new_folder" => format!("[{}] New Folder", self.keybindings.new_folder),
+            "new_file" => format!("[{}] New File", self.keybindings.new_file),
+            "add_bookmark" => format!("[{}] Bookmark", self.keybindings.add_bookmark),
+            "bookmarks" => format!("[{}] Bookmarks", self.keybindings.bookmarks),
+            "recent_dirs" => format!("[{}] Recent Folders", self.keybindings.recent_dirs),
+            "go_back" => format!("[{}] Back", self.keybindings.go_back),
+            "go_forward" => format!("[{}] Forward", self.keybindings.go_forward),
+            "new_tab" => format!("[{}] New Tab", self.keybindings.new_tab),
+            "close_tab" => format!("[{}] Close Tab", self.keybindings.close_tab),
+            "next_tab" => format!("[{}] Next Tab", self.keybindings.next_tab),
+            "prev_tab" => format!("[{}] Previous Tab", self.keybindings.prev_tab),
+            "cycle_search_mode" => format!("[{}] Search Mode", self.keybindings.cycle_search_mode),
+            "toggle_content_search" => format!("[{}] Contents", self.keybindings.toggle_content_search),
+            "toggle_search_scope" => format!("[{}] Scope", self.keybindings.toggle_search_scope),
             "delete" => format!("[{}] Delete", self.keybindings.delete),
+            "rename" => format!("[{}] Rename", self.keybindings.rename),
+            "undo" => format!("[{}] Undo", self.keybindings.undo),
+            "permissions" => format!("[{}] Permissions", self.keybindings.permissions),
+            "symlink" => format!("[{}] Symlink", self.keybindings.symlink),
+            "archive" => format!("[{}] Zip", self.keybindings.archive),
+            "extract" => format!("[{}] Extract", self.keybindings.extract),
+            "delete_permanent" => format!("[{}] Delete Permanently", self.keybindings.delete_permanent),
+            "toggle_hidden" => format!("[{}] Hidden", self.keybindings.toggle_hidden),
+            "filter" => format!("[{}] Filter", self.keybindings.filter),
+            "cycle_sort" => format!("[{}] Sort", self.keybindings.cycle_sort),
+            "reverse_sort" => format!("[{}] Reverse Sort", self.keybindings.reverse_sort),
+            "toggle_dirs_first" => format!("[{}] Dirs First", self.keybindings.toggle_dirs_first),
+            "grow_list" => format!("[{}] Wider List", self.keybindings.grow_list),
+            "shrink_list" => format!("[{}] Narrower List", self.keybindings.shrink_list),
+            "calc_size" => format!("[{}] Calculate Size", self.keybindings.calc_size),
+            "toggle_dual_pane" => format!("[{}] Dual Pane", self.keybindings.toggle_dual_pane),
+            "toggle_details" => format!("[{}] Details", self.keybindings.toggle_details),
+            "switch_pane" => format!("[{}] Switch Pane", self.keybindings.switch_pane),
+            "open_with" => format!("[{}] Open With", self.keybindings.open_with),
+            "shell_command" => format!("[{}] Shell Command", self.keybindings.shell_command),
+            "copy_path" => format!("[{}] Copy Path", self.keybindings.copy_path),
+            "breadcrumbs" => format!("[{}] Jump Up", self.keybindings.breadcrumbs),
+            "go_to_path" => format!("[{}] Go To Path", self.keybindings.go_to_path),
+            "reveal" => format!("[{}] Reveal in Folder", self.keybindings.reveal),
+            "jump_to_name" => format!("[{}] Jump to Name", self.keybindings.jump_to_name),
             "help" => format!("[{}] Help", self.keybindings.help),
             "home" => format!("[{}] Visible Top", self.keybindings.home),
             "end" => format!("[{}] Visible Bottom", self.keybindings.end),
@@ -245,12 +873,55 @@ impl Config {
             "page_down" => format!("[{}] Page Down", self.keybindings.page_down),
             "select_all" => format!("[{}] Select All", self.keybindings.select_all),
             "deselect_all" => format!("[{}] Deselect All", self.keybindings.deselect_all),
+            "invert_selection" => format!("[{}] Invert Selection", self.keybindings.invert_selection),
             "toggle_terminal" => format!("[{}] Terminal", self.keybindings.toggle_terminal),
+            "open_shell" => format!("[{}] Shell", self.keybindings.open_shell),
             _ => String::new(),
         }
     }
 }
 
+/// Keys the file list reacts to directly, whatever they are bound to.
+const NAVIGATION_KEYS: [&str; 8] = ["up", "down", "enter", "backspace", "home", "end", "pageup", "pagedown"];
+/// Actions those keys belong to, which may keep them.
+const NAVIGATION_KEYS_ACTIONS: [&str; 8] = ["up", "down", "enter", "backspace", "home", "end", "page_up", "page_down"];
+
+/// Key names key_event_to_string produces besides single characters and function keys.
+const NAMED_KEYS: [&str; 13] = [
+    "space", "enter", "tab", "backspace", "up", "down", "left", "right",
+    "delete", "home", "end", "pageup", "pagedown",
+];
+
+/// Whether `key` is a string key_event_to_string can produce, i.e. a binding that can ever match,
+/// or a sequence of two such keys separated by a space like "g g".
+pub fn is_valid_key(key: &str) -> bool {
+    match key.split_once(' ') {
+        Some((first, second)) => is_valid_single_key(first) && is_valid_single_key(second),
+        None => is_valid_single_key(key),
+    }
+}
+
+/// Esc is excluded since it cancels remapping and closes every popup.
+fn is_valid_single_key(key: &str) -> bool {
+    let mut rest = key;
+    let mut shift = false;
+    for modifier in ["ctrl+", "alt+", "shift+"] {
+        if let Some(stripped) = rest.strip_prefix(modifier) {
+            rest = stripped;
+            shift = modifier == "shift+";
+        }
+    }
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next()) {
+        // Shift is folded into symbols and letters are stored lowercase
+        (Some(c), None) => !c.is_control() && c != ' ' && !c.is_uppercase() && (!shift || c.is_alphabetic()),
+        _ => {
+            NAMED_KEYS.contains(&rest)
+                || rest.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()).is_some_and(|n| (1..=24).contains(&n))
+        }
+    }
+}
+
 pub fn key_event_to_string(code: crossterm::event::KeyCode, modifiers: crossterm::event::KeyModifiers) -> String {
     let mut parts = Vec::new();
     if modifiers.contains(crossterm::event::KeyModifiers::CONTROL) {
@@ -259,7 +930,9 @@ pub fn key_event_to_string(code: crossterm::event::KeyCode, modifiers: crossterm
     if modifiers.contains(crossterm::event::KeyModifiers::ALT) {
         parts.push("alt");
     }
-    if modifiers.contains(crossterm::event::KeyModifiers::SHIFT) {
+    // Shift is already part of a symbol like ':' or '!', and some terminals report it anyway
+    let shifted_symbol = matches!(code, crossterm::event::KeyCode::Char(c) if !c.is_alphabetic() && c != ' ');
+    if modifiers.contains(crossterm::event::KeyModifiers::SHIFT) && !shifted_symbol {
         parts.push("shift");
     }
 
@@ -272,6 +945,7 @@ pub fn key_event_to_string(code: crossterm::event::KeyCode, modifiers: crossterm
             }
         },
         crossterm::event::KeyCode::Enter => "enter".to_string(),
+        crossterm::event::KeyCode::Tab => "tab".to_string(),
         crossterm::event::KeyCode::Backspace => "backspace".to_string(),
         crossterm::event::KeyCode::Up => "up".to_string(),
         crossterm::event::KeyCode::Down => "down".to_string(),
@@ -294,3 +968,81 @@ pub fn key_event_to_string(code: crossterm::event::KeyCode, modifiers: crossterm
     parts.push(&code_str);
     parts.join("+")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_key() {
+        for key in ["q", "ctrl+c", "shift+o", "alt+left", "ctrl+alt+shift+delete", "f12", ":", "space", "g g", "z ctrl+a"] {
+            assert!(is_valid_key(key), "{key}");
+        }
+        for key in ["", "esc", "Q", "shift+:", "f0", "f25", "ctrl+", "meta+a", "shift+ctrl+a", "pgup", " g", "g g g", "g esc"] {
+            assert!(!is_valid_key(key), "{key}");
+        }
+    }
+
+    #[test]
+    fn test_set_key_warns_about_navigation_keys() {
+        let mut config = Config::default();
+        assert!(config.set_key("edit", "".to_string()).is_err());
+        assert!(config.set_key("edit", "q".to_string()).is_err());
+        assert_eq!(config.set_key("edit", "f5".to_string()), Ok(None));
+        assert!(config.set_key("edit", "home".to_string()).is_err());
+        config.keybindings.home = "ctrl+h".into();
+        assert!(config.set_key("edit", "home".to_string()).unwrap().is_some());
+        assert_eq!(config.set_key("page_up", "pageup".to_string()), Ok(None));
+    }
+
+    #[test]
+    fn test_key_lists() {
+        let config: Config = toml::from_str("[keybindings]\nquit = \"x\"\ndown = [\"j\", \"down\", \"ctrl+n\"]\n").unwrap();
+        assert_eq!(config.keybindings.quit, KeyList::new(&["x"]));
+        assert!("ctrl+n".to_string() == config.keybindings.down);
+        assert_eq!(config.keybindings.down.to_string(), "j/down/ctrl+n");
+
+        let mut config = Config::default();
+        assert!(config.add_key("up", "ctrl+p".to_string()).is_ok());
+        assert!(config.add_key("up", "ctrl+p".to_string()).is_err());
+        assert!(config.add_key("up", "q".to_string()).is_err());
+        config.remove_key("up").unwrap();
+        config.remove_key("up").unwrap();
+        assert_eq!(config.keybindings.up, KeyList::new(&["k"]));
+        assert!(config.remove_key("up").is_err());
+
+        // Old configs get the keys that used to be hard-wired back
+        config.restore_navigation_keys();
+        assert_eq!(config.keybindings.up, KeyList::new(&["k", "up"]));
+    }
+
+    #[test]
+    fn test_resize_list() {
+        let mut config = Config::default();
+        config.resize_list(10);
+        assert_eq!(config.list_width, 60);
+        config.resize_list(100);
+        assert_eq!(config.list_width, 80);
+        config.list_width = 5;
+        config.resize_list(0);
+        assert_eq!(config.list_width, 20);
+    }
+
+    #[test]
+    fn test_ticks_per() {
+        let mut config = Config::default();
+        assert_eq!(config.ticks_per(Duration::from_secs(1)), 10);
+        config.tick_rate_ms = 250;
+        assert_eq!(config.ticks_per(Duration::from_millis(500)), 2);
+        // A period shorter than a tick still happens every tick
+        assert_eq!(config.ticks_per(Duration::from_millis(100)), 1);
+    }
+
+    #[test]
+    fn test_is_valid_date_format() {
+        assert!(is_valid_date_format(DEFAULT_DATE_FORMAT));
+        assert!(is_valid_date_format("%d.%m.%Y %I:%M %p"));
+        assert!(!is_valid_date_format("%Y-%m-%"));
+        assert!(!is_valid_date_format("%Q"));
+    }
+}
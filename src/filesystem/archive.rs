@@ -0,0 +1,275 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl Format {
+    /// Recognize an archive by its extension.
+    pub fn detect(path: &Path) -> Option<Format> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Format::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Format::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Format::Tar)
+        } else {
+            None
+        }
+    }
+
+    /// `name` without the archive extension, e.g. "photos" for "photos.tar.gz".
+    pub fn strip_extension(self, name: &str) -> String {
+        let lower = name.to_lowercase();
+        let extensions: &[&str] = match self {
+            Format::Zip => &[".zip"],
+            Format::Tar => &[".tar"],
+            Format::TarGz => &[".tar.gz", ".tgz"],
+        };
+        extensions.iter()
+            .find(|ext| lower.ends_with(*ext) && lower.len() > ext.len())
+            .map(|ext| name[..name.len() - ext.len()].to_string())
+            .unwrap_or_else(|| name.to_string())
+    }
+}
+
+/// Write a zip of `paths` to `file`, naming entries relative to each path's parent.
+pub fn write_zip(paths: &[PathBuf], dst: &Path, file: fs::File, on_file: &mut dyn FnMut(&Path) -> bool) -> io::Result<()> {
+    use zip::write::SimpleFileOptions;
+
+    let mut zip = zip::ZipWriter::new(io::BufWriter::new(file));
+    for path in paths {
+        let base = path.parent().unwrap_or(Path::new(""));
+        for entry in walkdir::WalkDir::new(path).follow_links(false) {
+            let entry = entry?;
+            // Archiving a folder into itself would pick up the archive being written
+            if entry.path() == dst {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(base).unwrap_or(entry.path());
+            let name = relative.components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let metadata = entry.metadata()?;
+            let mut options = SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .large_file(metadata.len() > u32::MAX as u64);
+            // Zip timestamps only cover 1980 to 2107
+            if let Some(time) = metadata.modified().ok()
+                .and_then(|t| DateTime::<Local>::from(t).naive_local().try_into().ok())
+            {
+                options = options.last_modified_time(time);
+            }
+            #[cfg(unix)]
+            {
+                options = options.unix_permissions(metadata.permissions().mode() & 0o777);
+            }
+
+            if entry.file_type().is_dir() {
+                zip.add_directory(name, options)?;
+                continue;
+            }
+            if !on_file(entry.path()) {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "Archive cancelled"));
+            }
+            if entry.file_type().is_symlink() {
+                zip.add_symlink(name, fs::read_link(entry.path())?.to_string_lossy(), options)?;
+            } else {
+                zip.start_file(name, options)?;
+                io::copy(&mut fs::File::open(entry.path())?, &mut zip)?;
+            }
+        }
+    }
+    zip.finish()?.flush()
+}
+
+/// Number of files and their total size in an archive, for progress reporting.
+pub fn contents_size(path: &Path, format: Format) -> io::Result<(u64, u64)> {
+    let file = fs::File::open(path)?;
+    match format {
+        Format::Zip => {
+            let mut archive = zip::ZipArchive::new(file)?;
+            let (mut files, mut bytes) = (0, 0);
+            for i in 0..archive.len() {
+                let entry = archive.by_index_raw(i)?;
+                if !entry.is_dir() {
+                    files += 1;
+                    bytes += entry.size();
+                }
+            }
+            Ok((files, bytes))
+        }
+        Format::Tar => tar_contents_size(file),
+        Format::TarGz => tar_contents_size(flate2::read::GzDecoder::new(file)),
+    }
+}
+
+fn tar_contents_size(reader: impl Read) -> io::Result<(u64, u64)> {
+    let mut archive = tar::Archive::new(reader);
+    let (mut files, mut bytes) = (0, 0);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_dir() {
+            files += 1;
+            bytes += entry.size();
+        }
+    }
+    Ok((files, bytes))
+}
+
+/// Unpack an archive into the existing directory `dst`, returning how many entries were
+/// skipped because they would have landed outside it. `on_file` gets each file's destination
+/// and size before it is written and can return false to stop.
+pub fn extract(path: &Path, format: Format, dst: &Path, on_file: &mut dyn FnMut(&Path, u64) -> bool) -> io::Result<usize> {
+    let root = dst.canonicalize()?;
+    let file = fs::File::open(path)?;
+    match format {
+        Format::Zip => extract_zip(file, &root, on_file),
+        Format::Tar => extract_tar(file, &root, on_file),
+        Format::TarGz => extract_tar(flate2::read::GzDecoder::new(file), &root, on_file),
+    }
+}
+
+fn cancelled() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "Extraction cancelled")
+}
+
+/// Whether `path` resolves to somewhere below `root` once the part that already exists,
+/// including any symlinks extracted earlier, is followed.
+fn stays_inside(root: &Path, path: &Path) -> bool {
+    let mut existing = path;
+    while existing.symlink_metadata().is_err() {
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => return false,
+        }
+    }
+    existing.canonicalize().is_ok_and(|resolved| resolved.starts_with(root))
+}
+
+fn extract_zip(file: fs::File, root: &Path, on_file: &mut dyn FnMut(&Path, u64) -> bool) -> io::Result<usize> {
+    let mut archive = zip::ZipArchive::new(file)?;
+    let mut skipped = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        // enclosed_name rejects absolute names and ones that climb out with ".."
+        let Some(relative) = entry.enclosed_name() else {
+            skipped += 1;
+            continue;
+        };
+        let target = root.join(relative);
+        let Some(parent) = target.parent() else { continue };
+        if !stays_inside(root, parent) {
+            skipped += 1;
+            continue;
+        }
+        if entry.is_dir() {
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+        if !on_file(&target, entry.size()) {
+            return Err(cancelled());
+        }
+        fs::create_dir_all(parent)?;
+
+        if entry.is_symlink() {
+            let mut link = String::new();
+            entry.read_to_string(&mut link)?;
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(link, &target)?;
+            #[cfg(not(unix))]
+            {
+                skipped += 1;
+            }
+            continue;
+        }
+        // create_new so an entry can't overwrite, or write through, something already there
+        let mut out = fs::OpenOptions::new().write(true).create_new(true).open(&target)?;
+        io::copy(&mut entry, &mut out)?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            fs::set_permissions(&target, fs::Permissions::from_mode(mode & 0o777))?;
+        }
+    }
+    Ok(skipped)
+}
+
+fn extract_tar(reader: impl Read, root: &Path, on_file: &mut dyn FnMut(&Path, u64) -> bool) -> io::Result<usize> {
+    let mut archive = tar::Archive::new(reader);
+    let mut skipped = 0;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_dir() && !on_file(&root.join(entry.path()?), entry.size()) {
+            return Err(cancelled());
+        }
+        // unpack_in does the same containment checks and reports entries it refused
+        if !entry.unpack_in(root)? {
+            skipped += 1;
+        }
+    }
+    Ok(skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use zip::write::SimpleFileOptions;
+
+    #[test]
+    fn test_detect_and_strip_extension() {
+        assert_eq!(Format::detect(Path::new("a/Photos.ZIP")), Some(Format::Zip));
+        assert_eq!(Format::detect(Path::new("src.tgz")), Some(Format::TarGz));
+        assert_eq!(Format::detect(Path::new("notes.txt")), None);
+        assert_eq!(Format::TarGz.strip_extension("backup.tar.gz"), "backup");
+        assert_eq!(Format::Zip.strip_extension(".zip"), ".zip");
+    }
+
+    #[test]
+    fn test_extract_skips_entries_outside_the_target() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let mut zip = zip::ZipWriter::new(fs::File::create(root.join("evil.zip")).unwrap());
+        for name in ["ok/inside.txt", "../outside.txt", "/etc/absolute.txt"] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(b"x").unwrap();
+        }
+        zip.finish().unwrap();
+
+        fs::create_dir(root.join("out")).unwrap();
+        let skipped = extract(&root.join("evil.zip"), Format::Zip, &root.join("out"), &mut |_, _| true).unwrap();
+        assert_eq!(skipped, 1);
+        assert!(root.join("out/ok/inside.txt").exists());
+        assert!(!root.join("outside.txt").exists());
+        // Absolute names are taken relative to the target
+        assert!(root.join("out/etc/absolute.txt").exists());
+    }
+
+    #[test]
+    fn test_extract_tar_gz() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/sub")).unwrap();
+        fs::write(root.join("src/sub/a.txt"), "hello").unwrap();
+        let gz = flate2::write::GzEncoder::new(fs::File::create(root.join("src.tar.gz")).unwrap(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(gz);
+        builder.append_dir_all("src", root.join("src")).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let path = root.join("src.tar.gz");
+        assert_eq!(contents_size(&path, Format::TarGz).unwrap(), (1, 5));
+        fs::create_dir(root.join("out")).unwrap();
+        assert_eq!(extract(&path, Format::TarGz, &root.join("out"), &mut |_, _| true).unwrap(), 0);
+        assert_eq!(fs::read_to_string(root.join("out/src/sub/a.txt")).unwrap(), "hello");
+    }
+}
@@ -0,0 +1,169 @@
+use std::fs;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+fn is_separator(c: char) -> bool {
+    c == '/' || c == MAIN_SEPARATOR
+}
+
+/// Turn typed path input into a path: a leading `~` becomes the home directory and
+/// relative input is taken relative to `base`.
+pub fn resolve_input(base: &Path, input: &str) -> PathBuf {
+    if input == "~" {
+        return dirs::home_dir().unwrap_or_else(|| base.to_path_buf());
+    }
+    if let Some(rest) = input.strip_prefix('~')
+        && rest.starts_with(is_separator)
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(rest.trim_start_matches(is_separator));
+    }
+    base.join(input)
+}
+
+/// Complete the last component of `input` against the entries of the directory it names,
+/// ignoring case. Each candidate is the whole input with that component filled in; directories
+/// end with a separator so completion can carry on into them. Dotfiles only show up once the
+/// typed component starts with a dot.
+pub fn complete_path(base: &Path, input: &str, dirs_only: bool) -> Vec<String> {
+    if input == "~" {
+        return vec![format!("~{}", MAIN_SEPARATOR)];
+    }
+    let split = input.rfind(is_separator).map(|i| i + 1).unwrap_or(0);
+    let (dir_part, prefix) = input.split_at(split);
+    let prefix_lower = prefix.to_lowercase();
+    let dir = if dir_part.is_empty() { base.to_path_buf() } else { resolve_input(base, dir_part) };
+
+    let Ok(read_dir) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<String> = read_dir
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.to_lowercase().starts_with(&prefix_lower) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            // Follow symlinks so a link to a directory completes like one
+            let is_dir = entry.path().is_dir();
+            if dirs_only && !is_dir {
+                return None;
+            }
+            let suffix = if is_dir { MAIN_SEPARATOR.to_string() } else { String::new() };
+            Some(format!("{}{}{}", dir_part, name, suffix))
+        })
+        .collect();
+    candidates.sort_by_key(|c| c.to_lowercase());
+    candidates
+}
+
+/// The longest prefix shared by every candidate, ignoring case, spelled as in the first one.
+pub fn longest_common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+    let mut len = first.len();
+    for candidate in &candidates[1..] {
+        len = first.char_indices()
+            .zip(candidate.chars())
+            .take_while(|((_, a), b)| a.to_lowercase().eq(b.to_lowercase()))
+            .last()
+            .map(|((i, a), _)| i + a.len_utf8())
+            .unwrap_or(0)
+            .min(len);
+    }
+    first[..len].to_string()
+}
+
+/// Tab-completion state for a prompt. The first Tab inserts the longest common prefix of the
+/// matches; once that adds nothing, further Tabs cycle through the matches themselves.
+#[derive(Default)]
+pub struct Completion {
+    candidates: Vec<String>,
+    index: Option<usize>,
+    /// What the last Tab put in the buffer; any other edit starts a fresh completion
+    applied: String,
+}
+
+impl Completion {
+    /// Handle a Tab on `input`, returning the new buffer contents if there is anything to insert.
+    pub fn complete(&mut self, base: &Path, input: &str, dirs_only: bool) -> Option<String> {
+        // Callers may strip the trailing separator from what was applied, so ignore it here
+        let continues = input.trim_end_matches(is_separator) == self.applied.trim_end_matches(is_separator);
+        if self.candidates.is_empty() || !continues {
+            self.candidates = complete_path(base, input, dirs_only);
+            self.index = None;
+            match self.candidates.len() {
+                0 => return None,
+                1 => {
+                    // Nothing to cycle through; the next Tab completes inside the result
+                    let only = self.candidates.remove(0);
+                    self.applied.clear();
+                    return Some(only);
+                }
+                _ => {}
+            }
+            let prefix = longest_common_prefix(&self.candidates);
+            if prefix.chars().count() > input.chars().count() {
+                self.applied = prefix.clone();
+                return Some(prefix);
+            }
+        }
+        let next = self.index.map_or(0, |i| (i + 1) % self.candidates.len());
+        self.index = Some(next);
+        self.applied = self.candidates[next].clone();
+        Some(self.applied.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_complete_path() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("projects/xplore")).unwrap();
+        fs::create_dir(root.join("pictures")).unwrap();
+        fs::create_dir(root.join(".private")).unwrap();
+        File::create(root.join("plan.txt")).unwrap();
+
+        let sep = MAIN_SEPARATOR;
+        let dirs = complete_path(root, "p", true);
+        assert_eq!(dirs, vec![format!("pictures{sep}"), format!("projects{sep}")]);
+        assert_eq!(longest_common_prefix(&dirs), "p");
+
+        let everything = complete_path(root, "p", false);
+        assert!(everything.contains(&"plan.txt".to_string()));
+        assert_eq!(complete_path(root, "projects/x", true), vec![format!("projects/xplore{sep}")]);
+        assert_eq!(complete_path(root, ".p", true), vec![format!(".private{sep}")]);
+        assert_eq!(longest_common_prefix(&["Projects".to_string(), "project".to_string()]), "Project");
+        assert_eq!(complete_path(root, "PIC", true), vec![format!("pictures{sep}")]);
+    }
+
+    #[test]
+    fn test_completion_cycles() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for name in ["report-2023", "report-2024", "notes"] {
+            fs::create_dir(root.join(name)).unwrap();
+        }
+
+        let sep = MAIN_SEPARATOR;
+        let mut completion = Completion::default();
+        let buffer = completion.complete(root, "rep", true).unwrap();
+        assert_eq!(buffer, "report-202");
+        let buffer = completion.complete(root, &buffer, true).unwrap();
+        assert_eq!(buffer, format!("report-2023{sep}"));
+        let buffer = completion.complete(root, &buffer, true).unwrap();
+        assert_eq!(buffer, format!("report-2024{sep}"));
+        let buffer = completion.complete(root, &buffer, true).unwrap();
+        assert_eq!(buffer, format!("report-2023{sep}"));
+
+        // Editing the buffer starts over
+        assert_eq!(completion.complete(root, "n", true).unwrap(), format!("notes{sep}"));
+        assert_eq!(completion.complete(root, "x", true), None);
+    }
+}
@@ -1,5 +1,61 @@
 use chrono::{DateTime, Local};
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+
+/// What kind of filesystem object an entry is. For a symlink this describes what it points
+/// to; only a dangling link is a Symlink itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    File,
+    Directory,
+    Symlink,
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+    Other,
+}
+
+impl FileKind {
+    pub fn from_file_type(file_type: fs::FileType) -> Self {
+        #[cfg(unix)]
+        {
+            if file_type.is_fifo() {
+                return FileKind::Fifo;
+            } else if file_type.is_socket() {
+                return FileKind::Socket;
+            } else if file_type.is_block_device() {
+                return FileKind::BlockDevice;
+            } else if file_type.is_char_device() {
+                return FileKind::CharDevice;
+            }
+        }
+        if file_type.is_symlink() {
+            FileKind::Symlink
+        } else if file_type.is_dir() {
+            FileKind::Directory
+        } else if file_type.is_file() {
+            FileKind::File
+        } else {
+            FileKind::Other
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FileKind::File => "Regular file",
+            FileKind::Directory => "Directory",
+            FileKind::Symlink => "Symlink",
+            FileKind::Fifo => "Named pipe (FIFO)",
+            FileKind::Socket => "Socket",
+            FileKind::BlockDevice => "Block device",
+            FileKind::CharDevice => "Character device",
+            FileKind::Other => "Other",
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct FileEntry {
@@ -7,12 +63,16 @@ pub struct FileEntry {
     pub path: PathBuf,
     pub size: u64,
     pub is_dir: bool,
+    pub kind: FileKind,
     pub mod_time: DateTime<Local>,
     pub description: Option<String>,
     pub permissions: String,
     pub owner: String,
     pub group: String,
     pub inode: u64,
+    pub is_symlink: bool,
+    /// Where a symlink points, as stored in the link (may be relative or dangling)
+    pub link_target: Option<PathBuf>,
 }
 
 impl FileEntry {
@@ -20,18 +80,106 @@ impl FileEntry {
         if self.is_dir {
             return "---".to_string();
         }
-        let size = self.size as f64;
-        let units = ["B", "KB", "MB", "GB", "TB", "PB"];
-        let mut i = 0;
-        let mut val = size;
-        while val >= 1024.0 && i < units.len() - 1 {
-            val /= 1024.0;
-            i += 1;
-        }
-        if i == 0 {
-            format!("{} {}", val as u64, units[i])
-        } else {
-            format!("{:.2} {}", val, units[i])
+        format_size(self.size)
+    }
+
+    /// How long ago the entry was modified, e.g. "3 hours ago".
+    pub fn relative_mod_time(&self) -> String {
+        format_relative(self.mod_time, Local::now())
+    }
+
+    /// The first non-blank line of the description, with "…" when more lines follow.
+    pub fn description_summary(&self) -> Option<String> {
+        self.description.as_deref().and_then(summarize)
+    }
+}
+
+fn summarize(description: &str) -> Option<String> {
+    let mut lines = description.lines().map(str::trim).filter(|line| !line.is_empty());
+    let first = lines.next()?;
+    Some(match lines.next() {
+        Some(_) => format!("{} …", first),
+        None => first.to_string(),
+    })
+}
+
+/// `time` relative to `now` in the largest whole unit, e.g. "2 days ago". Times in the
+/// future, from clock skew or a copied mtime, read "in the future".
+pub fn format_relative(time: DateTime<Local>, now: DateTime<Local>) -> String {
+    let seconds = (now - time).num_seconds();
+    if seconds < 0 {
+        return "in the future".to_string();
+    }
+    let units = [
+        (365 * 24 * 3600, "year"),
+        (30 * 24 * 3600, "month"),
+        (7 * 24 * 3600, "week"),
+        (24 * 3600, "day"),
+        (3600, "hour"),
+        (60, "minute"),
+    ];
+    for (length, unit) in units {
+        let count = seconds / length;
+        if count > 0 {
+            return format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" });
         }
     }
+    "just now".to_string()
+}
+
+/// `path` with the `home` folder at its start written as "~", for display only.
+pub fn abbreviate_home(path: &Path, home: &Path) -> String {
+    match path.strip_prefix(home) {
+        Ok(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Ok(rest) => format!("~{}{}", std::path::MAIN_SEPARATOR, rest.display()),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+/// Format a byte count using binary units (B, KB, MB, ...).
+pub fn format_size(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB", "PB"];
+    let mut i = 0;
+    let mut val = bytes as f64;
+    while val >= 1024.0 && i < units.len() - 1 {
+        val /= 1024.0;
+        i += 1;
+    }
+    if i == 0 {
+        format!("{} {}", val as u64, units[i])
+    } else {
+        format!("{:.2} {}", val, units[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_format_relative() {
+        let now = Local::now();
+        assert_eq!(format_relative(now - Duration::seconds(20), now), "just now");
+        assert_eq!(format_relative(now - Duration::minutes(1), now), "1 minute ago");
+        assert_eq!(format_relative(now - Duration::hours(3), now), "3 hours ago");
+        assert_eq!(format_relative(now - Duration::days(9), now), "1 week ago");
+        assert_eq!(format_relative(now - Duration::days(800), now), "2 years ago");
+        assert_eq!(format_relative(now + Duration::hours(1), now), "in the future");
+    }
+
+    #[test]
+    fn test_abbreviate_home() {
+        let home = Path::new("/home/me");
+        assert_eq!(abbreviate_home(home, home), "~");
+        assert_eq!(abbreviate_home(Path::new("/home/me/src/app"), home), format!("~{}src/app", std::path::MAIN_SEPARATOR));
+        assert_eq!(abbreviate_home(Path::new("/home/meadow"), home), "/home/meadow");
+    }
+
+    #[test]
+    fn test_summarize() {
+        assert_eq!(summarize("one line").as_deref(), Some("one line"));
+        assert_eq!(summarize("\n  Title  \n\nbody\n").as_deref(), Some("Title …"));
+        assert_eq!(summarize(" \n\n"), None);
+    }
 }
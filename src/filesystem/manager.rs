@@ -1,20 +1,35 @@
+use std::ffi::OsStr;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use chrono::{DateTime, Local};
-use crate::filesystem::FileEntry;
+use crate::filesystem::archive;
+use crate::filesystem::search::find_content_match;
+use crate::filesystem::{sort_entries, ContentMatch, FileEntry, FileKind, Matcher, SortKey, SortOrder};
 use crate::metadata;
 #[cfg(unix)]
+use crate::filesystem::owners;
+#[cfg(unix)]
 use std::os::unix::fs::{MetadataExt, PermissionsExt};
 
+#[derive(Clone)]
 pub struct FileSystemManager {
     current_dir: PathBuf,
+    back_stack: Vec<PathBuf>,
+    forward_stack: Vec<PathBuf>,
 }
 
 impl FileSystemManager {
+    /// Start in `path`, or in the process's working directory if it can't be resolved. The
+    /// path is always absolute so the parent chain ends at a real root.
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
-        let abs_path = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from("."));
+        let abs_path = Self::canonicalize(path)
+            .or_else(|_| std::env::current_dir().and_then(Self::canonicalize))
+            .unwrap_or_else(|_| PathBuf::from(std::path::MAIN_SEPARATOR_STR));
         Self {
             current_dir: abs_path,
+            back_stack: Vec::new(),
+            forward_stack: Vec::new(),
         }
     }
 
@@ -22,108 +37,231 @@ impl FileSystemManager {
         &self.current_dir
     }
 
+    /// fs::canonicalize, except that on Windows drive paths come back as "C:\..." rather than
+    /// the verbatim "\\?\C:\..." form, which the breadcrumbs and path prompts can't show nicely.
+    pub fn canonicalize<P: AsRef<Path>>(path: P) -> std::io::Result<PathBuf> {
+        let path = fs::canonicalize(path)?;
+        #[cfg(windows)]
+        if let Some(Component::Prefix(prefix)) = path.components().next()
+            && let std::path::Prefix::VerbatimDisk(_) = prefix.kind()
+            && let Some(simple) = path.to_str().and_then(|s| s.strip_prefix(r"\\?\"))
+        {
+            return Ok(PathBuf::from(simple));
+        }
+        Ok(path)
+    }
+
+    /// Whether this is the top of its filesystem tree ("/", "C:\" or a network share), where
+    /// there is no ".." row and going up does nothing.
+    pub fn is_at_root(&self) -> bool {
+        self.current_dir.parent().is_none()
+    }
+
     pub fn list_directory(&self) -> std::io::Result<Vec<FileEntry>> {
-        let mut entries = Vec::new();
+        let mut entries = self.special_entries()?;
+        for entry in fs::read_dir(&self.current_dir)? {
+            entries.push(Self::entry_for(entry?)?);
+        }
+
+        // Default order: "." first, then "..", then directories, then alphabetically.
+        // The UI re-sorts with the user's chosen key via sort_entries.
+        sort_entries(&mut entries, SortKey::Name, SortOrder::Asc, true);
+
+        Ok(entries)
+    }
 
+    /// Number of rows list_directory would return, counted without reading any metadata.
+    pub fn entry_count(&self) -> std::io::Result<usize> {
+        let special = if self.is_at_root() { 1 } else { 2 };
+        Ok(fs::read_dir(&self.current_dir)?.count() + special)
+    }
+
+    /// Like list_directory, but hands over unsorted entries `chunk_size` at a time so a huge
+    /// directory can be shown while it loads. Stops early once `on_chunk` returns false.
+    pub fn list_directory_chunked<F>(&self, chunk_size: usize, mut on_chunk: F) -> std::io::Result<()>
+    where
+        F: FnMut(Vec<FileEntry>) -> bool,
+    {
+        let mut chunk = self.special_entries()?;
+        for entry in fs::read_dir(&self.current_dir)? {
+            chunk.push(Self::entry_for(entry?)?);
+            if chunk.len() >= chunk_size && !on_chunk(std::mem::take(&mut chunk)) {
+                return Ok(());
+            }
+        }
+        if !chunk.is_empty() {
+            on_chunk(chunk);
+        }
+        Ok(())
+    }
+
+    /// The "." and ".." rows at the top of every listing ("..", unless at the root).
+    fn special_entries(&self) -> std::io::Result<Vec<FileEntry>> {
+        let mut entries = Vec::new();
         // Add "." entry
         let meta_dot = fs::metadata(&self.current_dir)?;
-        let (perm_dot, owner_dot, group_dot, inode_dot) = self.get_metadata_info(&meta_dot);
+        let (perm_dot, owner_dot, group_dot, inode_dot) = Self::get_metadata_info(&meta_dot);
         entries.push(FileEntry {
             name: ".".to_string(),
             path: self.current_dir.clone(),
             size: 0,
             is_dir: true,
+            kind: FileKind::Directory,
             mod_time: meta_dot.modified()?.into(),
-            description: None,
+            // The folder's own description, the same one its parent lists next to it
+            description: metadata::cached_description(&self.current_dir, &meta_dot),
             permissions: perm_dot,
             owner: owner_dot,
             group: group_dot,
             inode: inode_dot,
+            is_symlink: false,
+            link_target: None,
         });
 
         // Add ".." entry if not at root
         if let Some(parent) = self.current_dir.parent() {
             let meta_parent = fs::metadata(parent)?;
-            let (perm_p, owner_p, group_p, inode_p) = self.get_metadata_info(&meta_parent);
+            let (perm_p, owner_p, group_p, inode_p) = Self::get_metadata_info(&meta_parent);
             entries.push(FileEntry {
                 name: "..".to_string(),
                 path: parent.to_path_buf(),
                 size: 0,
                 is_dir: true,
+                kind: FileKind::Directory,
                 mod_time: meta_parent.modified()?.into(),
                 description: None,
                 permissions: perm_p,
                 owner: owner_p,
                 group: group_p,
                 inode: inode_p,
+                is_symlink: false,
+                link_target: None,
             });
         }
+        Ok(entries)
+    }
 
-        for entry in fs::read_dir(&self.current_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            let metadata = entry.metadata()?;
-            let is_dir = metadata.is_dir();
-            let size = metadata.len();
-            let mod_time: DateTime<Local> = metadata.modified()?.into();
-            let name = entry.file_name().to_string_lossy().to_string();
-            
-            let description = metadata::get_description(&path);
-            let (permissions, owner, group, inode) = self.get_metadata_info(&metadata);
-
-            entries.push(FileEntry {
-                name,
-                path,
-                size,
-                is_dir,
-                mod_time,
-                description,
-                permissions,
-                owner,
-                group,
-                inode,
-            });
-        }
+    /// Read everything the listing shows about one directory entry.
+    fn entry_for(entry: fs::DirEntry) -> std::io::Result<FileEntry> {
+        let path = entry.path();
+        let link_metadata = entry.metadata()?;
+        let is_symlink = link_metadata.file_type().is_symlink();
+        let link_target = if is_symlink { fs::read_link(&path).ok() } else { None };
+        // Follow links so a link to a directory can be entered; a broken link keeps its own metadata
+        let metadata = if is_symlink {
+            fs::metadata(&path).unwrap_or(link_metadata)
+        } else {
+            link_metadata
+        };
+        let is_dir = metadata.is_dir();
+        let kind = FileKind::from_file_type(metadata.file_type());
+        let size = metadata.len();
+        let mod_time: DateTime<Local> = metadata.modified()?.into();
+        let name = entry.file_name().to_string_lossy().to_string();
         
-        // Sort: "." first, then "..", then directories, then alphabetically
-        entries.sort_by(|a, b| {
-            if a.name == "." {
-                std::cmp::Ordering::Less
-            } else if b.name == "." {
-                std::cmp::Ordering::Greater
-            } else if a.name == ".." {
-                std::cmp::Ordering::Less
-            } else if b.name == ".." {
-                std::cmp::Ordering::Greater
-            } else if a.is_dir == b.is_dir {
-                a.name.to_lowercase().cmp(&b.name.to_lowercase())
-            } else {
-                b.is_dir.cmp(&a.is_dir)
-            }
-        });
+        let description = metadata::cached_description(&path, &metadata);
+        let (permissions, owner, group, inode) = Self::get_metadata_info(&metadata);
 
-        Ok(entries)
+        Ok(FileEntry {
+            name,
+            path,
+            size,
+            is_dir,
+            kind,
+            mod_time,
+            description,
+            permissions,
+            owner,
+            group,
+            inode,
+            is_symlink,
+            link_target,
+        })
     }
 
+    /// Enter `path`, taken relative to the current directory unless it is absolute, like the
+    /// paths of search results, which are used as they are.
     pub fn navigate_to<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<()> {
         let full_path = self.current_dir.join(path);
-        let new_path = fs::canonicalize(&full_path).unwrap_or(full_path);
+        let new_path = Self::canonicalize(&full_path).unwrap_or(full_path);
         
         if new_path.is_dir() {
-            self.current_dir = new_path;
+            // Refuse to enter a directory that can't be listed instead of showing a blank pane
+            fs::read_dir(&new_path)?;
+            self.push_history(new_path);
             Ok(())
         } else {
             Err(std::io::Error::new(std::io::ErrorKind::NotADirectory, "Not a directory"))
         }
     }
 
+    /// Go to the parent directory. Returns false, leaving everything as it was, at the root.
     pub fn navigate_up(&mut self) -> bool {
-        if let Some(parent) = self.current_dir.parent() {
-            self.current_dir = parent.to_path_buf();
-            true
-        } else {
-            false
+        match self.current_dir.parent() {
+            Some(parent) => {
+                let parent = parent.to_path_buf();
+                self.push_history(parent);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return to the previously visited directory, like a browser's back button.
+    pub fn go_back(&mut self) -> bool {
+        match self.back_stack.pop() {
+            Some(previous) => {
+                let current = std::mem::replace(&mut self.current_dir, previous);
+                self.forward_stack.push(current);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Undo a go_back.
+    pub fn go_forward(&mut self) -> bool {
+        match self.forward_stack.pop() {
+            Some(next) => {
+                let current = std::mem::replace(&mut self.current_dir, next);
+                self.back_stack.push(current);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Split the current directory into (label, path) pairs from the root down, for a breadcrumb bar.
+    /// The root label keeps its separator so consecutive labels read as the full path.
+    pub fn breadcrumbs(&self) -> Vec<(String, PathBuf)> {
+        let mut crumbs: Vec<(String, PathBuf)> = Vec::new();
+        let mut path = PathBuf::new();
+        for component in self.current_dir.components() {
+            path.push(component);
+            match component {
+                // On Windows the root follows the drive prefix; fold it into the "C:\" crumb
+                Component::RootDir if !crumbs.is_empty() => {
+                    if let Some(last) = crumbs.last_mut() {
+                        last.0.push(std::path::MAIN_SEPARATOR);
+                        last.1 = path.clone();
+                    }
+                }
+                Component::RootDir => crumbs.push((std::path::MAIN_SEPARATOR.to_string(), path.clone())),
+                other => crumbs.push((other.as_os_str().to_string_lossy().to_string(), path.clone())),
+            }
+        }
+        crumbs
+    }
+
+    /// Move to `path`, recording the current directory for go_back. A fresh jump
+    /// invalidates the forward history.
+    fn push_history(&mut self, path: PathBuf) {
+        if path == self.current_dir {
+            return;
         }
+        let previous = std::mem::replace(&mut self.current_dir, path);
+        self.back_stack.push(previous);
+        self.forward_stack.clear();
     }
 
     /// Custom move that preserves xattrs even across partitions
@@ -158,6 +296,12 @@ impl FileSystemManager {
             }
         }
 
+        // Sidecar descriptions live next to the file, not on it, so carry them over by hand.
+        // The entry is keyed by name, so it can still be taken once the source is gone.
+        if let Some(desc) = metadata::take_sidecar_description(src) {
+            metadata::set_description(dst, &desc)?;
+        }
+
         Ok(())
     }
 
@@ -165,66 +309,389 @@ impl FileSystemManager {
         std::fs::create_dir_all(path)
     }
 
+    /// Create a new empty file. Unlike File::create this refuses to truncate an existing file.
+    pub fn create_file(&self, path: &Path) -> std::io::Result<()> {
+        fs::OpenOptions::new().write(true).create_new(true).open(path).map(|_| ())
+    }
+
+    /// Create a symbolic link at `link` pointing to `target`.
+    pub fn create_symlink(&self, target: &Path, link: &Path) -> std::io::Result<()> {
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(target, link)
+        }
+        #[cfg(windows)]
+        {
+            let result = if target.is_dir() {
+                std::os::windows::fs::symlink_dir(target, link)
+            } else {
+                std::os::windows::fs::symlink_file(target, link)
+            };
+            // ERROR_PRIVILEGE_NOT_HELD; the default message doesn't say what to do about it
+            result.map_err(|e| match e.raw_os_error() {
+                Some(1314) => std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    "Creating symlinks needs Developer Mode or administrator rights",
+                ),
+                _ => e,
+            })
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            let _ = (target, link);
+            Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Symlinks are not supported on this platform"))
+        }
+    }
+
     pub fn delete_recursive(&self, path: &Path) -> std::io::Result<()> {
-        if path.is_dir() {
+        // Only ever remove the link itself, never what it points to
+        if path.is_dir() && !path.is_symlink() {
             std::fs::remove_dir_all(path)
         } else {
             std::fs::remove_file(path)
         }
     }
 
+    /// Move an entry into the XDG trash (`$XDG_DATA_HOME/Trash`) and write the matching
+    /// `.trashinfo` so desktop trash tools can restore it. Returns the path inside the trash.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    pub fn trash(&self, path: &Path) -> std::io::Result<PathBuf> {
+        let trash_dir = dirs::data_dir()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No data directory for trash"))?
+            .join("Trash");
+        let files_dir = trash_dir.join("files");
+        let info_dir = trash_dir.join("info");
+        fs::create_dir_all(&files_dir)?;
+        fs::create_dir_all(&info_dir)?;
+
+        // Resolve only the parent so a symlink is trashed itself rather than its target
+        let name = path
+            .file_name()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Cannot trash this path"))?;
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => fs::canonicalize(parent)?,
+            _ => std::env::current_dir()?,
+        };
+        let abs_path = parent.join(name);
+        abs_path.symlink_metadata()?;
+        let file_name = name.to_string_lossy().to_string();
+
+        let info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            percent_encode_path(&abs_path),
+            Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+
+        // Reserve a unique name by creating the .trashinfo file exclusively, as the spec requires.
+        let mut counter = 1;
+        loop {
+            let trash_name = if counter == 1 {
+                file_name.clone()
+            } else {
+                format!("{}.{}", file_name, counter)
+            };
+            let info_path = info_dir.join(format!("{}.trashinfo", trash_name));
+            let dst = files_dir.join(&trash_name);
+
+            if dst.symlink_metadata().is_err() {
+                match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                    Ok(mut file) => {
+                        use std::io::Write;
+                        file.write_all(info.as_bytes())?;
+                        if let Err(e) = self.move_entry(&abs_path, &dst) {
+                            let _ = fs::remove_file(&info_path);
+                            return Err(e);
+                        }
+                        return Ok(dst);
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            counter += 1;
+        }
+    }
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    pub fn trash(&self, _path: &Path) -> std::io::Result<PathBuf> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Trash is not supported on this platform"))
+    }
+
+    /// Move an entry that `trash` put at `trashed` back to `original` and drop its `.trashinfo`.
+    pub fn restore_from_trash(&self, trashed: &Path, original: &Path) -> std::io::Result<()> {
+        if original.symlink_metadata().is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} already exists", original.display()),
+            ));
+        }
+        self.move_entry(trashed, original)?;
+        if let (Some(name), Some(trash_dir)) = (trashed.file_name(), trashed.parent().and_then(Path::parent)) {
+            let _ = fs::remove_file(trash_dir.join("info").join(format!("{}.trashinfo", name.to_string_lossy())));
+        }
+        Ok(())
+    }
+
+    /// Read the permission bits of `path`, including setuid/setgid/sticky.
+    #[cfg(unix)]
+    pub fn permissions_mode(&self, path: &Path) -> std::io::Result<u32> {
+        Ok(fs::metadata(path)?.permissions().mode() & 0o7777)
+    }
+
+    #[cfg(not(unix))]
+    pub fn permissions_mode(&self, _path: &Path) -> std::io::Result<u32> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Permissions can only be changed on Unix"))
+    }
+
+    #[cfg(unix)]
+    pub fn set_permissions_mode(&self, path: &Path, mode: u32) -> std::io::Result<()> {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+    }
+
+    #[cfg(not(unix))]
+    pub fn set_permissions_mode(&self, _path: &Path, _mode: u32) -> std::io::Result<()> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Permissions can only be changed on Unix"))
+    }
+
+    /// Total and available bytes on the filesystem holding the current directory. Available
+    /// is what an unprivileged user can still write, which can be less than what is free.
+    #[cfg(unix)]
+    #[allow(clippy::useless_conversion)] // The statvfs field types differ between platforms
+    pub fn disk_usage(&self) -> Option<(u64, u64)> {
+        use std::os::unix::ffi::OsStrExt;
+        let path = std::ffi::CString::new(self.current_path().as_os_str().as_bytes()).ok()?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+        let block_size = u64::from(stat.f_frsize);
+        Some((u64::from(stat.f_blocks) * block_size, u64::from(stat.f_bavail) * block_size))
+    }
+
+    #[cfg(not(unix))]
+    pub fn disk_usage(&self) -> Option<(u64, u64)> {
+        None
+    }
+
+    /// Pick a free name in `dir` for `file_name` by appending " copy", " copy 2", ... before
+    /// the extension (or at the end for directories), as GUI file managers do.
+    pub fn unique_destination(dir: &Path, file_name: &OsStr) -> PathBuf {
+        let candidate = dir.join(file_name);
+        if candidate.symlink_metadata().is_err() {
+            return candidate;
+        }
+
+        let name = Path::new(file_name);
+        let (stem, ext) = match (candidate.is_dir(), name.file_stem(), name.extension()) {
+            (false, Some(stem), Some(ext)) => (stem.to_string_lossy().to_string(), format!(".{}", ext.to_string_lossy())),
+            _ => (file_name.to_string_lossy().to_string(), String::new()),
+        };
+
+        let mut counter = 1;
+        loop {
+            let suffix = if counter == 1 { " copy".to_string() } else { format!(" copy {}", counter) };
+            let candidate = dir.join(format!("{}{}{}", stem, suffix, ext));
+            if candidate.symlink_metadata().is_err() {
+                return candidate;
+            }
+            counter += 1;
+        }
+    }
+
+    /// Copy `src` to `dst`, keeping everything CopyOptions can keep.
     pub fn copy_recursive(&self, src: &Path, dst: &Path) -> std::io::Result<()> {
-        if src.is_dir() {
+        self.copy_recursive_with(src, dst, CopyOptions::default(), &mut |_| {})
+    }
+
+    /// Same as copy_recursive, but calls `on_file` with each file's path just before it is copied
+    /// and keeps only the metadata `options` asks for. Symlinks are copied as links rather than
+    /// followed, so a link cycle can't recurse forever.
+    pub fn copy_recursive_with(&self, src: &Path, dst: &Path, options: CopyOptions, on_file: &mut dyn FnMut(&Path)) -> std::io::Result<()> {
+        let metadata = fs::symlink_metadata(src)?;
+        if metadata.is_symlink() {
+            on_file(src);
+            self.create_symlink(&fs::read_link(src)?, dst)?;
+        } else if metadata.is_dir() {
+            refuse_nested_copy(src, dst)?;
+            std::fs::create_dir_all(dst)?;
+            if options.preserve_xattrs {
+                copy_xattrs(src, dst)?;
+            }
+            for entry in std::fs::read_dir(src)? {
+                let entry = entry?;
+                let file_name = entry.file_name();
+                self.copy_recursive_with(&src.join(&file_name), &dst.join(&file_name), options, on_file)?;
+            }
+        } else {
+            on_file(src);
+            // Not fs::copy, which brings the permissions along before the xattrs can be written
+            std::io::copy(&mut fs::File::open(src)?, &mut fs::File::create(dst)?)?;
+            if options.preserve_xattrs {
+                copy_xattrs(src, dst)?;
+            }
+        }
+
+        // Last, since filling a folder changes its mtime and a read-only one couldn't be filled
+        if options.preserve_perms && !metadata.is_symlink() {
+            fs::set_permissions(dst, metadata.permissions())?;
+        }
+        if options.preserve_timestamps {
+            let accessed = filetime::FileTime::from_last_access_time(&metadata);
+            let modified = filetime::FileTime::from_last_modification_time(&metadata);
+            filetime::set_symlink_file_times(dst, accessed, modified)?;
+        }
+        Ok(())
+    }
+
+    /// Like copy_recursive_with, but every file at `dst` is a hard link to the one at `src`, so
+    /// the tree takes no extra space. Directories themselves can't be linked and are recreated.
+    pub fn link_recursive_with(&self, src: &Path, dst: &Path, on_file: &mut dyn FnMut(&Path)) -> std::io::Result<()> {
+        // A link to a folder is linked itself rather than entered, like in copy_recursive_with
+        if src.is_dir() && !src.is_symlink() {
+            refuse_nested_copy(src, dst)?;
             std::fs::create_dir_all(dst)?;
             for entry in std::fs::read_dir(src)? {
                 let entry = entry?;
                 let file_name = entry.file_name();
-                self.copy_recursive(&src.join(&file_name), &dst.join(&file_name))?;
+                self.link_recursive_with(&src.join(&file_name), &dst.join(&file_name), on_file)?;
             }
         } else {
-            std::fs::copy(src, dst)?;
+            on_file(src);
+            std::fs::hard_link(src, dst).map_err(|e| match e.kind() {
+                std::io::ErrorKind::CrossesDevices => std::io::Error::new(
+                    e.kind(),
+                    "hard links can't point to another filesystem; copy instead",
+                ),
+                _ => e,
+            })?;
         }
         Ok(())
     }
 
-    pub fn search_recursive<P: AsRef<Path>>(&self, root: P, query: &str) -> Vec<FileEntry> {
-        let query = query.to_lowercase();
-        walkdir::WalkDir::new(root)
+    /// Zip `paths` into a new archive at `dst`. Folders keep their structure below their own
+    /// name and symlinks are stored as links. `on_file` is called before each file goes in and
+    /// can return false to abandon the archive. A failed or abandoned archive is removed.
+    pub fn create_archive(&self, paths: &[PathBuf], dst: &Path, on_file: &mut dyn FnMut(&Path) -> bool) -> std::io::Result<()> {
+        let file = fs::OpenOptions::new().write(true).create_new(true).open(dst)?;
+        let result = archive::write_zip(paths, dst, file, on_file);
+        if result.is_err() {
+            let _ = fs::remove_file(dst);
+        }
+        result
+    }
+
+    /// Unpack the archive at `path` into the new directory `dst`. Entries that would end up
+    /// outside `dst`, through `..` or an absolute path or a symlink, are left out and counted in
+    /// the result. `on_file` is called as in archive::extract; on failure `dst` is removed again.
+    pub fn extract_archive(&self, path: &Path, dst: &Path, on_file: &mut dyn FnMut(&Path, u64) -> bool) -> std::io::Result<usize> {
+        let format = archive::Format::detect(path)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Not a zip or tar archive"))?;
+        fs::create_dir(dst)?;
+        let result = archive::extract(path, format, dst, on_file);
+        if result.is_err() {
+            let _ = fs::remove_dir_all(dst);
+        }
+        result
+    }
+
+
+    /// Sum the sizes of all files below `path`. Symlinks are not followed and the walk
+    /// stays on the same filesystem, so link cycles and mounted trees can't blow it up.
+    /// Returns None once `cancel` is set.
+    pub fn directory_size(path: &Path, cancel: &AtomicBool) -> Option<u64> {
+        let mut total = 0;
+        for entry in walkdir::WalkDir::new(path).follow_links(false).same_file_system(true) {
+            if cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+            if let Ok(entry) = entry
+                && entry.file_type().is_file()
+                && let Ok(metadata) = entry.metadata()
+            {
+                total += metadata.len();
+            }
+        }
+        Some(total)
+    }
+
+    /// Read up to `max_bytes` of a file for previewing. Returns None for anything but a regular
+    /// file, unreadable files, and anything that looks binary (contains a NUL byte).
+    pub fn read_preview(&self, path: &Path, max_bytes: usize) -> Option<String> {
+        use std::io::Read;
+        // Opening a FIFO or a terminal would block until someone writes to it
+        if !fs::metadata(path).ok()?.is_file() {
+            return None;
+        }
+        let file = fs::File::open(path).ok()?;
+        let mut buffer = Vec::with_capacity(max_bytes.min(64 * 1024));
+        file.take(max_bytes as u64).read_to_end(&mut buffer).ok()?;
+        if buffer.contains(&0) {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&buffer).into_owned())
+    }
+
+    /// Walk `root` and hand every entry whose name or description satisfies `matcher` to `on_match`.
+    /// With `search_contents`, files are also matched by their text and the matching line is passed along.
+    /// The walk stops early once `on_match` returns false or `limit` results have been found.
+    /// With `follow_links`, linked folders are searched too, but each real folder only once, so
+    /// links back up the tree or several links to the same place don't repeat the walk.
+    /// This does not borrow the manager so it can run on a background task.
+    pub fn search_recursive<P, F>(root: P, matcher: &Matcher, search_contents: bool, follow_links: bool, limit: usize, mut on_match: F)
+    where
+        P: AsRef<Path>,
+        F: FnMut(FileEntry, Option<ContentMatch>) -> bool,
+    {
+        let mut visited = std::collections::HashSet::new();
+        let matches = walkdir::WalkDir::new(root)
+            .follow_links(follow_links)
             .into_iter()
+            .filter_entry(|entry| {
+                !follow_links || !entry.file_type().is_dir()
+                    || fs::canonicalize(entry.path()).is_ok_and(|real| visited.insert(real))
+            })
             .filter_map(|e| e.ok())
             .filter_map(|entry| {
                 let path = entry.path();
                 let name = entry.file_name().to_string_lossy().to_string();
                 
-                // Only include if name or description matches
-                let description = metadata::get_description(path);
-                let matches = name.to_lowercase().contains(&query) || 
-                             description.as_ref().map(|d| d.to_lowercase().contains(&query)).unwrap_or(false);
-
-                if matches {
-                    let metadata = entry.metadata().ok()?;
-                    let (permissions, owner, group, inode) = self.get_metadata_info(&metadata);
-                    Some(FileEntry {
-                        name,
-                        path: path.to_path_buf(),
-                        size: metadata.len(),
-                        is_dir: metadata.is_dir(),
-                        mod_time: metadata.modified().ok()?.into(),
-                        description,
-                        permissions,
-                        owner,
-                        group,
-                        inode,
-                    })
-                } else {
+                // Only include if name or description matches, falling back to the contents
+                let metadata = entry.metadata().ok()?;
+                let description = metadata::cached_description(path, &metadata);
+                let content_match = if matcher.matches_entry(&name, description.as_deref()) {
                     None
-                }
+                } else if search_contents && entry.file_type().is_file() {
+                    Some(find_content_match(path, matcher)?)
+                } else {
+                    return None;
+                };
+                let (permissions, owner, group, inode) = Self::get_metadata_info(&metadata);
+                Some((FileEntry {
+                    name,
+                    path: path.to_path_buf(),
+                    size: metadata.len(),
+                    is_dir: metadata.is_dir(),
+                    kind: FileKind::from_file_type(metadata.file_type()),
+                    mod_time: metadata.modified().ok()?.into(),
+                    description,
+                    permissions,
+                    owner,
+                    group,
+                    inode,
+                    is_symlink: entry.path_is_symlink(),
+                    link_target: entry.path_is_symlink().then(|| fs::read_link(path).ok()).flatten(),
+                }, content_match))
             })
-            .take(1000) // Limit results for performance
-            .collect()
+            .take(limit);
+
+        for (entry, content_match) in matches {
+            if !on_match(entry, content_match) {
+                break;
+            }
+        }
     }
 
-    fn get_metadata_info(&self, metadata: &fs::Metadata) -> (String, String, String, u64) {
+    fn get_metadata_info(metadata: &fs::Metadata) -> (String, String, String, u64) {
         #[cfg(unix)]
         {
             let mode = metadata.permissions().mode();
@@ -243,8 +710,8 @@ impl FileSystemManager {
                 if mode & 0o001 != 0 { "x" } else { "-" },
             );
             
-            let owner = metadata.uid().to_string();
-            let group = metadata.gid().to_string();
+            let owner = owners::user_name(metadata.uid());
+            let group = owners::group_name(metadata.gid());
             (permissions, owner, group, inode)
         }
         #[cfg(not(unix))]
@@ -253,3 +720,331 @@ impl FileSystemManager {
         }
     }
 }
+
+/// Percent-encode a path for the `Path=` key of a `.trashinfo` file, leaving `/` intact.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn percent_encode_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    let mut out = String::new();
+    for &byte in path.as_os_str().as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => out.push(byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Which metadata copy_recursive_with carries over besides the contents. The default keeps all of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopyOptions {
+    /// Modified and accessed times
+    pub preserve_timestamps: bool,
+    /// Permission bits, or the read-only flag on Windows
+    pub preserve_perms: bool,
+    /// Extended attributes, including descriptions
+    pub preserve_xattrs: bool,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            preserve_timestamps: true,
+            preserve_perms: true,
+            preserve_xattrs: true,
+        }
+    }
+}
+
+/// Carry the xattrs of `src` over to `dst`. Where `dst` can't hold xattrs the description
+/// still lands in a sidecar, and one that `src` keeps in a sidecar is carried over the same way.
+fn copy_xattrs(src: &Path, dst: &Path) -> std::io::Result<()> {
+    if let Ok(attrs) = xattr::list(src) {
+        for attr in attrs {
+            if let Ok(Some(val)) = xattr::get(src, &attr) {
+                let _ = xattr::set(dst, attr, &val);
+            }
+        }
+    }
+    if let Some(description) = metadata::get_description(src)
+        && metadata::get_description(dst).as_ref() != Some(&description)
+    {
+        metadata::set_description(dst, &description)?;
+    }
+    Ok(())
+}
+
+/// Copying a folder somewhere inside itself would keep finding the copy and never finish.
+fn refuse_nested_copy(src: &Path, dst: &Path) -> std::io::Result<()> {
+    let src = fs::canonicalize(src)?;
+    let dst_parent = dst.parent().and_then(|p| fs::canonicalize(p).ok());
+    if dst_parent.is_some_and(|parent| parent.starts_with(&src)) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("can't copy {} into itself", src.display()),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_back_and_forward_history() {
+        let dir = tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+
+        let mut manager = FileSystemManager::new(&root);
+        manager.navigate_to("a").unwrap();
+        manager.navigate_to("b").unwrap();
+        assert_eq!(manager.current_path(), root.join("a/b"));
+
+        assert!(manager.go_back());
+        assert_eq!(manager.current_path(), root.join("a"));
+        assert!(manager.go_back());
+        assert_eq!(manager.current_path(), root);
+        assert!(!manager.go_back());
+
+        assert!(manager.go_forward());
+        assert_eq!(manager.current_path(), root.join("a"));
+
+        // A new jump drops the forward history
+        assert!(manager.navigate_up());
+        assert!(!manager.go_forward());
+        assert!(manager.go_back());
+        assert_eq!(manager.current_path(), root.join("a"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_root_boundary() {
+        let dir = tempdir().unwrap();
+        let temp = FileSystemManager::canonicalize(dir.path()).unwrap();
+        let root = temp.ancestors().last().unwrap().to_path_buf();
+        let first_rows = |manager: &FileSystemManager| {
+            let mut rows = Vec::new();
+            manager.list_directory_chunked(1, |chunk| {
+                rows = chunk;
+                false
+            }).unwrap();
+            rows
+        };
+
+        let mut manager = FileSystemManager::new(&root);
+        assert!(manager.is_at_root());
+        assert!(first_rows(&manager).iter().all(|e| e.name != ".."));
+        assert!(!manager.navigate_up());
+        manager.navigate_to("..").unwrap();
+        assert_eq!(manager.current_path(), root);
+        // Neither counted as a move, so there is nothing to go back to
+        assert!(!manager.go_back());
+
+        // One level down, ".." leads to the root and going up lands there
+        let child = temp.ancestors().nth(temp.ancestors().count() - 2).unwrap();
+        let mut manager = FileSystemManager::new(child);
+        let parent = first_rows(&manager).into_iter().find(|e| e.name == "..").unwrap();
+        assert_eq!(parent.path, root);
+        assert!(manager.navigate_up());
+        assert!(manager.is_at_root());
+    }
+
+    #[test]
+    fn test_create_archive() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let manager = FileSystemManager::new(root);
+        fs::create_dir_all(root.join("docs/sub")).unwrap();
+        fs::write(root.join("docs/sub/a.txt"), "hello").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+
+        let paths = vec![root.join("docs"), root.join("b.txt")];
+        let mut files = 0;
+        manager.create_archive(&paths, &root.join("out.zip"), &mut |_| { files += 1; true }).unwrap();
+        assert_eq!(files, 2);
+        let mut archive = zip::ZipArchive::new(File::open(root.join("out.zip")).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(|name| name.unwrap().to_string()).collect();
+        names.sort();
+        assert_eq!(names, ["b.txt", "docs/", "docs/sub/", "docs/sub/a.txt"]);
+        let mut text = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("docs/sub/a.txt").unwrap(), &mut text).unwrap();
+        assert_eq!(text, "hello");
+
+        // An existing archive is never overwritten, and an abandoned one is cleaned up
+        assert!(manager.create_archive(&paths, &root.join("out.zip"), &mut |_| true).is_err());
+        assert!(manager.create_archive(&paths, &root.join("stop.zip"), &mut |_| false).is_err());
+        assert!(!root.join("stop.zip").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_link_recursive() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let manager = FileSystemManager::new(root);
+        fs::create_dir_all(root.join("src/sub")).unwrap();
+        fs::write(root.join("src/sub/a.txt"), "a").unwrap();
+
+        manager.link_recursive_with(&root.join("src"), &root.join("dst"), &mut |_| {}).unwrap();
+        let original = fs::metadata(root.join("src/sub/a.txt")).unwrap();
+        let linked = fs::metadata(root.join("dst/sub/a.txt")).unwrap();
+        assert_eq!(original.ino(), linked.ino());
+        assert_eq!(linked.nlink(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_symlink() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let manager = FileSystemManager::new(root);
+        fs::write(root.join("target.txt"), "x").unwrap();
+        manager.create_symlink(&root.join("target.txt"), &root.join("link")).unwrap();
+        assert_eq!(fs::read_link(root.join("link")).unwrap(), root.join("target.txt"));
+        assert!(manager.create_symlink(&root.join("target.txt"), &root.join("link")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_disk_usage() {
+        let dir = tempdir().unwrap();
+        let (total, available) = FileSystemManager::new(dir.path()).disk_usage().unwrap();
+        assert!(total > 0 && available <= total);
+    }
+
+    #[test]
+    fn test_breadcrumbs() {
+        let manager = FileSystemManager { current_dir: PathBuf::from("/home/user"), back_stack: Vec::new(), forward_stack: Vec::new() };
+        assert_eq!(manager.breadcrumbs(), vec![
+            ("/".to_string(), PathBuf::from("/")),
+            ("home".to_string(), PathBuf::from("/home")),
+            ("user".to_string(), PathBuf::from("/home/user")),
+        ]);
+    }
+
+    #[test]
+    fn test_list_directory_chunked() {
+        let dir = tempdir().unwrap();
+        for i in 0..7 {
+            File::create(dir.path().join(format!("file{}", i))).unwrap();
+        }
+        let manager = FileSystemManager::new(dir.path());
+
+        let mut sizes = Vec::new();
+        manager.list_directory_chunked(4, |chunk| {
+            sizes.push(chunk.len());
+            true
+        }).unwrap();
+        // "." and ".." come first, in the first chunk
+        assert_eq!(sizes, vec![4, 4, 1]);
+        assert_eq!(manager.entry_count().unwrap(), 9);
+
+        let mut chunks = 0;
+        manager.list_directory_chunked(4, |_| {
+            chunks += 1;
+            false
+        }).unwrap();
+        assert_eq!(chunks, 1);
+    }
+
+    #[test]
+    fn test_unique_destination() {
+        let dir = tempdir().unwrap();
+        let name = OsStr::new("report.txt");
+        assert_eq!(FileSystemManager::unique_destination(dir.path(), name), dir.path().join("report.txt"));
+
+        File::create(dir.path().join("report.txt")).unwrap();
+        assert_eq!(FileSystemManager::unique_destination(dir.path(), name), dir.path().join("report copy.txt"));
+
+        File::create(dir.path().join("report copy.txt")).unwrap();
+        assert_eq!(FileSystemManager::unique_destination(dir.path(), name), dir.path().join("report copy 2.txt"));
+
+        fs::create_dir(dir.path().join("photos.d")).unwrap();
+        assert_eq!(
+            FileSystemManager::unique_destination(dir.path(), OsStr::new("photos.d")),
+            dir.path().join("photos.d copy")
+        );
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_trash_moves_the_entry_itself() {
+        let data = tempdir().unwrap();
+        // SAFETY: no other test reads or writes XDG_DATA_HOME
+        unsafe { std::env::set_var("XDG_DATA_HOME", data.path()) };
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let manager = FileSystemManager::new(root);
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::create_dir(root.join("target")).unwrap();
+        std::os::unix::fs::symlink(root.join("target"), root.join("link")).unwrap();
+
+        let trashed = manager.trash(&root.join("a.txt")).unwrap();
+        assert_eq!(trashed, data.path().join("Trash/files/a.txt"));
+        assert!(!root.join("a.txt").exists());
+        assert_eq!(fs::read_to_string(&trashed).unwrap(), "a");
+
+        // The link goes to the trash and its target stays put
+        let trashed = manager.trash(&root.join("link")).unwrap();
+        assert_eq!(trashed, data.path().join("Trash/files/link"));
+        assert!(root.join("link").symlink_metadata().is_err());
+        assert!(root.join("target").is_dir());
+        assert!(trashed.symlink_metadata().unwrap().file_type().is_symlink());
+        let info = fs::read_to_string(data.path().join("Trash/info/link.trashinfo")).unwrap();
+        assert!(info.contains(&percent_encode_path(&fs::canonicalize(root).unwrap().join("link"))));
+
+        manager.restore_from_trash(&trashed, &root.join("link")).unwrap();
+        assert_eq!(fs::read_link(root.join("link")).unwrap(), root.join("target"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_directory_symlinks() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("real")).unwrap();
+        std::os::unix::fs::symlink("real", dir.path().join("linked")).unwrap();
+        std::os::unix::fs::symlink("missing", dir.path().join("broken")).unwrap();
+
+        let entries = FileSystemManager::new(dir.path()).list_directory().unwrap();
+        let find = |name: &str| entries.iter().find(|e| e.name == name).unwrap();
+
+        let linked = find("linked");
+        assert!(linked.is_symlink && linked.is_dir);
+        assert_eq!(linked.link_target.as_deref(), Some(Path::new("real")));
+
+        let broken = find("broken");
+        assert!(broken.is_symlink && !broken.is_dir);
+        assert_eq!(broken.link_target.as_deref(), Some(Path::new("missing")));
+
+        assert!(!find("real").is_symlink);
+
+        // The kind describes what a link points to, and a dangling link stays a symlink
+        assert_eq!(linked.kind, FileKind::Directory);
+        assert_eq!(broken.kind, FileKind::Symlink);
+        let fifo = dir.path().join("pipe");
+        let c_path = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o644) }, 0);
+        let entries = FileSystemManager::new(dir.path()).list_directory().unwrap();
+        assert_eq!(entries.iter().find(|e| e.name == "pipe").unwrap().kind, FileKind::Fifo);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_preview_only_reads_regular_files() {
+        let dir = tempdir().unwrap();
+        let manager = FileSystemManager::new(dir.path());
+        fs::write(dir.path().join("notes.txt"), "hello").unwrap();
+        assert_eq!(manager.read_preview(&dir.path().join("notes.txt"), 100).as_deref(), Some("hello"));
+        assert_eq!(manager.read_preview(dir.path(), 100), None);
+
+        // Nothing writes to the pipe, so this would hang if the preview opened it
+        let fifo = dir.path().join("pipe");
+        let c_path = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o644) }, 0);
+        assert_eq!(manager.read_preview(&fifo, 100), None);
+    }
+}
@@ -1,5 +1,18 @@
+pub mod archive;
+pub mod complete;
 pub mod entry;
 pub mod manager;
+#[cfg(unix)]
+pub mod owners;
+pub mod rename;
+pub mod search;
+pub mod sort;
+pub mod transfer;
+pub mod undo;
 
-pub use entry::FileEntry;
-pub use manager::FileSystemManager;
+pub use entry::{abbreviate_home, format_relative, format_size, FileEntry, FileKind};
+pub use manager::{CopyOptions, FileSystemManager};
+pub use search::{ContentMatch, Matcher, NameFilter, SearchMode};
+pub use sort::{compare_depth, compare_entries, sort_entries, SortKey, SortOrder};
+pub use transfer::{CopyProgress, TransferItem, TransferKind};
+pub use undo::Operation;
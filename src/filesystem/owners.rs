@@ -0,0 +1,80 @@
+//! uid/gid to name resolution for the Details panel, cached since a directory's entries
+//! usually share a handful of owners.
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::sync::{Mutex, OnceLock};
+
+static USERS: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+static GROUPS: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+
+/// Name of the user with `uid`, or the number itself when it has no passwd entry.
+pub fn user_name(uid: u32) -> String {
+    cached(&USERS, uid, lookup_user)
+}
+
+/// Name of the group with `gid`, or the number itself when it has no group entry.
+pub fn group_name(gid: u32) -> String {
+    cached(&GROUPS, gid, lookup_group)
+}
+
+fn cached(cache: &OnceLock<Mutex<HashMap<u32, String>>>, id: u32, lookup: fn(u32) -> Option<String>) -> String {
+    let mut cache = cache.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    cache.entry(id)
+        .or_insert_with(|| lookup(id).unwrap_or_else(|| id.to_string()))
+        .clone()
+}
+
+/// Run a reentrant libc lookup, growing the scratch buffer while it reports ERANGE.
+fn with_buffer(mut call: impl FnMut(&mut [libc::c_char]) -> libc::c_int) -> bool {
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        match call(&mut buf) {
+            libc::ERANGE if buf.len() < 1 << 20 => buf.resize(buf.len() * 2, 0),
+            rc => return rc == 0,
+        }
+    }
+}
+
+fn lookup_user(uid: u32) -> Option<String> {
+    // SAFETY: passwd is plain data; getpwuid_r only points its fields into `buf`, which
+    // outlives every read of them below.
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    let mut name = None;
+    with_buffer(|buf| {
+        let rc = unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+        if rc == 0 && !result.is_null() {
+            name = Some(unsafe { CStr::from_ptr(pwd.pw_name) }.to_string_lossy().into_owned());
+        }
+        rc
+    });
+    name
+}
+
+fn lookup_group(gid: u32) -> Option<String> {
+    // SAFETY: as for lookup_user
+    let mut grp: libc::group = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    let mut name = None;
+    with_buffer(|buf| {
+        let rc = unsafe { libc::getgrgid_r(gid, &mut grp, buf.as_mut_ptr(), buf.len(), &mut result) };
+        if rc == 0 && !result.is_null() {
+            name = Some(unsafe { CStr::from_ptr(grp.gr_name) }.to_string_lossy().into_owned());
+        }
+        rc
+    });
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_ids_fall_back_to_numbers() {
+        assert_eq!(user_name(0), "root");
+        assert_eq!(user_name(3_999_999_999), "3999999999");
+        assert_eq!(group_name(3_999_999_999), "3999999999");
+    }
+}
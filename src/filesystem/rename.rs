@@ -0,0 +1,128 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use crate::filesystem::{FileSystemManager, Operation};
+
+/// Build a new file name from a batch rename pattern. `{n}` is the 1-based position, padded to
+/// `width` digits so the names still sort in order; `{name}` is the original stem and `{ext}`
+/// the original extension. A dot right before `{ext}` is dropped for files without one.
+pub fn apply_pattern(pattern: &str, path: &Path, n: usize, width: usize) -> String {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let pattern = if ext.is_empty() { pattern.replace(".{ext}", "") } else { pattern.to_string() };
+    pattern
+        .replace("{n}", &format!("{:0width$}", n, width = width))
+        .replace("{name}", &stem)
+        .replace("{ext}", &ext)
+}
+
+/// Work out where each of `paths` would go under `pattern`, in order. Names that are invalid,
+/// collide with another name in the batch or with an unrelated existing entry come back as errors.
+pub fn plan_batch_rename(paths: &[PathBuf], pattern: &str) -> Vec<(PathBuf, Result<PathBuf, String>)> {
+    let width = paths.len().to_string().len();
+    let targets: Vec<Option<PathBuf>> = paths.iter().enumerate()
+        .map(|(i, path)| {
+            let name = apply_pattern(pattern, path, i + 1, width);
+            let valid = !name.trim().is_empty() && name != "." && name != ".."
+                && !name.contains(['/', std::path::MAIN_SEPARATOR]);
+            valid.then(|| path.with_file_name(name))
+        })
+        .collect();
+
+    let mut counts: HashMap<&PathBuf, usize> = HashMap::new();
+    for target in targets.iter().flatten() {
+        *counts.entry(target).or_default() += 1;
+    }
+    let sources: HashSet<&PathBuf> = paths.iter().collect();
+
+    paths.iter().zip(&targets)
+        .map(|(path, target)| {
+            let result = match target {
+                None => Err("invalid name".to_string()),
+                Some(target) if counts[target] > 1 => Err("same name as another file in the batch".to_string()),
+                // Entries being renamed themselves will have moved out of the way
+                Some(target) if !sources.contains(target) && target.symlink_metadata().is_ok() => {
+                    Err("already exists".to_string())
+                }
+                Some(target) => Ok(target.clone()),
+            };
+            (path.clone(), result)
+        })
+        .collect()
+}
+
+/// Carry out a checked plan with move_entry so xattrs and descriptions travel along. When a
+/// target is still occupied by a later item, everything first moves to a temporary name.
+/// Returns the moves that were actually done, for undo, and the error that stopped the batch.
+pub fn execute_batch_rename(manager: &FileSystemManager, plan: &[(PathBuf, PathBuf)]) -> (Vec<Operation>, Option<String>) {
+    let plan: Vec<&(PathBuf, PathBuf)> = plan.iter().filter(|(src, dst)| src != dst).collect();
+    let sources: HashSet<&PathBuf> = plan.iter().map(|(src, _)| src).collect();
+    let two_phase = plan.iter().any(|(_, dst)| sources.contains(dst));
+
+    let mut operations = Vec::new();
+    let mut steps: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (src, dst) in &plan {
+        if two_phase {
+            let dir = src.parent().unwrap_or(Path::new("."));
+            let name = src.file_name().unwrap_or_default().to_string_lossy();
+            let temp = FileSystemManager::unique_destination(dir, OsStr::new(&format!(".{}.xplore-rename", name)));
+            if let Err(e) = manager.move_entry(src, &temp) {
+                return (operations, Some(format!("Failed to rename {}: {}", src.display(), e)));
+            }
+            operations.push(Operation::Move { from: src.clone(), to: temp.clone() });
+            steps.push((temp, dst.clone()));
+        } else {
+            steps.push((src.clone(), dst.clone()));
+        }
+    }
+    for (src, dst) in steps {
+        if let Err(e) = manager.move_entry(&src, &dst) {
+            return (operations, Some(format!("Failed to rename {}: {}", src.display(), e)));
+        }
+        operations.push(Operation::Move { from: src, to: dst });
+    }
+    (operations, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_apply_pattern() {
+        let path = Path::new("/photos/IMG_0042.jpg");
+        assert_eq!(apply_pattern("trip_{n}.{ext}", path, 3, 2), "trip_03.jpg");
+        assert_eq!(apply_pattern("{name}-old.{ext}", path, 1, 1), "IMG_0042-old.jpg");
+        assert_eq!(apply_pattern("{n}.{ext}", Path::new("/photos/README"), 7, 1), "7");
+    }
+
+    #[test]
+    fn test_batch_rename_collisions_and_swaps() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for name in ["a.txt", "b.txt", "other.txt"] {
+            fs::write(root.join(name), name).unwrap();
+        }
+        let paths = vec![root.join("a.txt"), root.join("b.txt")];
+
+        let plan = plan_batch_rename(&paths, "same.{ext}");
+        assert!(plan.iter().all(|(_, result)| result.is_err()));
+        let plan = plan_batch_rename(&paths, "other.{ext}");
+        assert!(plan.iter().all(|(_, result)| result.is_err()));
+
+        // Swapping the two names needs the temporary step
+        let swapped = vec![(root.join("a.txt"), root.join("b.txt")), (root.join("b.txt"), root.join("a.txt"))];
+        let manager = FileSystemManager::new(root);
+        let (operations, error) = execute_batch_rename(&manager, &swapped);
+        assert!(error.is_none());
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "b.txt");
+        assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "a.txt");
+
+        for operation in operations.iter().rev() {
+            operation.undo(&manager, false).unwrap();
+        }
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "a.txt");
+    }
+}
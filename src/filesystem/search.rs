@@ -0,0 +1,345 @@
+use regex::Regex;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// Files larger than this are skipped when searching contents.
+pub const CONTENT_SEARCH_MAX_BYTES: u64 = 1024 * 1024;
+/// Matched lines are cut to this many chars before being shown.
+const CONTENT_LINE_MAX_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    Substring,
+    CaseSensitive,
+    Regex,
+    /// The query's characters in order, with anything in between
+    Fuzzy,
+}
+
+impl SearchMode {
+    pub fn next(self) -> Self {
+        match self {
+            SearchMode::Substring => SearchMode::CaseSensitive,
+            SearchMode::CaseSensitive => SearchMode::Regex,
+            SearchMode::Regex => SearchMode::Fuzzy,
+            SearchMode::Fuzzy => SearchMode::Substring,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchMode::Substring => "abc",
+            SearchMode::CaseSensitive => "Aa",
+            SearchMode::Regex => ".*",
+            SearchMode::Fuzzy => "~",
+        }
+    }
+}
+
+/// A compiled search query, cheap to test against many names and safe to send to a background task.
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    Substring(String),
+    CaseSensitive(String),
+    Regex(Regex),
+    /// Lowercased query characters
+    Fuzzy(Vec<char>),
+}
+
+impl Matcher {
+    pub fn new(query: &str, mode: SearchMode) -> Result<Self, regex::Error> {
+        Ok(match mode {
+            SearchMode::Substring => Matcher::Substring(query.to_lowercase()),
+            SearchMode::CaseSensitive => Matcher::CaseSensitive(query.to_string()),
+            SearchMode::Regex => Matcher::Regex(Regex::new(query)?),
+            SearchMode::Fuzzy => Matcher::Fuzzy(query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect()),
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Substring(query) => text.to_lowercase().contains(query),
+            Matcher::CaseSensitive(query) => text.contains(query),
+            Matcher::Regex(regex) => regex.is_match(text),
+            Matcher::Fuzzy(query) => fuzzy_match(query, text).is_some(),
+        }
+    }
+
+    /// An entry matches when either its name or its description does.
+    pub fn matches_entry(&self, name: &str, description: Option<&str>) -> bool {
+        self.is_match(name) || description.is_some_and(|d| self.is_match(d))
+    }
+
+    /// The char ranges of `text` the query matched, for highlighting. Non-overlapping and in order.
+    pub fn match_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            Matcher::Substring(query) => {
+                let query: Vec<char> = query.chars().collect();
+                let lower: Vec<char> = text.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect();
+                find_all(&lower, &query)
+            }
+            Matcher::CaseSensitive(query) => {
+                let query: Vec<char> = query.chars().collect();
+                find_all(&text.chars().collect::<Vec<_>>(), &query)
+            }
+            Matcher::Regex(regex) => {
+                let char_index = |byte: usize| text[..byte].chars().count();
+                regex.find_iter(text)
+                    .filter(|m| !m.is_empty())
+                    .map(|m| char_index(m.start())..char_index(m.end()))
+                    .collect()
+            }
+            Matcher::Fuzzy(query) => {
+                let Some((_, positions)) = fuzzy_match(query, text) else { return Vec::new() };
+                // Neighbouring positions merge into one range
+                let mut ranges: Vec<Range<usize>> = Vec::new();
+                for position in positions {
+                    match ranges.last_mut() {
+                        Some(last) if last.end == position => last.end += 1,
+                        _ => ranges.push(position..position + 1),
+                    }
+                }
+                ranges
+            }
+        }
+    }
+
+    /// How well an entry matches, higher being better, for ordering fuzzy results. The other
+    /// modes rank every match the same.
+    pub fn entry_score(&self, name: &str, description: Option<&str>) -> Option<i64> {
+        let Matcher::Fuzzy(query) = self else {
+            return self.matches_entry(name, description).then_some(0);
+        };
+        let name_score = fuzzy_match(query, name).map(|(score, _)| score);
+        // A hit in the name says more than one somewhere in a long description
+        let description_score = description.and_then(|d| fuzzy_match(query, d)).map(|(score, _)| score / 2);
+        name_score.max(description_score)
+    }
+}
+
+/// Every non-overlapping occurrence of `query` in `text`, from the left.
+fn find_all(text: &[char], query: &[char]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    if query.is_empty() {
+        return ranges;
+    }
+    let mut i = 0;
+    while i + query.len() <= text.len() {
+        if text[i..i + query.len()] == *query {
+            ranges.push(i..i + query.len());
+            i += query.len();
+        } else {
+            i += 1;
+        }
+    }
+    ranges
+}
+
+/// Score `text` against the lowercased `query` characters, returning the char positions they
+/// matched at. Matches in a run or at the start of a word score higher and gaps cost a little,
+/// so "mainrs" ranks "main.rs" above "my_awesome_input.nrs".
+fn fuzzy_match(query: &[char], text: &str) -> Option<(i64, Vec<usize>)> {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+
+    // The earliest place the whole query fits, then walk back from its end for the tightest start
+    let mut end = 0;
+    let mut matched = 0;
+    while matched < query.len() {
+        let offset = lower[end..].iter().position(|c| *c == query[matched])?;
+        end += offset + 1;
+        matched += 1;
+    }
+    let mut start = end;
+    for q in query.iter().rev() {
+        start = lower[..start].iter().rposition(|c| c == q)?;
+    }
+    let mut positions = Vec::with_capacity(query.len());
+    let mut i = start;
+    for q in query {
+        while lower[i] != *q {
+            i += 1;
+        }
+        positions.push(i);
+        i += 1;
+    }
+
+    let mut score = 0;
+    for (n, &position) in positions.iter().enumerate() {
+        score += 16;
+        let word_start = position == 0
+            || !chars[position - 1].is_alphanumeric()
+            || (chars[position - 1].is_lowercase() && chars[position].is_uppercase());
+        if word_start {
+            score += 20;
+        }
+        if n > 0 {
+            match position - positions[n - 1] - 1 {
+                0 => score += 24,
+                gap => score -= gap.min(10) as i64,
+            }
+        }
+    }
+    score -= start.min(10) as i64;
+    Some((score, positions))
+}
+
+/// Limits the listing to names matching any of a few space or comma separated globs, ignoring
+/// case. A term without wildcards is taken as an extension, so "rs" means "*.rs".
+#[derive(Debug, Clone)]
+pub struct NameFilter {
+    pattern: String,
+    regex: Regex,
+}
+
+impl NameFilter {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        let terms: Vec<String> = pattern
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|term| !term.is_empty())
+            .map(|term| {
+                if term.contains(['*', '?', '[']) {
+                    glob_to_regex(term)
+                } else {
+                    format!(".*\\.{}", regex::escape(term.trim_start_matches('.')))
+                }
+            })
+            .collect();
+        let regex = Regex::new(&format!("(?i)^(?:{})$", terms.join("|")))?;
+        Ok(NameFilter { pattern: pattern.trim().to_string(), regex })
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+/// `*` matches any run of characters, `?` a single one and `[...]` a class, as in a shell.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::new();
+    let mut in_class = false;
+    for c in glob.chars() {
+        match c {
+            '*' if !in_class => regex.push_str(".*"),
+            '?' if !in_class => regex.push('.'),
+            '[' if !in_class => {
+                in_class = true;
+                regex.push('[');
+            }
+            ']' if in_class => {
+                in_class = false;
+                regex.push(']');
+            }
+            '!' if in_class && regex.ends_with('[') => regex.push('^'),
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex
+}
+
+/// The first line of a file that matched a content search.
+#[derive(Debug, Clone)]
+pub struct ContentMatch {
+    pub line_number: usize,
+    pub line: String,
+}
+
+/// Search the contents of a regular file, skipping large and binary files.
+pub fn find_content_match(path: &Path, matcher: &Matcher) -> Option<ContentMatch> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > CONTENT_SEARCH_MAX_BYTES {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    if bytes.contains(&0) {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    text.lines()
+        .enumerate()
+        .find(|(_, line)| matcher.is_match(line))
+        .map(|(i, line)| ContentMatch {
+            line_number: i + 1,
+            line: line.trim().chars().take(CONTENT_LINE_MAX_CHARS).collect(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_modes() {
+        let plain = Matcher::new("readme", SearchMode::Substring).unwrap();
+        assert!(plain.matches_entry("README.md", None));
+
+        let exact = Matcher::new("readme", SearchMode::CaseSensitive).unwrap();
+        assert!(!exact.matches_entry("README.md", None));
+        assert!(exact.matches_entry("notes", Some("see readme")));
+
+        let regex = Matcher::new(r"^\d{4}-.*\.log$", SearchMode::Regex).unwrap();
+        assert!(regex.matches_entry("2024-01.log", None));
+        assert!(!regex.matches_entry("app.log", None));
+
+        assert!(Matcher::new("(unclosed", SearchMode::Regex).is_err());
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        let fuzzy = Matcher::new("mainrs", SearchMode::Fuzzy).unwrap();
+        assert!(fuzzy.is_match("main.rs"));
+        assert!(fuzzy.is_match("MyApp_Main.RS"));
+        assert!(!fuzzy.is_match("rsmain"));
+
+        let score = |name: &str| fuzzy.entry_score(name, None).unwrap();
+        assert!(score("main.rs") > score("my_awesome_input.nrs"));
+        assert!(score("main.rs") > score("src/domain.rs"));
+        assert_eq!(fuzzy_match(&['m', 'r'], "xmain.rs").unwrap().1, [1, 6]);
+        assert!(fuzzy.entry_score("notes", Some("see main.rs")).unwrap() < score("main.rs"));
+    }
+
+    #[test]
+    fn test_match_ranges() {
+        let ranges = |query: &str, mode: SearchMode, text: &str| Matcher::new(query, mode).unwrap().match_ranges(text);
+        assert_eq!(ranges("ab", SearchMode::Substring, "xABabab"), [1..3, 3..5, 5..7]);
+        assert_eq!(ranges("ab", SearchMode::CaseSensitive, "xABabab"), [3..5, 5..7]);
+        // Regex matches come back as char ranges, not byte ranges
+        assert_eq!(ranges(r"\d+", SearchMode::Regex, "é12x3"), [1..3, 4..5]);
+        assert_eq!(ranges("mrs", SearchMode::Fuzzy, "main.rs"), [0..1, 5..7]);
+        assert!(ranges("zz", SearchMode::Fuzzy, "main.rs").is_empty());
+    }
+
+    #[test]
+    fn test_name_filter() {
+        let filter = NameFilter::new("rs, *.TOML").unwrap();
+        assert!(filter.is_match("main.rs"));
+        assert!(filter.is_match("Cargo.toml"));
+        assert!(!filter.is_match("rs"));
+        assert!(!filter.is_match("notes.rs.bak"));
+
+        let glob = NameFilter::new("img_[!0]?.*").unwrap();
+        assert!(glob.is_match("IMG_12.jpg"));
+        assert!(!glob.is_match("img_01.jpg"));
+        assert!(NameFilter::new("[unclosed").is_err());
+    }
+
+    #[test]
+    fn test_content_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = dir.path().join("notes.txt");
+        fs::write(&text, "first\n  needle here  \nlast").unwrap();
+        let binary = dir.path().join("blob.bin");
+        fs::write(&binary, b"needle\0").unwrap();
+
+        let matcher = Matcher::new("needle", SearchMode::Substring).unwrap();
+        let found = find_content_match(&text, &matcher).unwrap();
+        assert_eq!(found.line_number, 2);
+        assert_eq!(found.line, "needle here");
+        assert!(find_content_match(&binary, &matcher).is_none());
+    }
+}
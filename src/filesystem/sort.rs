@@ -0,0 +1,188 @@
+use std::cmp::Ordering;
+use crate::filesystem::FileEntry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Size,
+    ModTime,
+    Extension,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortKey {
+    pub fn next(self) -> Self {
+        match self {
+            SortKey::Name => SortKey::Size,
+            SortKey::Size => SortKey::ModTime,
+            SortKey::ModTime => SortKey::Extension,
+            SortKey::Extension => SortKey::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Name => "Name",
+            SortKey::Size => "Size",
+            SortKey::ModTime => "Modified",
+            SortKey::Extension => "Extension",
+        }
+    }
+}
+
+impl SortOrder {
+    pub fn reverse(self) -> Self {
+        match self {
+            SortOrder::Asc => SortOrder::Desc,
+            SortOrder::Desc => SortOrder::Asc,
+        }
+    }
+
+    pub fn arrow(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "↑",
+            SortOrder::Desc => "↓",
+        }
+    }
+}
+
+/// Sort entries in place. "." and ".." always stay pinned at the top regardless of key or order.
+pub fn sort_entries(entries: &mut [FileEntry], key: SortKey, order: SortOrder, dirs_first: bool) {
+    entries.sort_by(|a, b| compare_entries(a, b, key, order, dirs_first));
+}
+
+/// The order sort_entries() puts `a` and `b` in.
+pub fn compare_entries(a: &FileEntry, b: &FileEntry, key: SortKey, order: SortOrder, dirs_first: bool) -> Ordering {
+    let pinned = pin_rank(a).cmp(&pin_rank(b));
+    if pinned != Ordering::Equal {
+        return pinned;
+    }
+    if dirs_first && a.is_dir != b.is_dir {
+        return b.is_dir.cmp(&a.is_dir);
+    }
+
+    let ordering = match key {
+        SortKey::Name => compare_names(a, b),
+        SortKey::Size => a.size.cmp(&b.size).then_with(|| compare_names(a, b)),
+        SortKey::ModTime => a.mod_time.cmp(&b.mod_time).then_with(|| compare_names(a, b)),
+        SortKey::Extension => extension(a).cmp(&extension(b)).then_with(|| compare_names(a, b)),
+    };
+
+    match order {
+        SortOrder::Asc => ordering,
+        SortOrder::Desc => ordering.reverse(),
+    }
+}
+
+/// Shallower paths first, then paths of the same depth in natural order.
+pub fn compare_depth(a: &FileEntry, b: &FileEntry) -> Ordering {
+    a.path.components().count().cmp(&b.path.components().count())
+        .then_with(|| natural_cmp(&a.path.to_string_lossy(), &b.path.to_string_lossy()))
+}
+
+fn pin_rank(entry: &FileEntry) -> u8 {
+    match entry.name.as_str() {
+        "." => 0,
+        ".." => 1,
+        _ => 2,
+    }
+}
+
+fn compare_names(a: &FileEntry, b: &FileEntry) -> Ordering {
+    natural_cmp(&a.name, &b.name)
+}
+
+/// Compare names ignoring case, with runs of digits compared by value so "file2" comes before
+/// "file10". Names that only differ in case or leading zeros still get a stable order.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut left = a.chars().flat_map(char::to_lowercase).peekable();
+    let mut right = b.chars().flat_map(char::to_lowercase).peekable();
+    while let (Some(&l), Some(&r)) = (left.peek(), right.peek()) {
+        if l.is_ascii_digit() && r.is_ascii_digit() {
+            let l_digits = take_digits(&mut left);
+            let r_digits = take_digits(&mut right);
+            let l_value = l_digits.trim_start_matches('0');
+            let r_value = r_digits.trim_start_matches('0');
+            let ordering = l_value.len().cmp(&r_value.len()).then_with(|| l_value.cmp(r_value));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        } else {
+            if l != r {
+                return l.cmp(&r);
+            }
+            left.next();
+            right.next();
+        }
+    }
+    left.peek().is_some().cmp(&right.peek().is_some())
+        .then_with(|| a.cmp(b))
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<impl Iterator<Item = char>>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits
+}
+
+fn extension(entry: &FileEntry) -> String {
+    if entry.is_dir {
+        return String::new();
+    }
+    entry.path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec!["file10", "File2", "file1", "file02b", "notes", "file2a"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, ["file1", "File2", "file2a", "file02b", "file10", "notes"]);
+        assert_eq!(natural_cmp("a", "A"), Ordering::Greater);
+        assert_eq!(natural_cmp("ep9", "ep9"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_dirs_first_off_keeps_navigation_pinned() {
+        let entry = |name: &str, is_dir: bool| FileEntry {
+            name: name.to_string(),
+            path: name.into(),
+            size: 0,
+            is_dir,
+            kind: if is_dir { crate::filesystem::FileKind::Directory } else { crate::filesystem::FileKind::File },
+            mod_time: chrono::Local::now(),
+            description: None,
+            permissions: String::new(),
+            owner: String::new(),
+            group: String::new(),
+            inode: 0,
+            is_symlink: false,
+            link_target: None,
+        };
+        let mut entries = vec![entry("b", false), entry("c", true), entry("..", true), entry("a", true)];
+        sort_entries(&mut entries, SortKey::Name, SortOrder::Desc, false);
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["..", "c", "b", "a"]);
+        sort_entries(&mut entries, SortKey::Name, SortOrder::Asc, true);
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["..", "a", "c", "b"]);
+
+        let mut deep = [entry("x/y/z", false), entry("x/b", false), entry("x/a10", false), entry("x/a9", false)];
+        deep.sort_by(compare_depth);
+        let paths: Vec<&str> = deep.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(paths, ["x/a9", "x/a10", "x/b", "x/y/z"]);
+    }
+}
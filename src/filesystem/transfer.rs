@@ -0,0 +1,220 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use crate::filesystem::{archive, CopyOptions, FileSystemManager, Operation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferKind {
+    Copy,
+    Move,
+    /// Hard link the files instead of copying them
+    Link,
+}
+
+/// One planned paste: copy or move `src` to `dst`, optionally replacing whatever is at `dst`.
+#[derive(Debug, Clone)]
+pub struct TransferItem {
+    pub src: PathBuf,
+    pub dst: PathBuf,
+    pub replace: bool,
+}
+
+/// Snapshot of a running transfer, sent from the worker to the UI after every file.
+#[derive(Debug, Clone, Default)]
+pub struct CopyProgress {
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub current_file: PathBuf,
+    pub error: Option<String>,
+    /// What the last finished item did, so the paste can be undone
+    pub operations: Vec<Operation>,
+}
+
+impl CopyProgress {
+    pub fn ratio(&self) -> f64 {
+        if self.bytes_total > 0 {
+            (self.bytes_done as f64 / self.bytes_total as f64).min(1.0)
+        } else if self.files_total > 0 {
+            (self.files_done as f64 / self.files_total as f64).min(1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Count files and bytes below `path` without following symlinks.
+fn measure(path: &Path) -> (u64, u64) {
+    walkdir::WalkDir::new(path)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| !e.file_type().is_dir())
+        .fold((0, 0), |(files, bytes), e| {
+            (files + 1, bytes + e.metadata().map(|m| m.len()).unwrap_or(0))
+        })
+}
+
+/// Execute a paste plan, reporting progress over `tx`. Meant to run on a blocking task;
+/// the transfer is finished when the sender is dropped. Setting `cancel` stops it before the
+/// next item, so an item is never left half copied.
+pub fn run_transfer(
+    manager: FileSystemManager,
+    kind: TransferKind,
+    items: Vec<TransferItem>,
+    copy_options: CopyOptions,
+    use_trash: bool,
+    cancel: Arc<AtomicBool>,
+    tx: Sender<CopyProgress>,
+) {
+    let sizes: Vec<(u64, u64)> = items.iter().map(|item| measure(&item.src)).collect();
+    let mut progress = CopyProgress {
+        files_total: sizes.iter().map(|s| s.0).sum(),
+        bytes_total: sizes.iter().map(|s| s.1).sum(),
+        ..Default::default()
+    };
+    let _ = tx.send(progress.clone());
+
+    let (mut files_before, mut bytes_before) = (0, 0);
+    for (item, (files, bytes)) in items.iter().zip(sizes) {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        progress.current_file = item.src.clone();
+
+        let mut operations = Vec::new();
+        let result = replace_destination(&manager, item, use_trash).and_then(|replaced| {
+            operations.extend(replaced);
+            match kind {
+                TransferKind::Copy | TransferKind::Link => {
+                    let mut previous_size = None;
+                    let mut on_file = |file: &Path| {
+                        // The previous file has finished by the time the next one starts
+                        if let Some(size) = previous_size {
+                            progress.files_done += 1;
+                            progress.bytes_done += size;
+                        }
+                        previous_size = Some(std::fs::symlink_metadata(file).map(|m| m.len()).unwrap_or(0));
+                        progress.current_file = file.to_path_buf();
+                        let _ = tx.send(progress.clone());
+                    };
+                    let result = if kind == TransferKind::Copy {
+                        manager.copy_recursive_with(&item.src, &item.dst, copy_options, &mut on_file)
+                    } else {
+                        manager.link_recursive_with(&item.src, &item.dst, &mut on_file)
+                    };
+                    result.map(|_| operations.push(Operation::Create { path: item.dst.clone() }))
+                }
+                TransferKind::Move => manager.move_entry(&item.src, &item.dst)
+                    .map(|_| operations.push(Operation::Move { from: item.src.clone(), to: item.dst.clone() })),
+            }
+        });
+
+        // Snap to the exact totals for this item regardless of how it finished
+        files_before += files;
+        bytes_before += bytes;
+        progress.files_done = files_before;
+        progress.bytes_done = bytes_before;
+        progress.error = result
+            .err()
+            .map(|e| format!("Failed to paste {}: {}", item.src.display(), e));
+        progress.operations = operations;
+        let _ = tx.send(progress.clone());
+        progress.error = None;
+        progress.operations.clear();
+    }
+}
+
+/// Zip `paths` into a new archive at `dst`, reporting progress over `tx` like run_transfer.
+/// Setting `cancel` stops at the next file and removes the unfinished archive.
+pub fn run_archive(
+    manager: FileSystemManager,
+    paths: Vec<PathBuf>,
+    dst: PathBuf,
+    cancel: Arc<AtomicBool>,
+    tx: Sender<CopyProgress>,
+) {
+    let (files_total, bytes_total) = paths.iter()
+        .map(|path| measure(path))
+        .fold((0, 0), |(files, bytes), (f, b)| (files + f, bytes + b));
+    let mut progress = CopyProgress { files_total, bytes_total, ..Default::default() };
+    let _ = tx.send(progress.clone());
+
+    let mut previous_size = None;
+    let result = manager.create_archive(&paths, &dst, &mut |file| {
+        if let Some(size) = previous_size {
+            progress.files_done += 1;
+            progress.bytes_done += size;
+        }
+        previous_size = Some(std::fs::symlink_metadata(file).map(|m| m.len()).unwrap_or(0));
+        progress.current_file = file.to_path_buf();
+        let _ = tx.send(progress.clone());
+        !cancel.load(Ordering::Relaxed)
+    });
+
+    progress.files_done = files_total;
+    progress.bytes_done = bytes_total;
+    match result {
+        Ok(()) => progress.operations.push(Operation::Create { path: dst }),
+        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+        Err(e) => progress.error = Some(format!("Failed to create {}: {}", dst.display(), e)),
+    }
+    let _ = tx.send(progress);
+}
+
+/// Unpack the archive at `path` into the new directory `dst`, reporting progress over `tx`
+/// like run_transfer. Setting `cancel` stops at the next file and removes `dst` again.
+pub fn run_extract(
+    manager: FileSystemManager,
+    path: PathBuf,
+    dst: PathBuf,
+    cancel: Arc<AtomicBool>,
+    tx: Sender<CopyProgress>,
+) {
+    let (files_total, bytes_total) = archive::Format::detect(&path)
+        .and_then(|format| archive::contents_size(&path, format).ok())
+        .unwrap_or_default();
+    let mut progress = CopyProgress { files_total, bytes_total, ..Default::default() };
+    let _ = tx.send(progress.clone());
+
+    let mut previous_size = None;
+    let result = manager.extract_archive(&path, &dst, &mut |file, size| {
+        if let Some(size) = previous_size.replace(size) {
+            progress.files_done += 1;
+            progress.bytes_done += size;
+        }
+        progress.current_file = file.to_path_buf();
+        let _ = tx.send(progress.clone());
+        !cancel.load(Ordering::Relaxed)
+    });
+
+    progress.files_done = files_total;
+    progress.bytes_done = bytes_total;
+    match result {
+        Ok(skipped) => {
+            progress.operations.push(Operation::Create { path: dst });
+            if skipped > 0 {
+                progress.error = Some(format!("Skipped {} entries that would have been written outside the folder", skipped));
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+        Err(e) => progress.error = Some(format!("Failed to extract {}: {}", path.display(), e)),
+    }
+    let _ = tx.send(progress);
+}
+
+/// Clear the way for an overwrite, returning the trash operation if the old entry can be restored.
+fn replace_destination(manager: &FileSystemManager, item: &TransferItem, use_trash: bool) -> std::io::Result<Option<Operation>> {
+    // Replacing an item with itself would destroy the source
+    if !item.replace || item.src == item.dst {
+        return Ok(None);
+    }
+    if use_trash {
+        let trashed = manager.trash(&item.dst)?;
+        Ok(Some(Operation::Trash { original: item.dst.clone(), trashed }))
+    } else {
+        manager.delete_recursive(&item.dst).map(|_| None)
+    }
+}
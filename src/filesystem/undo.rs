@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+use crate::filesystem::FileSystemManager;
+
+/// A file operation that has been carried out, recorded with enough detail to reverse it.
+/// Permanent deletes are never recorded since there is nothing left to bring back.
+#[derive(Debug, Clone)]
+pub enum Operation {
+    /// An entry was moved or renamed from `from` to `to`
+    Move { from: PathBuf, to: PathBuf },
+    /// An entry was moved to the trash and now lives at `trashed`
+    Trash { original: PathBuf, trashed: PathBuf },
+    /// A new entry was created, e.g. by pasting a copy
+    Create { path: PathBuf },
+}
+
+impl Operation {
+    /// Reverse the operation. Refuses to overwrite anything that has since taken the original place.
+    pub fn undo(&self, manager: &FileSystemManager, use_trash: bool) -> std::io::Result<()> {
+        match self {
+            Operation::Move { from, to } => {
+                if from.symlink_metadata().is_ok() {
+                    return Err(already_exists(from));
+                }
+                manager.move_entry(to, from)
+            }
+            Operation::Trash { original, trashed } => manager.restore_from_trash(trashed, original),
+            Operation::Create { path } => {
+                if use_trash {
+                    manager.trash(path).map(|_| ())
+                } else {
+                    manager.delete_recursive(path)
+                }
+            }
+        }
+    }
+}
+
+fn already_exists(path: &std::path::Path) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::AlreadyExists, format!("{} already exists", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_undo_move_and_create() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let manager = FileSystemManager::new(root);
+        fs::write(root.join("a.txt"), "a").unwrap();
+
+        manager.move_entry(&root.join("a.txt"), &root.join("b.txt")).unwrap();
+        let moved = Operation::Move { from: root.join("a.txt"), to: root.join("b.txt") };
+        moved.undo(&manager, false).unwrap();
+        assert!(root.join("a.txt").exists());
+        assert!(!root.join("b.txt").exists());
+
+        // Something new in the original place blocks the undo
+        manager.move_entry(&root.join("a.txt"), &root.join("b.txt")).unwrap();
+        fs::write(root.join("a.txt"), "new").unwrap();
+        assert!(moved.undo(&manager, false).is_err());
+        assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "a");
+
+        Operation::Create { path: root.join("b.txt") }.undo(&manager, false).unwrap();
+        assert!(!root.join("b.txt").exists());
+    }
+}
@@ -0,0 +1,5 @@
+//! The filesystem side of Xplore, usable without a terminal. The TUI in the binary is built
+//! on top of it, and the integration tests drive it directly.
+
+pub mod filesystem;
+pub mod metadata;
@@ -1,37 +1,68 @@
-mod metadata;
-mod filesystem;
 mod ui;
 mod config;
 
+use xplore_cli::{filesystem, metadata};
+
 use std::io;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
+use clap::Parser;
 use ratatui::{backend::CrosstermBackend, backend::Backend, Terminal};
 use crossterm::{
-    event::{self, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use crate::ui::app::App;
 
+/// How long the main loop waits for input while background work streams in; idle ticks
+/// follow the configured tick rate
+const BUSY_POLL: Duration = Duration::from_millis(16);
+
+#[derive(Parser)]
+#[command(name = "xplore", version, about)]
+struct Cli {
+    /// Directory to open, or a file to select in its parent directory
+    path: Option<PathBuf>,
+}
+
+/// Where to start: the directory to open and an entry to pre-select in it.
+fn resolve_start(path: &Path) -> io::Result<(PathBuf, Option<PathBuf>)> {
+    let path = filesystem::FileSystemManager::canonicalize(path)?;
+    if path.is_dir() {
+        return Ok((path, None));
+    }
+    let parent = path.parent().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("/"));
+    Ok((parent, Some(path)))
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    // Validate before touching the terminal so errors print normally
+    let start = match cli.path {
+        Some(path) => match resolve_start(&path) {
+            Ok(start) => Some(start),
+            Err(e) => {
+                eprintln!("xplore: {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
     // Setup terminal
+    install_panic_hook();
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Create app and run it
-    let res = run_app(&mut terminal).await;
+    let res = run_app(&mut terminal, start).await;
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-    )?;
-    terminal.show_cursor()?;
+    restore_terminal()?;
 
     if let Err(err) = res {
         println!("{:?}", err);
@@ -40,30 +71,113 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Leave raw mode and the alternate screen, giving the terminal back in a usable state.
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, crossterm::cursor::Show)
+}
+
+/// Restore the terminal before a panic message prints, so a crash doesn't leave it in raw
+/// mode. Panics in background tasks are caught by tokio while the UI keeps running, so only
+/// the main thread, which runs the UI, restores.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if std::thread::current().name() == Some("main") {
+            let _ = restore_terminal();
+        }
+        default_hook(info);
+    }));
+}
+
 async fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
+    start: Option<(PathBuf, Option<PathBuf>)>,
 ) -> Result<(), Box<dyn std::error::Error>> 
 where <B as Backend>::Error: 'static
 {
-    let mut app = App::new();
+    let (start_dir, select) = start.unzip();
+    let mut app = App::new(start_dir);
+    if let Some(path) = select.flatten() {
+        app.select_path(&path);
+    }
 
     loop {
-        terminal.draw(|f| ui::ui::render(f, &mut app))?;
-        
+        if app.dirty {
+            app.dirty = false;
+            terminal.draw(|f| ui::ui::render(f, &mut app))?;
+        }
+
         // Tick input from PTY
         app.tick();
 
-        if event::poll(Duration::from_millis(10))? {
-            if let Event::Key(key) = event::read()? {
-                let code_str = match key.code {
-                    KeyCode::Char(c) => c.to_string(),
-                    _ => String::new(),
-                };
-                if code_str == app.config.keybindings.quit {
-                    return Ok(());
-                }
-                app.on_key(key.code, key.modifiers);
+        // Wake up often while something streams in, otherwise just often enough for the
+        // polling in tick() and the status timeout
+        let idle = app.config.tick_rate();
+        let timeout = if app.is_busy() { BUSY_POLL.min(idle) } else { idle };
+        if event::poll(timeout)? {
+            match event::read()? {
+                Event::Key(key) => app.on_key(key.code, key.modifiers),
+                Event::Mouse(mouse) => app.on_mouse(mouse),
+                Event::Resize(..) => app.dirty = true,
+                _ => {}
             }
         }
+
+        if app.should_quit {
+            app.on_quit();
+            return Ok(());
+        }
+        if app.shell_requested {
+            app.shell_requested = false;
+            run_shell(terminal, &mut app)?;
+        }
+        if app.editor_requested {
+            app.editor_requested = false;
+            run_editor(terminal, &mut app)?;
+        }
     }
 }
+
+/// Give the terminal to `command` until it exits, then take it back.
+fn run_suspended<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut command: std::process::Command) -> Result<io::Result<std::process::ExitStatus>, Box<dyn std::error::Error>>
+where <B as Backend>::Error: 'static
+{
+    restore_terminal()?;
+
+    let status = command.status();
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+    Ok(status)
+}
+
+/// Hand the terminal to an interactive shell until it exits, then take it back and reload
+/// the listing in case the shell changed anything.
+fn run_shell<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<(), Box<dyn std::error::Error>>
+where <B as Backend>::Error: 'static
+{
+    if let Err(e) = run_suspended(terminal, app.shell_command_here())? {
+        app.error_message = Some(format!("Failed to start the shell: {}", e));
+    }
+    app.refresh();
+    Ok(())
+}
+
+/// Edit the description of app.edit_target in the user's $EDITOR.
+fn run_editor<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<(), Box<dyn std::error::Error>>
+where <B as Backend>::Error: 'static
+{
+    let (command, file) = match app.external_edit_command() {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            app.error_message = Some(format!("Failed to edit the description: {}", e));
+            return Ok(());
+        }
+    };
+    let status = run_suspended(terminal, command)?;
+    app.finish_external_edit(&file, status);
+    app.dirty = true;
+    Ok(())
+}
@@ -1,32 +1,228 @@
-use std::path::Path;
-use xattr;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 
 pub const XPLORE_DESC_KEY: &str = "user.xplore.description";
 
-/// Get the description from a file's extended attributes.
+/// Per-directory fallback file used when the filesystem has no user xattrs (FAT, exFAT, some network mounts).
+pub const SIDECAR_FILE_NAME: &str = ".xplore_descriptions.toml";
+
+/// Get the description from a file's extended attributes, falling back to the sidecar file.
 pub fn get_description<P: AsRef<Path>>(path: P) -> Option<String> {
+    let path = path.as_ref();
     match xattr::get(path, XPLORE_DESC_KEY) {
         Ok(Some(data)) => String::from_utf8(data).ok(),
-        _ => None,
+        _ => get_sidecar_description(path),
     }
 }
 
-/// Set the description in a file's extended attributes.
-pub fn set_description<P: AsRef<Path>>(path: P, description: &str) -> std::io::Result<()> {
-    xattr::set(path, XPLORE_DESC_KEY, description.as_bytes())
+/// When an entry last changed. Writing an xattr bumps the ctime but not the mtime, so Unix uses the former.
+type ChangeStamp = (i64, i64);
+
+/// Descriptions read so far, with the change stamp of the entry when they were read.
+type DescriptionCache = HashMap<PathBuf, (ChangeStamp, Option<String>)>;
+static DESCRIPTION_CACHE: OnceLock<Mutex<DescriptionCache>> = OnceLock::new();
+
+/// Bound on cached descriptions; a global search can touch millions of entries.
+const DESCRIPTION_CACHE_LIMIT: usize = 100_000;
+
+fn description_cache() -> std::sync::MutexGuard<'static, DescriptionCache> {
+    DESCRIPTION_CACHE.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner())
 }
 
-/// Clear the description from a file's extended attributes.
-pub fn clear_description<P: AsRef<Path>>(path: P) -> std::io::Result<()> {
-    xattr::remove(path, XPLORE_DESC_KEY)
+fn change_stamp(metadata: &fs::Metadata) -> ChangeStamp {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        (metadata.ctime(), metadata.ctime_nsec())
+    }
+    #[cfg(not(unix))]
+    {
+        metadata.modified().ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| (d.as_secs() as i64, d.subsec_nanos() as i64))
+            .unwrap_or_default()
+    }
 }
 
-/// Check if the target filesystem supports xattrs by attempting a dummy write or just checking the path.
-/// Note: Real check often requires attempting an operation or checking mount options.
-pub fn is_xattr_supported<P: AsRef<Path>>(_path: P) -> bool {
-    // In a real app, we might check the filesystem type, but xattr::get/set will return 
-    // ENOTSUP if not supported.
-    true // Simplified for now
+/// get_description, served from the cache while the entry is unchanged since the last read.
+/// `metadata` is the entry's own (followed) metadata, which the callers already have at hand.
+pub fn cached_description(path: &Path, metadata: &fs::Metadata) -> Option<String> {
+    let stamp = change_stamp(metadata);
+    if let Some((cached_stamp, description)) = description_cache().get(path)
+        && *cached_stamp == stamp
+    {
+        return description.clone();
+    }
+
+    let description = get_description(path);
+    let mut cache = description_cache();
+    if cache.len() >= DESCRIPTION_CACHE_LIMIT {
+        cache.clear();
+    }
+    cache.insert(path.to_path_buf(), (stamp, description.clone()));
+    description
+}
+
+/// Drop the cached description of `path` so the next read goes to disk.
+fn invalidate_description(path: &Path) {
+    description_cache().remove(path);
+}
+
+/// Set the description in a file's extended attributes, or in the sidecar file when xattrs are unsupported.
+pub fn set_description<P: AsRef<Path>>(path: P, description: &str) -> io::Result<()> {
+    let path = path.as_ref();
+    // A sidecar write doesn't touch the entry itself, so its change stamp can't be relied on
+    invalidate_description(path);
+    if !is_xattr_supported(path) {
+        return set_sidecar_description(path, Some(description));
+    }
+    match xattr::set(path, XPLORE_DESC_KEY, description.as_bytes()) {
+        Err(e) if is_unsupported(&e) => set_sidecar_description(path, Some(description)),
+        result => result,
+    }
+}
+
+/// Clear the description from a file's extended attributes and the sidecar file.
+pub fn clear_description<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    invalidate_description(path);
+    if get_sidecar_description(path).is_some() {
+        set_sidecar_description(path, None)?;
+    }
+    match xattr::remove(path, XPLORE_DESC_KEY) {
+        Err(e) if is_unsupported(&e) || xattr::get(path, XPLORE_DESC_KEY).ok().flatten().is_none() => Ok(()),
+        result => result,
+    }
+}
+
+/// Remove and return the sidecar description for `path`, if any, so it can follow a move.
+pub fn take_sidecar_description(path: &Path) -> Option<String> {
+    invalidate_description(path);
+    let description = get_sidecar_description(path)?;
+    set_sidecar_description(path, None).ok()?;
+    Some(description)
+}
+
+/// Check if the filesystem holding `path` supports user xattrs by probing a read.
+/// A missing attribute is fine; only an "operation not supported" error means no support.
+pub fn is_xattr_supported<P: AsRef<Path>>(path: P) -> bool {
+    if !xattr::SUPPORTED_PLATFORM {
+        return false;
+    }
+    match xattr::get(path, XPLORE_DESC_KEY) {
+        Err(e) => !is_unsupported(&e),
+        Ok(_) => true,
+    }
+}
+
+fn is_unsupported(err: &io::Error) -> bool {
+    if err.kind() == io::ErrorKind::Unsupported {
+        return true;
+    }
+    #[cfg(unix)]
+    {
+        matches!(err.raw_os_error(), Some(code) if code == libc::ENOTSUP || code == libc::EOPNOTSUPP)
+    }
+    #[cfg(not(unix))]
+    {
+        false
+    }
+}
+
+/// Sidecar location and lookup key for `path`: the parent directory's sidecar file keyed by file name.
+fn sidecar_location(path: &Path) -> Option<(PathBuf, String)> {
+    let parent = path.parent()?;
+    let name = path.file_name()?.to_string_lossy().to_string();
+    Some((parent.join(SIDECAR_FILE_NAME), name))
+}
+
+fn read_sidecar(sidecar: &Path) -> BTreeMap<String, String> {
+    fs::read_to_string(sidecar)
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn get_sidecar_description(path: &Path) -> Option<String> {
+    let (sidecar, name) = sidecar_location(path)?;
+    if !sidecar.exists() {
+        return None;
+    }
+    read_sidecar(&sidecar).remove(&name)
+}
+
+/// Insert or (with `None`) remove the sidecar entry for `path`. An emptied sidecar is deleted.
+fn set_sidecar_description(path: &Path, description: Option<&str>) -> io::Result<()> {
+    let (sidecar, name) = sidecar_location(path)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no parent directory"))?;
+    let mut entries = read_sidecar(&sidecar);
+    match description {
+        Some(desc) => {
+            entries.insert(name, desc.to_string());
+        }
+        None => {
+            entries.remove(&name);
+        }
+    }
+
+    if entries.is_empty() {
+        return match fs::remove_file(&sidecar) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    let content = toml::to_string_pretty(&entries).map_err(io::Error::other)?;
+    fs::write(sidecar, content)
+}
+
+/// Default name for a file written by export_descriptions.
+pub const EXPORT_FILE_NAME: &str = "xplore-descriptions.toml";
+
+/// Write every description below `root`, including its own, to `file` as a TOML table of
+/// "/"-separated paths relative to `root`. Symlinks aren't followed. Returns how many were written.
+pub fn export_descriptions(root: &Path, file: &Path) -> io::Result<usize> {
+    let mut entries = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(root).follow_links(false).into_iter().filter_map(|e| e.ok()) {
+        if entry.file_name() == SIDECAR_FILE_NAME {
+            continue;
+        }
+        let Some(description) = get_description(entry.path()) else { continue };
+        let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        let key = match relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>() {
+            parts if parts.is_empty() => ".".to_string(),
+            parts => parts.join("/"),
+        };
+        entries.insert(key, description);
+    }
+    let content = toml::to_string_pretty(&entries).map_err(io::Error::other)?;
+    fs::write(file, content)?;
+    Ok(entries.len())
+}
+
+/// Restore descriptions from a file written by export_descriptions onto the entries below
+/// `root`. Returns how many were set and how many named a path that doesn't exist here;
+/// paths that would leave `root` count as missing too.
+pub fn import_descriptions(root: &Path, file: &Path) -> io::Result<(usize, usize)> {
+    let content = fs::read_to_string(file)?;
+    let entries: BTreeMap<String, String> = toml::from_str(&content)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let (mut imported, mut missing) = (0, 0);
+    for (key, description) in entries {
+        let relative = Path::new(&key);
+        let inside = relative.components()
+            .all(|c| matches!(c, std::path::Component::Normal(_) | std::path::Component::CurDir));
+        let path = root.join(relative);
+        if !inside || path.symlink_metadata().is_err() {
+            missing += 1;
+            continue;
+        }
+        set_description(&path, &description)?;
+        imported += 1;
+    }
+    Ok((imported, missing))
 }
 
 #[cfg(test)]
@@ -50,4 +246,58 @@ mod tests {
             println!("xattr not supported, skipping test");
         }
     }
+
+    #[test]
+    fn test_sidecar_description() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("notes.txt");
+        File::create(&file_path).unwrap();
+
+        set_sidecar_description(&file_path, Some("Stored beside the file")).unwrap();
+        assert!(dir.path().join(SIDECAR_FILE_NAME).exists());
+        assert_eq!(get_sidecar_description(&file_path), Some("Stored beside the file".to_string()));
+
+        set_sidecar_description(&file_path, None).unwrap();
+        assert_eq!(get_sidecar_description(&file_path), None);
+        assert!(!dir.path().join(SIDECAR_FILE_NAME).exists());
+    }
+
+    #[test]
+    fn test_cached_description_invalidation() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("cached.txt");
+        File::create(&file_path).unwrap();
+        let meta = fs::metadata(&file_path).unwrap();
+        assert_eq!(cached_description(&file_path, &meta), None);
+
+        // Sidecar writes leave the file's stamp alone, so only invalidation can refresh the cache
+        set_description(&file_path, "first").unwrap();
+        let meta = fs::metadata(&file_path).unwrap();
+        assert_eq!(cached_description(&file_path, &meta), Some("first".to_string()));
+        clear_description(&file_path).unwrap();
+        let meta = fs::metadata(&file_path).unwrap();
+        assert_eq!(cached_description(&file_path, &meta), None);
+    }
+
+    #[test]
+    fn test_export_and_import_descriptions() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        File::create(root.join("sub/a.txt")).unwrap();
+        set_description(&root, "the root").unwrap();
+        set_description(root.join("sub/a.txt"), "line one\nline two").unwrap();
+
+        let file = dir.path().join(EXPORT_FILE_NAME);
+        assert_eq!(export_descriptions(&root, &file).unwrap(), 2);
+        let exported = fs::read_to_string(&file).unwrap();
+        assert!(exported.contains("\"sub/a.txt\""));
+
+        clear_description(&root).unwrap();
+        clear_description(root.join("sub/a.txt")).unwrap();
+        fs::write(&file, format!("{}\n\"../escape\" = \"x\"\ngone = \"x\"\n", exported)).unwrap();
+        assert_eq!(import_descriptions(&root, &file).unwrap(), (2, 2));
+        assert_eq!(get_description(&root).as_deref(), Some("the root"));
+        assert_eq!(get_description(root.join("sub/a.txt")).as_deref(), Some("line one\nline two"));
+    }
 }
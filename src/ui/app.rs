@@ -1,20 +1,40 @@
-use crate::filesystem::{FileSystemManager, FileEntry};
-use crate::config::Config;
-use crossterm::event::KeyCode;
+use crate::filesystem::archive;
+use crate::filesystem::complete::{resolve_input, Completion};
+use crate::filesystem::rename::{execute_batch_rename, plan_batch_rename};
+use crate::filesystem::transfer;
+use crate::filesystem::{compare_depth, compare_entries, sort_entries, ContentMatch, CopyProgress, FileSystemManager, FileEntry, FileKind, Matcher, NameFilter, Operation, SearchMode, SortKey, SortOrder, TransferItem, TransferKind};
+use crate::config::{Config, Keybindings, RecentDir, SearchScope, SearchSort};
+use crossterm::event::{KeyCode, MouseButton, MouseEvent, MouseEventKind};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use unicode_width::UnicodeWidthStr;
 
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
 
 #[derive(Clone)]
 pub enum PromptType {
     NewFolder,
-    DeleteConfirmation,
+    NewFile,
+    /// `typed` is what the user has to type to go ahead, asked for when folders are involved
+    DeleteConfirmation { permanent: bool, typed: Option<String> },
+    Rename,
+    PasteConflict,
+    OpenWith,
+    GoToPath,
+    ResetKeybindings,
+    QuitConfirmation,
+    Symlink,
+    Archive,
+    Filter,
 }
 
 #[derive(Clone)]
 pub enum ClipboardMode {
     Copy,
     Cut,
+    /// Paste as hard links to the originals
+    Link,
 }
 
 #[derive(Clone)]
@@ -23,6 +43,34 @@ pub struct Clipboard {
     pub mode: ClipboardMode,
 }
 
+/// A paste being planned. Items are checked in order and planning pauses on the first
+/// destination that already exists until the user resolves it.
+pub struct PendingPaste {
+    pub destination: PathBuf,
+    pub mode: ClipboardMode,
+    pub items: VecDeque<PathBuf>,
+    pub planned: Vec<TransferItem>,
+}
+
+#[derive(Clone, Copy)]
+pub enum ConflictChoice {
+    Skip,
+    Overwrite,
+    Rename,
+}
+
+impl ConflictChoice {
+    pub const ALL: [ConflictChoice; 3] = [ConflictChoice::Skip, ConflictChoice::Overwrite, ConflictChoice::Rename];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConflictChoice::Skip => "Skip",
+            ConflictChoice::Overwrite => "Overwrite",
+            ConflictChoice::Rename => "Rename",
+        }
+    }
+}
+
 pub enum InputMode {
     Normal,
     Editing,
@@ -31,14 +79,66 @@ pub enum InputMode {
     Remapping(String),
     Prompt(PromptType),
     Help,
+    Bookmarks,
+    /// Picking one of the recently visited directories
+    RecentDirs,
+    OpenWith,
+    /// Typing a shell command to run on the selected entries
+    Command,
+    /// Showing the output of the last shell command
+    CommandOutput,
+    /// Picking an ancestor directory from the path bar
+    Breadcrumbs,
+    /// Typing a rename pattern for the selected entries
+    BatchRename,
+    /// Editing the permission bits of the selected entry
+    Permissions,
+}
+
+/// A listing of a large directory running in the background.
+pub struct Listing {
+    pub dir: PathBuf,
+    pub total: usize,
+    rx: Receiver<Result<Vec<FileEntry>, String>>,
+    /// Entries of a reload, swapped in once complete so the list doesn't empty out meanwhile
+    staged: Option<Vec<FileEntry>>,
+    /// Another reload was asked for while this one ran
+    stale: bool,
 }
 
+/// Browsing state of one tab. The active tab lives in the App's own fields and is
+/// written back here whenever another tab is opened or switched to.
+#[derive(Clone)]
+pub struct Tab {
+    pub manager: FileSystemManager,
+    pub selected_index: usize,
+    pub list_state: ListState,
+}
 
+use ratatui::layout::Rect;
 use ratatui::widgets::ListState;
 use portable_pty::{CommandBuilder, NativePtySystem, PtyPair, PtySize, PtySystem};
-use std::sync::mpsc::{channel, Receiver};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::Arc;
 use std::io::{Read, Write};
 use std::thread;
+use std::time::{Duration, Instant};
+
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+/// Bytes read for each preview line, generous enough for source code line lengths
+const PREVIEW_BYTES_PER_LINE: usize = 160;
+/// Directories with more entries than this are listed in the background
+const LARGE_DIR_ENTRIES: usize = 2000;
+const LISTING_CHUNK: usize = 500;
+const UNDO_LIMIT: usize = 50;
+/// Pause after which a type-ahead jump ends and letters go back to their bindings
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long a status message stays in the footer
+const STATUS_TIMEOUT: Duration = Duration::from_secs(3);
+const SEARCH_HISTORY_LIMIT: usize = 50;
+/// Percentage points the list pane grows or shrinks by per key press
+const LIST_WIDTH_STEP: i32 = 5;
 
 pub struct App {
     pub manager: FileSystemManager,
@@ -47,17 +147,134 @@ pub struct App {
     pub selected_index: usize,
     pub input_mode: InputMode,
     pub edit_buffer: String,
+    /// Byte offset of the cursor within edit_buffer, always on a char boundary
+    pub edit_cursor: usize,
+    /// First line and column of edit_buffer shown in the editor
+    pub edit_scroll: (usize, usize),
+    /// Where the description being edited is saved
+    pub edit_target: PathBuf,
     pub search_query: String,
+    /// Earlier queries, oldest first, without repeats
+    pub search_history: Vec<String>,
+    /// Which history entry Up/Down last put in the search box
+    search_history_index: Option<usize>,
+    /// The search box holds the last query as a suggestion; typing replaces it
+    pub search_prefilled: bool,
+    pub search_mode: SearchMode,
+    /// Set when the query doesn't compile in regex mode; nothing is shown until it's fixed
+    pub search_invalid: bool,
+    /// Glob filter on the listing; unlike the search query it stays across directories
+    pub name_filter: Option<NameFilter>,
     pub config: Config,
     pub config_index: usize,
     pub error_message: Option<String>,
+    /// Confirmation of the last finished action and when it was shown; cleared after STATUS_TIMEOUT
+    pub status_message: Option<(String, Instant)>,
+    /// Why the current directory couldn't be listed, so an empty pane isn't mistaken for an empty directory
+    pub listing_error: Option<String>,
+    /// Total and available bytes where the current directory lives, updated with each listing
+    pub disk_usage: Option<(u64, u64)>,
+    pub listing: Option<Listing>,
+    /// Directory that all_entries belongs to
+    listed_dir: Option<PathBuf>,
+    /// Entry to select once a background listing has produced it
+    pending_select: Option<PathBuf>,
+    /// Selected entry and scroll offset of each directory left, restored on coming back
+    dir_positions: HashMap<PathBuf, (PathBuf, usize)>,
     pub is_searching: bool,
+    pub showing_search_results: bool,
+    /// Where the deep search being shown started
+    search_results_root: PathBuf,
+    pub search_rx: Option<Receiver<(FileEntry, Option<ContentMatch>)>>,
+    /// The query of a fuzzy deep search, to rank its results by
+    search_ranking: Option<Matcher>,
+    pub search_scope: SearchScope,
+    /// Also match file contents during a deep search
+    pub search_contents: bool,
+    /// Matching line for deep search results that only matched by content
+    pub content_matches: HashMap<PathBuf, ContentMatch>,
+    pub search_cancel: Option<Arc<AtomicBool>>,
+    /// The last deep search stopped at max_search_results, so there may be more matches
+    pub search_truncated: bool,
     pub selected_paths: HashSet<PathBuf>,
+    /// Where a range selection started; while set, moving the cursor extends the range
+    pub selection_anchor: Option<usize>,
+    /// What was selected before the range started, so moving back shrinks the range again
+    range_base: HashSet<PathBuf>,
     pub clipboard: Option<Clipboard>,
+    pub pending_paste: Option<PendingPaste>,
+    pub copy_progress: Option<CopyProgress>,
+    pub copy_rx: Option<Receiver<CopyProgress>>,
+    /// What the running background transfer is doing, for its progress bar
+    pub transfer_title: &'static str,
+    /// Status message for when the running transfer finishes without an error
+    transfer_done: String,
+    transfer_cancel: Option<Arc<AtomicBool>>,
+    /// Finished actions, newest last, each as the operations it was made of
+    pub undo_stack: Vec<Vec<Operation>>,
+    /// Operations of the paste that is still running
+    transfer_operations: Vec<Operation>,
     pub prompt_buffer: String,
     pub list_state: ListState,
     pub list_height: u16,
+    pub list_area: Rect,
+    /// The folder/file divider is the top row, just above list_state's first entry
+    pub separator_at_top: bool,
+    pub last_click: Option<(usize, Instant)>,
     pub prompt_index: usize,
+    pub bookmark_index: usize,
+    pub recent_index: usize,
+    pub open_with_index: usize,
+    pub breadcrumb_index: usize,
+    /// Repeat count typed before a movement key, vim style
+    pub pending_count: Option<usize>,
+    /// First key of a two-key sequence like "g g", waiting for the second
+    pub pending_keys: Option<String>,
+    /// Characters typed to jump to a name and when the last one came
+    type_ahead: Option<(String, Instant)>,
+    /// The key being captured in Remapping mode is added to the action's keys instead of replacing them
+    pub remap_adding: bool,
+    /// Entries being batch renamed, in the order `{n}` counts them
+    pub batch_rename_paths: Vec<PathBuf>,
+    pub permission_target: Option<PathBuf>,
+    /// Entry the link being named in the Symlink prompt will point to
+    pub symlink_target: Option<PathBuf>,
+    /// Entries going into the archive being named in the Archive prompt
+    pub archive_paths: Vec<PathBuf>,
+    /// Mode being edited; only the low nine bits are shown, the rest are kept as they were
+    pub permission_mode: u32,
+    /// Which of the nine rwx bits is highlighted, owner read first
+    pub permission_cursor: usize,
+    completion: Completion,
+    /// Screen area of each path bar segment, for mouse clicks
+    pub breadcrumb_areas: Vec<(Rect, PathBuf)>,
+    /// Output of the last shell command; None while it is still running
+    pub command_output: Option<String>,
+    pub command_rx: Option<Receiver<String>>,
+    /// Process id of the running shell command, so quitting can stop it
+    command_pid: Option<u32>,
+    pub command_scroll: u16,
+    /// Kept alive because on X11 the copied text is only served while the handle exists
+    pub system_clipboard: Option<arboard::Clipboard>,
+    pub tabs: Vec<Tab>,
+    pub active_tab: usize,
+    /// Two side-by-side listings; the App's own fields always hold the focused pane
+    pub dual_pane: bool,
+    pub other_pane: Tab,
+    /// The details panel is shown next to the list; without it the list takes the full width
+    pub show_details: bool,
+    pub other_entries: Vec<FileEntry>,
+    pub active_pane_left: bool,
+    pub show_hidden: bool,
+    pub sort_key: SortKey,
+    pub sort_order: SortOrder,
+    pub dir_sizes: HashMap<PathBuf, u64>,
+    /// Directory whose size is being summed in the background
+    pub sizing_path: Option<PathBuf>,
+    pub size_rx: Option<Receiver<Option<u64>>>,
+    size_cancel: Option<Arc<AtomicBool>>,
+    /// Preview of the selected file, keyed by path and mtime so it is only re-read on change
+    pub preview_cache: Option<(PathBuf, chrono::DateTime<chrono::Local>, Option<String>)>,
     // Terminal state (PTY)
     pub is_terminal_open: bool,
     pub terminal_focused: bool,
@@ -68,6 +285,27 @@ pub struct App {
     pub shell_id: u32,
     pub last_synced_path: PathBuf,
     pub tick_count: u64,
+    /// Something changed since the last frame; the main loop only redraws when this is set
+    pub dirty: bool,
+    /// Watches the current directory; None when watching isn't available and tick() polls instead
+    watcher: Option<RecommendedWatcher>,
+    watch_rx: Option<Receiver<notify::Result<notify::Event>>>,
+    watched_dir: Option<PathBuf>,
+    /// Quit as soon as the cancelled paste has stopped
+    pub quit_pending: bool,
+    /// Set once the app is done; the main loop exits on seeing it
+    pub should_quit: bool,
+    /// Asks the main loop to suspend the TUI and run shell_command_here()
+    pub shell_requested: bool,
+    /// Asks the main loop to suspend the TUI and edit edit_target's description in $EDITOR
+    pub editor_requested: bool,
+}
+
+/// The command line of the user's preferred editor, from $VISUAL or $EDITOR.
+fn external_editor() -> Option<String> {
+    ["VISUAL", "EDITOR"].iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
 }
 
 fn find_shell_pid(parent_pid: u32) -> Option<u32> {
@@ -83,21 +321,21 @@ fn find_shell_pid(parent_pid: u32) -> Option<u32> {
         
         let path = entry.path();
         // Read stat for PPID
-        if let Ok(stat) = std::fs::read_to_string(path.join("stat")) {
-            if let Some(r_paren) = stat.rfind(')') {
-                let rest = &stat[r_paren+2..];
-                let parts: Vec<&str> = rest.split_whitespace().collect();
-                if parts.len() > 1 {
-                    if let Ok(ppid) = parts[1].parse::<u32>() {
-                        // Read comm for Name
-                        let name = if let Ok(comm) = std::fs::read_to_string(path.join("comm")) {
-                            comm.trim().to_string()
-                        } else {
-                            String::new()
-                        };
-                        process_map.insert(pid, (ppid, name));
-                    }
-                }
+        if let Ok(stat) = std::fs::read_to_string(path.join("stat"))
+            && let Some(r_paren) = stat.rfind(')')
+        {
+            let rest = &stat[r_paren+2..];
+            let parts: Vec<&str> = rest.split_whitespace().collect();
+            if parts.len() > 1
+                && let Ok(ppid) = parts[1].parse::<u32>()
+            {
+                // Read comm for Name
+                let name = if let Ok(comm) = std::fs::read_to_string(path.join("comm")) {
+                    comm.trim().to_string()
+                } else {
+                    String::new()
+                };
+                process_map.insert(pid, (ppid, name));
             }
         }
     }
@@ -139,12 +377,95 @@ fn find_shell_pid(parent_pid: u32) -> Option<u32> {
     None
 }
 
+/// Quote a path for the platform shell so spaces and metacharacters survive substitution.
+fn shell_quote(path: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", path)
+    } else {
+        format!("'{}'", path.replace('\'', "'\\''"))
+    }
+}
+
+/// Deleting a folder tree takes typing its name, or DELETE when there are several targets,
+/// instead of a single key. Links to folders only remove the link and don't count.
+fn delete_confirmation_text(targets: &[PathBuf]) -> Option<String> {
+    let has_dir = targets.iter().any(|p| p.symlink_metadata().is_ok_and(|m| m.is_dir()));
+    match targets {
+        _ if !has_dir => None,
+        [dir] => Some(dir.file_name().unwrap_or(dir.as_os_str()).to_string_lossy().to_string()),
+        _ => Some("DELETE".to_string()),
+    }
+}
+
+/// "1 item", "3 items" and so on.
+fn count_label(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+/// Dotfiles are hidden, but the "." and ".." navigation entries never are.
+fn is_hidden(name: &str) -> bool {
+    name.starts_with('.') && name != "." && name != ".."
+}
+
+fn prev_char_boundary(text: &str, cursor: usize) -> usize {
+    text[..cursor].chars().next_back().map_or(cursor, |c| cursor - c.len_utf8())
+}
+
+fn next_char_boundary(text: &str, cursor: usize) -> usize {
+    text[cursor..].chars().next().map_or(cursor, |c| cursor + c.len_utf8())
+}
+
+fn line_start(text: &str, cursor: usize) -> usize {
+    text[..cursor].rfind('\n').map_or(0, |i| i + 1)
+}
+
+fn line_end(text: &str, cursor: usize) -> usize {
+    text[cursor..].find('\n').map_or(text.len(), |i| cursor + i)
+}
+
+/// Move the cursor to the previous or next line, keeping its column where the line is long enough.
+fn move_vertically(text: &str, cursor: usize, up: bool) -> usize {
+    let start = line_start(text, cursor);
+    let column = text[start..cursor].chars().count();
+    let target = if up {
+        if start == 0 {
+            return cursor;
+        }
+        line_start(text, start - 1)
+    } else {
+        let end = line_end(text, cursor);
+        if end == text.len() {
+            return cursor;
+        }
+        end + 1
+    };
+    let target_end = line_end(text, target);
+    text[target..target_end]
+        .char_indices()
+        .nth(column)
+        .map_or(target_end, |(i, _)| target + i)
+}
+
 impl App {
-    pub fn new() -> Self {
-        let manager = FileSystemManager::new(".");
-        let config = Config::load();
+    /// Open `start_dir` if given, otherwise the directory of the last session, otherwise ".".
+    pub fn new(start_dir: Option<PathBuf>) -> Self {
+        Self::with_config(start_dir, Config::load())
+    }
+
+    /// Like new(), but with `config` in place of the one saved on disk.
+    fn with_config(start_dir: Option<PathBuf>, config: Config) -> Self {
+        let start_dir = start_dir
+            .or_else(|| config.last_dir.clone().filter(|dir| dir.is_dir()))
+            .unwrap_or_else(|| PathBuf::from("."));
+        let manager = FileSystemManager::new(start_dir);
+        let search_scope = config.search_scope;
         
         let current_path = manager.current_path().to_path_buf();
+        let tabs = vec![Tab {
+            manager: manager.clone(),
+            selected_index: 0,
+            list_state: ListState::default(),
+        }];
 
         let mut app = Self {
             manager,
@@ -153,17 +474,91 @@ impl App {
             selected_index: 0,
             input_mode: InputMode::Normal,
             edit_buffer: String::new(),
+            edit_cursor: 0,
+            edit_scroll: (0, 0),
+            edit_target: PathBuf::new(),
             search_query: String::new(),
+            search_history: Vec::new(),
+            search_history_index: None,
+            search_prefilled: false,
+            search_mode: SearchMode::Substring,
+            search_invalid: false,
+            name_filter: None,
             config,
             config_index: 0,
             error_message: None,
+            status_message: None,
+            listing_error: None,
+            disk_usage: None,
+            listing: None,
+            listed_dir: None,
+            pending_select: None,
+            dir_positions: HashMap::new(),
             is_searching: false,
+            showing_search_results: false,
+            search_results_root: PathBuf::new(),
+            search_rx: None,
+            search_ranking: None,
+            search_scope,
+            search_contents: false,
+            content_matches: HashMap::new(),
+            search_cancel: None,
+            search_truncated: false,
             selected_paths: HashSet::new(),
+            selection_anchor: None,
+            range_base: HashSet::new(),
             clipboard: None,
+            pending_paste: None,
+            copy_progress: None,
+            copy_rx: None,
+            transfer_title: " Pasting ",
+            transfer_done: String::new(),
+            transfer_cancel: None,
+            undo_stack: Vec::new(),
+            transfer_operations: Vec::new(),
             prompt_buffer: String::new(),
             list_state: ListState::default(),
             list_height: 0,
+            list_area: Rect::default(),
+            separator_at_top: false,
+            last_click: None,
             prompt_index: 0,
+            bookmark_index: 0,
+            recent_index: 0,
+            open_with_index: 0,
+            breadcrumb_index: 0,
+            pending_count: None,
+            pending_keys: None,
+            type_ahead: None,
+            remap_adding: false,
+            batch_rename_paths: Vec::new(),
+            permission_target: None,
+            symlink_target: None,
+            archive_paths: Vec::new(),
+            permission_mode: 0,
+            permission_cursor: 0,
+            completion: Completion::default(),
+            breadcrumb_areas: Vec::new(),
+            command_output: None,
+            command_rx: None,
+            command_pid: None,
+            command_scroll: 0,
+            system_clipboard: None,
+            other_pane: tabs[0].clone(),
+            tabs,
+            active_tab: 0,
+            dual_pane: false,
+            show_details: true,
+            other_entries: Vec::new(),
+            active_pane_left: true,
+            show_hidden: false,
+            sort_key: SortKey::Name,
+            sort_order: SortOrder::Asc,
+            dir_sizes: HashMap::new(),
+            sizing_path: None,
+            size_rx: None,
+            size_cancel: None,
+            preview_cache: None,
             // Terminal state
             is_terminal_open: false, 
             terminal_focused: false,
@@ -174,8 +569,18 @@ impl App {
             shell_id: 0, 
             last_synced_path: current_path,
             tick_count: 0,
+            dirty: true,
+            watcher: None,
+            watch_rx: None,
+            watched_dir: None,
+            quit_pending: false,
+            should_quit: false,
+            shell_requested: false,
+            editor_requested: false,
         };
         app.refresh();
+        app.remember_dir();
+        app.watch_current_dir();
         app
     }
 
@@ -224,23 +629,435 @@ impl App {
         self.selected_paths.contains(path)
     }
 
+    /// How many entries are selected and their total size. Folders only count once their size
+    /// has been calculated; the last value is how many are still left out.
+    pub fn selection_summary(&self) -> (usize, u64, usize) {
+        let listed: HashMap<&PathBuf, &FileEntry> = self.all_entries.iter().map(|e| (&e.path, e)).collect();
+        let mut total = 0;
+        let mut uncounted = 0;
+        for path in &self.selected_paths {
+            let (is_dir, size) = match listed.get(path) {
+                Some(entry) => (entry.is_dir, entry.size),
+                // Selected before leaving the directory
+                None => match path.symlink_metadata() {
+                    Ok(metadata) => (metadata.is_dir(), metadata.len()),
+                    Err(_) => continue,
+                },
+            };
+            if !is_dir {
+                total += size;
+            } else if let Some(size) = self.dir_sizes.get(path) {
+                total += size;
+            } else {
+                uncounted += 1;
+            }
+        }
+        (self.selected_paths.len(), total, uncounted)
+    }
+
+    /// Reload the current directory. The selection stays on the same entry when it is still
+    /// there, and otherwise keeps its position clamped to the new list.
     pub fn refresh(&mut self) {
-        self.all_entries = self.manager.list_directory().unwrap_or_default();
+        self.dirty = true;
+        let selected = self.filtered_entries.get(self.selected_index)
+            .map(|e| e.path.clone())
+            .filter(|path| path.parent() == Some(self.manager.current_path()));
+        self.disk_usage = self.manager.disk_usage();
+        match self.manager.entry_count() {
+            Ok(total) if total > LARGE_DIR_ENTRIES => self.start_listing(total),
+            _ => {
+                self.listing = None;
+                self.listed_dir = Some(self.manager.current_path().to_path_buf());
+                match self.manager.list_directory() {
+                    Ok(entries) => {
+                        self.all_entries = entries;
+                        self.listing_error = None;
+                    }
+                    Err(e) => {
+                        self.all_entries.clear();
+                        self.listing_error = Some(e.to_string());
+                    }
+                }
+            }
+        }
+        if self.dual_pane {
+            self.refresh_other_pane();
+        }
+        self.apply_sort();
         self.apply_filter();
+        if let Some(path) = selected {
+            self.select_path(&path);
+        }
+    }
+
+    /// List a large directory on a blocking task; tick() collects the chunks as they arrive.
+    fn start_listing(&mut self, total: usize) {
+        let dir = self.manager.current_path().to_path_buf();
+        if let Some(listing) = &mut self.listing
+            && listing.dir == dir
+        {
+            listing.stale = true;
+            return;
+        }
+
+        // A fresh directory fills in as it loads; a reload keeps showing the old entries until done
+        let staged = if self.listed_dir.as_ref() == Some(&dir) {
+            Some(Vec::new())
+        } else {
+            self.all_entries.clear();
+            None
+        };
+        self.listed_dir = Some(dir.clone());
+        self.listing_error = None;
+
+        let manager = self.manager.clone();
+        let (tx, rx) = channel();
+        tokio::task::spawn_blocking(move || {
+            // Sending fails once the listing is dropped, e.g. after navigating away
+            let result = manager.list_directory_chunked(LISTING_CHUNK, |chunk| tx.send(Ok(chunk)).is_ok());
+            if let Err(e) = result {
+                let _ = tx.send(Err(e.to_string()));
+            }
+        });
+        self.listing = Some(Listing { dir, total, rx, staged, stale: false });
+    }
+
+    /// Why the list shows no entries besides "." and "..", for a placeholder in their place.
+    /// None while the list has entries or is still filling up.
+    pub fn empty_list_message(&self) -> Option<String> {
+        let is_real = |e: &FileEntry| e.name != "." && e.name != "..";
+        if self.listing.is_some() || self.is_searching || self.filtered_entries.iter().any(is_real) {
+            return None;
+        }
+        if let Some(err) = &self.listing_error {
+            // "Permission denied (os error 13)" reads better without the code
+            return Some(err.split(" (os error").next().unwrap_or(err).to_string());
+        }
+        if !self.search_query.is_empty() {
+            return Some(format!("No matches for '{}'", self.search_query));
+        }
+        if self.showing_search_results {
+            return Some("No matches".to_string());
+        }
+        if let Some(filter) = &self.name_filter
+            && self.all_entries.iter().any(is_real)
+        {
+            return Some(format!("No matches for '{}'", filter.pattern()));
+        }
+        if self.all_entries.iter().any(is_real) {
+            return Some("Only hidden files".to_string());
+        }
+        Some("Empty directory".to_string())
+    }
+
+    /// Entries loaded so far and the expected total while a background listing runs.
+    pub fn listing_progress(&self) -> Option<(usize, usize)> {
+        self.listing.as_ref().map(|listing| {
+            let loaded = listing.staged.as_ref().unwrap_or(&self.all_entries).len();
+            (loaded, listing.total)
+        })
+    }
+
+    fn poll_listing(&mut self) {
+        let Some(listing) = &mut self.listing else { return };
+        let mut changed = false;
+        let finished = loop {
+            match listing.rx.try_recv() {
+                Ok(Ok(chunk)) => match &mut listing.staged {
+                    Some(staged) => staged.extend(chunk),
+                    None => {
+                        self.all_entries.extend(chunk);
+                        changed = true;
+                    }
+                },
+                Ok(Err(e)) => {
+                    self.all_entries.clear();
+                    self.listing_error = Some(e);
+                    listing.staged = None;
+                    changed = true;
+                }
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        if finished && let Some(listing) = self.listing.take() {
+            if let Some(staged) = listing.staged {
+                self.all_entries = staged;
+            }
+            changed = true;
+            if listing.stale {
+                self.refresh();
+            }
+        }
+        if changed {
+            let selected = self.pending_select.clone()
+                .or_else(|| self.filtered_entries.get(self.selected_index).map(|e| e.path.clone()));
+            self.apply_sort();
+            self.apply_filter();
+            if let Some(path) = selected {
+                self.select_path(&path);
+            }
+            if self.listing.is_none() {
+                self.pending_select = None;
+            }
+        }
+    }
+
+    /// Point the watcher at the current directory instead of the one it watched before.
+    /// Falls back to polling in tick() when the directory can't be watched.
+    fn watch_current_dir(&mut self) {
+        let dir = self.manager.current_path().to_path_buf();
+        let previous = self.watched_dir.replace(dir.clone());
+        if let Some(watcher) = &mut self.watcher {
+            if let Some(old) = previous {
+                let _ = watcher.unwatch(&old);
+            }
+            if watcher.watch(&dir, RecursiveMode::NonRecursive).is_ok() {
+                return;
+            }
+        } else {
+            let (tx, rx) = channel();
+            if let Ok(mut watcher) = notify::recommended_watcher(tx)
+                && watcher.watch(&dir, RecursiveMode::NonRecursive).is_ok()
+            {
+                self.watcher = Some(watcher);
+                self.watch_rx = Some(rx);
+                return;
+            }
+        }
+        self.watcher = None;
+        self.watch_rx = None;
+    }
+
+    /// Text preview of the selected file, read once per selection and cached.
+    pub fn selected_preview(&mut self) -> Option<&str> {
+        let entry = self.filtered_entries.get(self.selected_index)?;
+        if entry.kind != FileKind::File {
+            return None;
+        }
+        let is_cached = matches!(&self.preview_cache, Some((path, mtime, _)) if *path == entry.path && *mtime == entry.mod_time);
+        if !is_cached {
+            let max_bytes = self.config.preview_lines.saturating_mul(PREVIEW_BYTES_PER_LINE);
+            let preview = self.manager.read_preview(&entry.path, max_bytes);
+            self.preview_cache = Some((entry.path.clone(), entry.mod_time, preview));
+        }
+        self.preview_cache.as_ref().and_then(|(_, _, preview)| preview.as_deref())
+    }
+
+    pub fn apply_sort(&mut self) {
+        sort_entries(&mut self.all_entries, self.sort_key, self.sort_order, self.config.dirs_first);
+        sort_entries(&mut self.other_entries, self.sort_key, self.sort_order, self.config.dirs_first);
+        if self.showing_search_results {
+            let selected = self.filtered_entries.get(self.selected_index).map(|e| e.path.clone());
+            let mut results = std::mem::take(&mut self.filtered_entries);
+            results.sort_by(|a, b| self.compare_search_results(a, b));
+            self.filtered_entries = results;
+            if let Some(path) = selected {
+                self.select_path(&path);
+            }
+        }
+    }
+
+    /// The order of two deep search results under the search_sort setting.
+    fn compare_search_results(&self, a: &FileEntry, b: &FileEntry) -> std::cmp::Ordering {
+        if let Some(matcher) = &self.search_ranking {
+            let score = |e: &FileEntry| matcher.entry_score(&e.name, e.description.as_deref());
+            let by_score = score(b).cmp(&score(a));
+            if by_score.is_ne() {
+                return by_score;
+            }
+        }
+        match self.config.search_sort {
+            SearchSort::Listing => compare_entries(a, b, self.sort_key, self.sort_order, self.config.dirs_first),
+            SearchSort::Depth => compare_depth(a, b),
+            SearchSort::Found => std::cmp::Ordering::Equal,
+        }
+    }
+
+    /// Reload the unfocused pane with the same sort and hidden-file settings as the focused one.
+    fn refresh_other_pane(&mut self) {
+        self.dirty = true;
+        let mut entries = self.other_pane.manager.list_directory().unwrap_or_default();
+        let show_hidden = self.show_hidden;
+        entries.retain(|e| show_hidden || !is_hidden(&e.name));
+        sort_entries(&mut entries, self.sort_key, self.sort_order, self.config.dirs_first);
+        self.other_entries = entries;
+        let index = self.other_pane.selected_index.min(self.other_entries.len().saturating_sub(1));
+        self.other_pane.selected_index = index;
+        self.other_pane.list_state.select(Some(index));
+    }
+
+    fn toggle_dual_pane(&mut self) {
+        self.dual_pane = !self.dual_pane;
+        if self.dual_pane {
+            self.refresh_other_pane();
+        } else {
+            self.active_pane_left = true;
+        }
+    }
+
+    /// Move focus to the other pane by swapping its state into the App's fields.
+    fn switch_pane(&mut self) {
+        if !self.dual_pane {
+            return;
+        }
+        let focused = self.current_tab();
+        let other = std::mem::replace(&mut self.other_pane, focused);
+        self.manager = other.manager;
+        self.active_pane_left = !self.active_pane_left;
+        self.clear_selection_if_needed();
+        self.search_query.clear();
+        self.leave_search_results();
+        self.refresh();
+        self.selected_index = other.selected_index.min(self.filtered_entries.len().saturating_sub(1));
+        self.list_state = other.list_state;
+        self.list_state.select(Some(self.selected_index));
+    }
+
+    /// Where a paste lands: the unfocused pane in dual-pane mode, otherwise the current directory.
+    fn paste_destination(&self) -> PathBuf {
+        if self.dual_pane {
+            self.other_pane.manager.current_path().to_path_buf()
+        } else {
+            self.manager.current_path().to_path_buf()
+        }
+    }
+
+    pub fn sort_title(&self) -> String {
+        format!(
+            "{} {}{}",
+            self.sort_key.label(),
+            self.sort_order.arrow(),
+            if self.config.dirs_first { ", dirs first" } else { "" }
+        )
+    }
+
+    /// The folder a deep search result is in, relative to where the search started, e.g.
+    /// "src/ui/". Searches from the filesystem root show the whole path instead.
+    pub fn result_location(&self, entry: &FileEntry) -> Option<String> {
+        if !self.showing_search_results {
+            return None;
+        }
+        let parent = entry.path.parent()?;
+        if self.search_results_root.parent().is_none() {
+            return Some(self.display_path(parent));
+        }
+        let relative = parent.strip_prefix(&self.search_results_root).ok()?;
+        let folders: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+        Some(if folders.is_empty() { "./".to_string() } else { format!("{}/", folders.join("/")) })
+    }
+
+    /// `path` as shown in titles, with the home folder as "~" unless abbreviate_home is off.
+    pub fn display_path(&self, path: &Path) -> String {
+        match dirs::home_dir() {
+            Some(home) if self.config.abbreviate_home => crate::filesystem::abbreviate_home(path, &home),
+            _ => path.display().to_string(),
+        }
+    }
+
+    /// The crumbs of the path bar for `width` columns. The home folder folds into a "~" crumb
+    /// unless abbreviate_home is off, and folders in the middle fold into "…" until the rest
+    /// fits; the root or "~" and the last two folders always stay.
+    pub fn header_crumbs(&self, width: usize) -> Vec<(String, PathBuf)> {
+        let mut crumbs = self.manager.breadcrumbs();
+        if self.config.abbreviate_home
+            && let Some(home) = dirs::home_dir()
+            && let Some(i) = crumbs.iter().position(|(_, path)| *path == home)
+        {
+            crumbs.splice(..=i, [("~".to_string(), home)]);
+        }
+        let length = |crumbs: &[(String, PathBuf)]| crumbs.iter().map(|(label, _)| label.width() + 1).sum::<usize>();
+        while length(&crumbs) > width {
+            if crumbs.len() > 3 && crumbs[1].0 != "…" {
+                crumbs[1].0 = "…".to_string();
+            } else if crumbs.len() > 4 {
+                // The "…" crumb leads to the deepest folder it stands for
+                let folded = crumbs.remove(2);
+                crumbs[1].1 = folded.1;
+            } else {
+                break;
+            }
+        }
+        crumbs
+    }
+
+    /// The typed search as a matcher, while there is a valid one, for highlighting what it matched.
+    pub fn query_matcher(&self) -> Option<Matcher> {
+        if self.search_query.is_empty() {
+            return None;
+        }
+        Matcher::new(&self.search_query, self.search_mode).ok()
+    }
+
+    /// Where the cursor is in the list, e.g. "3/40", followed by " of N" with the number of
+    /// entries there would be without the listing filter or the typed search.
+    pub fn position_label(&self) -> Option<String> {
+        if self.filtered_entries.is_empty() {
+            return None;
+        }
+        let position = format!("{}/{}", self.selected_index + 1, self.filtered_entries.len());
+        let filtered = !self.showing_search_results && (self.name_filter.is_some() || !self.search_query.is_empty());
+        if !filtered {
+            return Some(position);
+        }
+        let total = self.all_entries.iter().filter(|e| self.show_hidden || !is_hidden(&e.name)).count();
+        Some(format!("{} of {}", position, total))
+    }
+
+    /// Index in filtered_entries of the first file when folders are grouped before the files,
+    /// where the list draws a divider row that belongs to no entry.
+    pub fn list_separator(&self) -> Option<usize> {
+        if !self.config.dirs_first || !self.config.group_separator {
+            return None;
+        }
+        let boundary = self.filtered_entries.iter().position(|e| !e.is_dir)?;
+        // The other order of a fuzzy search or search results has no single boundary
+        let grouped = self.filtered_entries[..boundary].iter().any(|e| e.name != "." && e.name != "..")
+            && self.filtered_entries[boundary..].iter().all(|e| !e.is_dir);
+        grouped.then_some(boundary)
+    }
+
+    /// Row of the rendered list, divider included, that is scrolled to the top.
+    pub fn list_row_offset(&self, boundary: usize) -> usize {
+        let offset = self.list_state.offset();
+        offset + usize::from(offset > boundary || (offset == boundary && !self.separator_at_top))
     }
 
     pub fn apply_filter(&mut self) {
+        // Deep search results replace the listing until the user leaves them
+        if self.showing_search_results {
+            return;
+        }
+
+        let show_hidden = self.show_hidden;
+        let keep_dirs = self.config.filter_keeps_dirs;
+        let name_filter = self.name_filter.as_ref();
+        let visible = self.all_entries.iter()
+            .filter(|e| show_hidden || !is_hidden(&e.name))
+            .filter(|e| {
+                name_filter.is_none_or(|filter| {
+                    e.name == "." || e.name == ".." || (keep_dirs && e.is_dir) || filter.is_match(&e.name)
+                })
+            });
+
+        self.search_invalid = false;
         if self.search_query.is_empty() {
-            self.filtered_entries = self.all_entries.clone();
+            self.filtered_entries = visible.cloned().collect();
         } else {
-            let query = self.search_query.to_lowercase();
-            self.filtered_entries = self.all_entries.iter()
-                .filter(|e| {
-                    e.name.to_lowercase().contains(&query) || 
-                    e.description.as_ref().map(|d| d.to_lowercase().contains(&query)).unwrap_or(false)
-                })
-                .cloned()
-                .collect();
+            match Matcher::new(&self.search_query, self.search_mode) {
+                Ok(matcher) => {
+                    let mut scored: Vec<(i64, &FileEntry)> = visible
+                        .filter_map(|e| matcher.entry_score(&e.name, e.description.as_deref()).map(|score| (score, e)))
+                        .collect();
+                    // Only fuzzy scores differ; the sort is stable, so other modes keep the listing order
+                    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+                    self.filtered_entries = scored.into_iter().map(|(_, e)| e.clone()).collect();
+                }
+                Err(_) => {
+                    self.search_invalid = true;
+                    self.filtered_entries.clear();
+                }
+            }
         }
         
         if self.selected_index >= self.filtered_entries.len() && !self.filtered_entries.is_empty() {
@@ -253,6 +1070,10 @@ impl App {
 
     pub fn tick(&mut self) {
         self.tick_count += 1;
+        // Keep the loading spinner turning
+        if self.listing.is_some() {
+            self.dirty = true;
+        }
 
         // Read from PTY
         if let Some(rx) = &self.pty_reader_rx {
@@ -260,12 +1081,145 @@ impl App {
                  if let Some(parser) = &mut self.pty_parser {
                      parser.process(&bytes);
                  }
+                 self.dirty = true;
              }
         }
         
-        // 1. Live View Poll (Every ~1s, assuming 100ms tick -> 10 ticks)
-        if self.tick_count % 10 == 0 {
-             self.refresh(); // Reload files
+        // Stream in deep search results
+        if let Some(rx) = &self.search_rx {
+            loop {
+                match rx.try_recv() {
+                    Ok((entry, content_match)) => {
+                        if let Some(found) = content_match {
+                            self.content_matches.insert(entry.path.clone(), found);
+                        }
+                        // Results go straight into their sorted place. A cursor moved off the
+                        // top stays on its result as others arrive above it
+                        let index = self.filtered_entries
+                            .partition_point(|e| self.compare_search_results(e, &entry) != std::cmp::Ordering::Greater);
+                        if self.selected_index > 0 && index <= self.selected_index {
+                            self.selected_index += 1;
+                        }
+                        self.filtered_entries.insert(index, entry);
+                        self.dirty = true;
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        self.dirty = true;
+                        self.search_rx = None;
+                        self.search_cancel = None;
+                        self.is_searching = false;
+                        self.search_truncated = self.filtered_entries.len() >= self.config.max_search_results;
+                        break;
+                    }
+                }
+            }
+            self.list_state.select(Some(self.selected_index));
+        }
+
+        // Pick up a finished directory size
+        if let Some(rx) = &self.size_rx {
+            match rx.try_recv() {
+                Ok(total) => {
+                    if let (Some(path), Some(total)) = (self.sizing_path.take(), total) {
+                        self.dir_sizes.insert(path, total);
+                    }
+                    self.size_rx = None;
+                    self.size_cancel = None;
+                    self.dirty = true;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.cancel_size(),
+            }
+        }
+
+        // Collect the output of a finished shell command
+        if let Some(rx) = &self.command_rx {
+            match rx.try_recv() {
+                Ok(output) => {
+                    self.command_output = Some(output);
+                    self.command_rx = None;
+                    self.command_pid = None;
+                    self.refresh();
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {
+                    self.dirty = true;
+                    self.command_rx = None;
+                    self.command_pid = None;
+                }
+            }
+        }
+
+        self.poll_listing();
+
+        // Track background paste progress
+        if let Some(rx) = &self.copy_rx {
+            loop {
+                match rx.try_recv() {
+                    Ok(mut progress) => {
+                        self.dirty = true;
+                        if let Some(err) = &progress.error {
+                            self.error_message = Some(err.clone());
+                        }
+                        self.transfer_operations.append(&mut progress.operations);
+                        self.copy_progress = Some(progress);
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        self.copy_rx = None;
+                        let succeeded = self.copy_progress.take().is_some_and(|p| p.error.is_none());
+                        let cancelled = self.transfer_cancel.take().is_some_and(|c| c.load(Ordering::Relaxed));
+                        if succeeded && !cancelled {
+                            let message = std::mem::take(&mut self.transfer_done);
+                            self.notify(message);
+                        }
+                        if self.quit_pending {
+                            self.should_quit = true;
+                        }
+                        let operations = std::mem::take(&mut self.transfer_operations);
+                        self.record_undo(operations);
+                        self.refresh();
+                        break;
+                    }
+                }
+            }
+        }
+
+        if self.status_message.as_ref().is_some_and(|(_, at)| at.elapsed() >= STATUS_TIMEOUT) {
+            self.status_message = None;
+            self.dirty = true;
+        }
+        // Take down the jump hint once typing no longer extends it
+        if self.type_ahead.is_some() && self.type_ahead_text().is_none() {
+            self.type_ahead = None;
+            self.dirty = true;
+        }
+
+        let every_second = self.config.ticks_per(Duration::from_secs(1));
+        let every_half_second = self.config.ticks_per(Duration::from_millis(500));
+
+        // 1. Live View: follow the directory on disk
+        if self.watched_dir.as_deref() != Some(self.manager.current_path()) {
+            self.watch_current_dir();
+        }
+        if let Some(rx) = &self.watch_rx {
+            let mut changed = false;
+            // Drain everything so a burst of events costs a single reload
+            while let Ok(event) = rx.try_recv() {
+                // Our own preview reads show up as access events; they never change the listing
+                changed |= event.is_ok_and(|e| !matches!(e.kind, EventKind::Access(_)));
+            }
+            if changed {
+                self.refresh();
+            }
+        } else if self.tick_count.is_multiple_of(every_second) {
+            // No watcher: poll every ~1s
+            self.refresh();
+        }
+        // Only the active pane is watched, so keep polling the other one
+        if self.dual_pane && self.tick_count.is_multiple_of(every_second) {
+            self.refresh_other_pane();
         }
 
         // 2. Sync Files -> Terminal
@@ -286,22 +1240,21 @@ impl App {
         }
         
         // 3. Sync Terminal -> Files (Linux only)
-        if self.shell_id == 0 {
-             if self.tick_count % 5 == 0 {
-                 if let Some(pid) = find_shell_pid(std::process::id()) {
-                      self.shell_id = pid;
-                 }
-             }
+        if self.shell_id == 0
+             && self.tick_count.is_multiple_of(every_half_second)
+             && let Some(pid) = find_shell_pid(std::process::id())
+        {
+             self.shell_id = pid;
         }
         
-        if self.shell_id > 0 && self.tick_count % 5 == 0 {
+        if self.shell_id > 0 && self.tick_count.is_multiple_of(every_half_second) {
              match std::fs::read_link(format!("/proc/{}/cwd", self.shell_id)) {
                   Ok(target) => {
-                      if target != current {
-                           if let Ok(_) = self.manager.navigate_to(target.clone()) {
-                                self.last_synced_path = target;
-                                self.refresh();
-                           }
+                      if target != current
+                           && self.manager.navigate_to(target.clone()).is_ok()
+                      {
+                           self.last_synced_path = target;
+                           self.refresh();
                       }
                   },
                   Err(_) => {
@@ -311,51 +1264,228 @@ impl App {
         }
     }
 
-    pub fn on_key(&mut self, code: KeyCode, modifiers: crossterm::event::KeyModifiers) {
-        let event_str = crate::config::key_event_to_string(code, modifiers);
+    /// Show a confirmation in the footer for a few seconds.
+    pub fn notify(&mut self, message: String) {
+        self.status_message = Some((message, Instant::now()));
+        self.dirty = true;
+    }
 
-        match &self.input_mode {
-            InputMode::Normal => {
-                if event_str == self.config.keybindings.toggle_terminal {
-                    self.is_terminal_open = !self.is_terminal_open;
-                    if self.is_terminal_open {
-                        self.terminal_focused = true;
-                        if self.pty_pair.is_none() {
-                             self.spawn_pty();
-                        }
-                    } else {
-                        self.terminal_focused = false;
-                    }
-                    return;
-                }
-                if event_str == self.config.keybindings.terminal_prefix {
-                    if self.is_terminal_open {
-                        self.terminal_focused = !self.terminal_focused;
-                    }
-                    return;
-                }
+    /// Whether background work is streaming in, so the main loop should tick more often.
+    pub fn is_busy(&self) -> bool {
+        self.listing.is_some()
+            || self.search_rx.is_some()
+            || self.size_rx.is_some()
+            || self.copy_rx.is_some()
+            || self.command_rx.is_some()
+            || self.is_terminal_open
+    }
 
-                if self.terminal_focused {
-                    // Check for Ctrl+D (EOF/Close)
-                    if modifiers == crossterm::event::KeyModifiers::CONTROL && code == KeyCode::Char('d') {
-                        // Close terminal pane and kill PTY
-                        self.is_terminal_open = false;
-                        self.terminal_focused = false;
-                        self.pty_pair = None; // Dropping closes the PTY
-                        self.pty_parser = None;
-                        self.pty_writer = None;
-                        self.pty_reader_rx = None;
-                        self.shell_id = 0;
-                        return;
-                    }
-                
-                    // Forward input to PTY
-                    if let Some(writer) = &mut self.pty_writer {
-                        let input_bytes = match code {
-                            KeyCode::Char(c) => {
-                                if modifiers.contains(crossterm::event::KeyModifiers::CONTROL) {
-                                    let byte = match c {
-                                        'a'..='z' => c as u8 - b'a' + 1,
+    /// Move the cursor onto `path` if it is in the current listing.
+    pub fn select_path(&mut self, path: &Path) {
+        if let Some(index) = self.filtered_entries.iter().position(|e| e.path == path) {
+            self.selected_index = index;
+            self.list_state.select(Some(index));
+            self.pending_select = None;
+        } else if self.listing.is_some() {
+            // Not loaded yet; try again as chunks come in
+            self.pending_select = Some(path.to_path_buf());
+        }
+    }
+
+    /// The user's shell ($SHELL, or %COMSPEC% on Windows) started in the current directory.
+    pub fn shell_command_here(&self) -> std::process::Command {
+        #[cfg(windows)]
+        let shell = std::env::var_os("COMSPEC").unwrap_or_else(|| "cmd".into());
+        #[cfg(not(windows))]
+        let shell = std::env::var_os("SHELL").unwrap_or_else(|| "/bin/sh".into());
+        let mut command = std::process::Command::new(shell);
+        command.current_dir(self.manager.current_path());
+        command
+    }
+
+    /// Write edit_buffer to a temporary file and build the command that opens it in the
+    /// user's editor. The file is read back by finish_external_edit().
+    pub fn external_edit_command(&self) -> std::io::Result<(std::process::Command, PathBuf)> {
+        let editor = external_editor()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "$EDITOR is not set"))?;
+        // A fresh, randomly named file only we can read, so no one can plant or watch it
+        let mut temp = tempfile::Builder::new().prefix("xplore-description-").suffix(".txt").tempfile()?;
+        temp.write_all(self.edit_buffer.as_bytes())?;
+        let (_, file) = temp.keep().map_err(|e| e.error)?;
+        // Editors such as "code --wait" come with their own arguments
+        let mut words = editor.split_whitespace();
+        let mut command = std::process::Command::new(words.next().unwrap_or_default());
+        command.args(words).arg(&file).current_dir(self.manager.current_path());
+        Ok((command, file))
+    }
+
+    /// Save what the editor left in `file` as edit_target's description. An editor that fails,
+    /// e.g. vim quit with :cq, leaves the description as it was.
+    pub fn finish_external_edit(&mut self, file: &Path, status: std::io::Result<std::process::ExitStatus>) {
+        let text = match status {
+            Ok(status) if status.success() => std::fs::read_to_string(file),
+            Ok(_) => {
+                let _ = std::fs::remove_file(file);
+                return;
+            }
+            Err(e) => Err(e),
+        };
+        let _ = std::fs::remove_file(file);
+        let text = match text {
+            Ok(text) => text,
+            Err(e) => {
+                self.error_message = Some(format!("Failed to edit the description: {}", e));
+                return;
+            }
+        };
+        // Editors end the file with a newline that isn't part of the description
+        let text = text.strip_suffix('\n').map(|t| t.strip_suffix('\r').unwrap_or(t)).unwrap_or(&text);
+        if text == self.edit_buffer {
+            return;
+        }
+        let result = if text.trim().is_empty() {
+            crate::metadata::clear_description(&self.edit_target)
+        } else {
+            crate::metadata::set_description(&self.edit_target, text)
+        };
+        self.error_message = result.err().map(|e| format!("Failed to save description: {}", e));
+        self.refresh();
+    }
+
+    /// Remember the current directory so the next session reopens it.
+    pub fn on_quit(&mut self) {
+        self.config.last_dir = Some(self.manager.current_path().to_path_buf());
+        let _ = self.config.save();
+    }
+
+    /// The type-ahead text, if the jump key or the last character was pressed recently enough
+    /// to extend it.
+    pub fn type_ahead_text(&self) -> Option<&str> {
+        self.type_ahead.as_ref()
+            .filter(|(_, at)| at.elapsed() < TYPE_AHEAD_TIMEOUT)
+            .map(|(text, _)| text.as_str())
+    }
+
+    /// Add `c` to the type-ahead text and select the next entry whose name starts with it,
+    /// ignoring case. A new jump starts looking after the selection, and so does the same
+    /// letter typed again, which steps through the names beginning with it.
+    fn type_ahead_jump(&mut self, c: char) {
+        let (mut text, start) = match self.type_ahead_text() {
+            Some(text) if !text.chars().all(|t| c.to_lowercase().eq(t.to_lowercase())) => {
+                (text.to_string(), self.selected_index)
+            }
+            _ => (String::new(), self.selected_index + 1),
+        };
+        text.extend(c.to_lowercase());
+        let len = self.filtered_entries.len();
+        if let Some(index) = (0..len)
+            .map(|i| (start + i) % len)
+            .find(|&i| self.filtered_entries[i].name.to_lowercase().starts_with(&text))
+        {
+            self.selected_index = index;
+            self.list_state.select(Some(index));
+        }
+        self.type_ahead = Some((text, Instant::now()));
+    }
+
+    /// Long-running work that quitting would cut short, phrased for the confirmation prompt.
+    pub fn running_operations(&self) -> Vec<&'static str> {
+        let mut running = Vec::new();
+        if self.copy_rx.is_some() {
+            running.push("a file operation");
+        }
+        if self.search_rx.is_some() {
+            running.push("a deep search");
+        }
+        if self.command_rx.is_some() {
+            running.push("a shell command");
+        }
+        running
+    }
+
+    /// Quit right away when nothing is running, otherwise ask first.
+    fn request_quit(&mut self) {
+        if self.running_operations().is_empty() {
+            self.should_quit = true;
+        } else {
+            // Default to staying; quitting has to be chosen on purpose
+            self.prompt_index = 1;
+            self.input_mode = InputMode::Prompt(PromptType::QuitConfirmation);
+        }
+    }
+
+    /// Stop everything that is running and quit. A paste first finishes the item it is on,
+    /// so in that case the quit happens once tick() sees the transfer end.
+    fn cancel_and_quit(&mut self) {
+        self.cancel_search();
+        self.stop_shell_command();
+        match &self.transfer_cancel {
+            Some(cancel) => {
+                cancel.store(true, Ordering::Relaxed);
+                self.quit_pending = true;
+            }
+            None => self.should_quit = true,
+        }
+    }
+
+    /// Terminate the running shell command, if any, discarding its output.
+    fn stop_shell_command(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.command_pid {
+            unsafe {
+                libc::kill(-(pid as libc::pid_t), libc::SIGTERM);
+            }
+        }
+        self.command_pid = None;
+        self.command_rx = None;
+    }
+
+    pub fn on_key(&mut self, code: KeyCode, modifiers: crossterm::event::KeyModifiers) {
+        self.dirty = true;
+        let event_str = crate::config::key_event_to_string(code, modifiers);
+
+        match &self.input_mode {
+            InputMode::Normal => {
+                if event_str == self.config.keybindings.toggle_terminal {
+                    self.is_terminal_open = !self.is_terminal_open;
+                    if self.is_terminal_open {
+                        self.terminal_focused = true;
+                        if self.pty_pair.is_none() {
+                             self.spawn_pty();
+                        }
+                    } else {
+                        self.terminal_focused = false;
+                    }
+                    return;
+                }
+                if event_str == self.config.keybindings.terminal_prefix {
+                    if self.is_terminal_open {
+                        self.terminal_focused = !self.terminal_focused;
+                    }
+                    return;
+                }
+
+                if self.terminal_focused {
+                    // Check for Ctrl+D (EOF/Close)
+                    if modifiers == crossterm::event::KeyModifiers::CONTROL && code == KeyCode::Char('d') {
+                        // Close terminal pane and kill PTY
+                        self.is_terminal_open = false;
+                        self.terminal_focused = false;
+                        self.pty_pair = None; // Dropping closes the PTY
+                        self.pty_parser = None;
+                        self.pty_writer = None;
+                        self.pty_reader_rx = None;
+                        self.shell_id = 0;
+                        return;
+                    }
+                
+                    // Forward input to PTY
+                    if let Some(writer) = &mut self.pty_writer {
+                        let input_bytes = match code {
+                            KeyCode::Char(c) => {
+                                if modifiers.contains(crossterm::event::KeyModifiers::CONTROL) {
+                                    let byte = match c {
+                                        'a'..='z' => c as u8 - b'a' + 1,
                                         '[' => 27, // Esc
                                         ' ' => 0, // Null
                                         _ => c as u8, // Fallback
@@ -390,64 +1520,139 @@ impl App {
                     return;
                 }
 
-                // Normal file manager keybindings (only when terminal is NOT focused)
-                if event_str == self.config.keybindings.up || code == KeyCode::Up {
-                    if self.selected_index > 0 {
-                        self.selected_index -= 1;
-                    }
-                } else if event_str == self.config.keybindings.down || code == KeyCode::Down {
-                    if self.selected_index < self.filtered_entries.len().saturating_sub(1) {
-                        self.selected_index += 1;
-                    }
-                } else if event_str == self.config.keybindings.enter || code == KeyCode::Enter {
-                    if let Some(entry) = self.filtered_entries.get(self.selected_index) {
-                        if entry.is_dir {
-                            let path = entry.path.clone();
-                            if self.manager.navigate_to(path).is_ok() {
-                                self.clear_selection_if_needed();
-                                self.search_query.clear();
-                                self.refresh();
-                                self.selected_index = 0;
-                            }
-                        } else {
-                            let _ = opener::open(&entry.path);
-                        }
+                // Any key dismisses a pending error; Esc does nothing beyond that
+                if self.error_message.take().is_some() && code == KeyCode::Esc {
+                    self.list_state.select(Some(self.selected_index));
+                    return;
+                }
+
+                if code == KeyCode::Esc {
+                    let typing = self.type_ahead_text().is_some();
+                    self.type_ahead = None;
+                    if self.pending_count.take().is_some() | self.pending_keys.take().is_some() | typing {
+                        return;
+                    }
+                }
+                // While a jump is fresh every character extends it, bound or not, so whole names
+                // can be typed
+                if let KeyCode::Char(c) = code
+                    && c != ' '
+                    && !modifiers.intersects(crossterm::event::KeyModifiers::CONTROL | crossterm::event::KeyModifiers::ALT)
+                    && self.type_ahead_text().is_some()
+                {
+                    self.type_ahead_jump(c);
+                    return;
+                }
+
+                // Unbound digits build up a count for the next movement; a leading 0 is not a count
+                if let KeyCode::Char(c) = code
+                    && let Some(digit) = c.to_digit(10)
+                    && (digit > 0 || self.pending_count.is_some())
+                    && self.pending_keys.is_none()
+                    && !self.config.is_key_taken(&event_str, "")
+                {
+                    let count = self.pending_count.unwrap_or(0);
+                    self.pending_count = Some(count.saturating_mul(10).saturating_add(digit as usize));
+                    return;
+                }
+                // The first key of a sequence waits for the second; together they match like one key
+                let event_str = match self.pending_keys.take() {
+                    Some(first) => format!("{} {}", first, event_str),
+                    None if self.config.is_sequence_prefix(&event_str) => {
+                        self.pending_keys = Some(event_str);
+                        return;
                     }
-                } else if event_str == self.config.keybindings.backspace || code == KeyCode::Backspace {
+                    None => event_str,
+                };
+                let count = self.pending_count.take().unwrap_or(1);
+
+                // Normal file manager keybindings (only when terminal is NOT focused)
+                // With wrap_navigation, moving on from either end comes back in at the other one
+                let last = self.filtered_entries.len().saturating_sub(1);
+                let wrap = self.config.wrap_navigation;
+                if event_str == self.config.keybindings.up {
+                    self.selected_index = if wrap && self.selected_index == 0 {
+                        last
+                    } else {
+                        self.selected_index.saturating_sub(count)
+                    };
+                } else if event_str == self.config.keybindings.down {
+                    self.selected_index = if wrap && self.selected_index == last {
+                        0
+                    } else {
+                        self.selected_index.saturating_add(count).min(last)
+                    };
+                } else if event_str == self.config.keybindings.enter {
+                    self.open_selected();
+                } else if event_str == self.config.keybindings.backspace {
                     if self.manager.navigate_up() {
-                        self.clear_selection_if_needed();
-                        self.search_query.clear();
-                        self.refresh();
-                        self.selected_index = 0;
+                        self.on_directory_changed();
+                    }
+                } else if event_str == self.config.keybindings.go_back {
+                    if self.manager.go_back() {
+                        self.on_directory_changed();
+                    }
+                } else if event_str == self.config.keybindings.go_forward {
+                    if self.manager.go_forward() {
+                        self.on_directory_changed();
                     }
                 } else if event_str == self.config.keybindings.edit {
-                    if let Some(entry) = self.filtered_entries.get(self.selected_index) {
-                        if entry.name != ".." && entry.name != "." {
-                            self.edit_buffer = entry.description.clone().unwrap_or_default();
-                            self.input_mode = InputMode::Editing;
+                    if let Some(entry) = self.filtered_entries.get(self.selected_index)
+                        && entry.name != ".." && entry.name != "."
+                    {
+                        self.begin_editing(entry.path.clone(), entry.description.clone());
+                    }
+                } else if event_str == self.config.keybindings.edit_dir_description {
+                    let dir = self.manager.current_path().to_path_buf();
+                    let description = crate::metadata::get_description(&dir);
+                    self.begin_editing(dir, description);
+                } else if event_str == self.config.keybindings.edit_external {
+                    if let Some(entry) = self.filtered_entries.get(self.selected_index)
+                        && entry.name != ".." && entry.name != "."
+                    {
+                        let (path, description) = (entry.path.clone(), entry.description.clone());
+                        if external_editor().is_some() {
+                            self.edit_target = path;
+                            self.edit_buffer = description.unwrap_or_default();
+                            self.editor_requested = true;
+                        } else {
+                            self.begin_editing(path, description);
                         }
                     }
+                } else if event_str == self.config.keybindings.clear_description {
+                    if let Some(entry) = self.filtered_entries.get(self.selected_index)
+                        && entry.name != ".." && entry.name != "."
+                    {
+                        if let Err(e) = crate::metadata::clear_description(&entry.path) {
+                            self.error_message = Some(format!("Failed to clear description: {}", e));
+                        }
+                        self.refresh();
+                    }
                 } else if event_str == self.config.keybindings.settings {
                     self.input_mode = InputMode::Config;
                     self.config_index = 0;
                 } else if event_str == self.config.keybindings.search {
-                    self.input_mode = InputMode::Search;
+                    self.selection_anchor = None;
+                    self.leave_search_results();
+                    self.start_search_input();
                 } else if event_str == self.config.keybindings.ctrl_home {
                     self.selected_index = 0;
                 } else if event_str == self.config.keybindings.ctrl_end {
                     self.selected_index = self.filtered_entries.len().saturating_sub(1);
-                } else if event_str == self.config.keybindings.home || code == KeyCode::Home {
+                } else if event_str == self.config.keybindings.home {
                     self.selected_index = self.list_state.offset();
-                } else if event_str == self.config.keybindings.end || code == KeyCode::End {
+                } else if event_str == self.config.keybindings.end {
                     let offset = self.list_state.offset();
                     let height = self.list_height as usize;
                     self.selected_index = (offset + height).saturating_sub(1).min(self.filtered_entries.len().saturating_sub(1));
-                } else if event_str == self.config.keybindings.page_up || code == KeyCode::PageUp {
+                } else if event_str == self.config.keybindings.page_up {
                     let height = self.list_height as usize;
-                    self.selected_index = self.selected_index.saturating_sub(height);
-                } else if event_str == self.config.keybindings.page_down || code == KeyCode::PageDown {
+                    self.selected_index = self.selected_index.saturating_sub(height.saturating_mul(count));
+                } else if event_str == self.config.keybindings.page_down {
                     let height = self.list_height as usize;
-                    self.selected_index = (self.selected_index + height).min(self.filtered_entries.len().saturating_sub(1));
+                    self.selected_index = self.selected_index
+                        .saturating_add(height.saturating_mul(count))
+                        .min(self.filtered_entries.len().saturating_sub(1));
                 } else if event_str == self.config.keybindings.select_all {
                     for entry in &self.filtered_entries {
                         if entry.name != ".." && entry.name != "." {
@@ -455,40 +1660,198 @@ impl App {
                         }
                     }
                 } else if event_str == self.config.keybindings.deselect_all {
+                    self.selection_anchor = None;
                     self.selected_paths.clear();
+                } else if event_str == self.config.keybindings.invert_selection {
+                    self.selection_anchor = None;
+                    self.invert_selection();
+                } else if event_str == self.config.keybindings.visual_select {
+                    if self.selection_anchor.take().is_none() {
+                        self.range_base = self.selected_paths.clone();
+                        self.selection_anchor = Some(self.selected_index);
+                    }
                 } else if event_str == self.config.keybindings.select {
-                    if let Some(entry) = self.filtered_entries.get(self.selected_index) {
-                        if entry.name != ".." && entry.name != "." {
-                            if self.selected_paths.contains(&entry.path) {
-                                self.selected_paths.remove(&entry.path);
-                            } else {
-                                self.selected_paths.insert(entry.path.clone());
-                            }
+                    if let Some(entry) = self.filtered_entries.get(self.selected_index)
+                        && entry.name != ".." && entry.name != "."
+                    {
+                        if self.selected_paths.contains(&entry.path) {
+                            self.selected_paths.remove(&entry.path);
+                        } else {
+                            self.selected_paths.insert(entry.path.clone());
                         }
                     }
                 } else if event_str == self.config.keybindings.copy {
                     self.perform_clipboard_action(ClipboardMode::Copy);
                 } else if event_str == self.config.keybindings.cut {
                     self.perform_clipboard_action(ClipboardMode::Cut);
+                } else if event_str == self.config.keybindings.copy_link {
+                    self.perform_clipboard_action(ClipboardMode::Link);
+                } else if event_str == self.config.keybindings.copy_path {
+                    self.copy_paths_to_system_clipboard();
                 } else if event_str == self.config.keybindings.paste {
                     self.paste_clipboard();
                 } else if event_str == self.config.keybindings.new_folder {
                     self.prompt_buffer.clear();
                     self.input_mode = InputMode::Prompt(PromptType::NewFolder);
-                } else if event_str == self.config.keybindings.delete {
-                    if !self.selected_paths.is_empty() || self.filtered_entries.get(self.selected_index).is_some() {
-                        self.input_mode = InputMode::Prompt(PromptType::DeleteConfirmation);
+                } else if event_str == self.config.keybindings.new_file {
+                    self.prompt_buffer.clear();
+                    self.error_message = None;
+                    self.input_mode = InputMode::Prompt(PromptType::NewFile);
+                } else if event_str == self.config.keybindings.rename {
+                    if self.selected_paths.len() > 1 {
+                        self.start_batch_rename();
+                    } else if let Some(entry) = self.filtered_entries.get(self.selected_index)
+                        && entry.name != ".." && entry.name != "."
+                    {
+                        self.prompt_buffer = entry.name.clone();
+                        self.error_message = None;
+                        self.input_mode = InputMode::Prompt(PromptType::Rename);
+                    }
+                } else if event_str == self.config.keybindings.permissions {
+                    self.start_permissions_edit();
+                } else if event_str == self.config.keybindings.archive {
+                    self.start_archive_prompt();
+                } else if event_str == self.config.keybindings.export_descriptions {
+                    self.export_descriptions();
+                } else if event_str == self.config.keybindings.import_descriptions {
+                    self.import_descriptions();
+                } else if event_str == self.config.keybindings.extract {
+                    if let Err(e) = self.start_extract() {
+                        self.error_message = Some(e);
+                    }
+                } else if event_str == self.config.keybindings.symlink {
+                    if let Some(entry) = self.filtered_entries.get(self.selected_index)
+                        && entry.name != ".." && entry.name != "."
+                    {
+                        self.prompt_buffer = format!("{} link", entry.name);
+                        self.symlink_target = Some(entry.path.clone());
+                        self.error_message = None;
+                        self.input_mode = InputMode::Prompt(PromptType::Symlink);
+                    }
+                } else if event_str == self.config.keybindings.undo {
+                    self.undo();
+                } else if event_str == self.config.keybindings.toggle_hidden {
+                    self.show_hidden = !self.show_hidden;
+                    self.refresh();
+                } else if event_str == self.config.keybindings.filter {
+                    self.prompt_buffer = self.name_filter.as_ref().map(|f| f.pattern().to_string()).unwrap_or_default();
+                    self.error_message = None;
+                    self.input_mode = InputMode::Prompt(PromptType::Filter);
+                } else if event_str == self.config.keybindings.cycle_sort {
+                    self.sort_key = self.sort_key.next();
+                    self.apply_sort();
+                    self.apply_filter();
+                } else if event_str == self.config.keybindings.reverse_sort {
+                    self.sort_order = self.sort_order.reverse();
+                    self.apply_sort();
+                    self.apply_filter();
+                } else if event_str == self.config.keybindings.toggle_dirs_first {
+                    // Remembered for the next session, like the other settings
+                    self.config.dirs_first = !self.config.dirs_first;
+                    self.error_message = self.config.save().err().map(|e| format!("Failed to save config: {}", e));
+                    self.apply_sort();
+                    self.apply_filter();
+                } else if event_str == self.config.keybindings.grow_list || event_str == self.config.keybindings.shrink_list {
+                    let step = if event_str == self.config.keybindings.grow_list { LIST_WIDTH_STEP } else { -LIST_WIDTH_STEP };
+                    self.config.resize_list(step.saturating_mul(count as i32));
+                    self.error_message = self.config.save().err().map(|e| format!("Failed to save config: {}", e));
+                } else if event_str == self.config.keybindings.calc_size {
+                    if let Some(entry) = self.filtered_entries.get(self.selected_index)
+                        && entry.is_dir
+                    {
+                        // Always recompute on request; the cache only serves the Details panel
+                        let path = entry.path.clone();
+                        self.calculate_size(path);
+                    }
+                } else if event_str == self.config.keybindings.delete || event_str == self.config.keybindings.delete_permanent {
+                    let targets = self.delete_targets();
+                    if !targets.is_empty() {
+                        // Without trash every delete is permanent, so the prompt should say so
+                        let permanent = event_str == self.config.keybindings.delete_permanent || !self.config.use_trash;
+                        let typed = delete_confirmation_text(&targets);
+                        self.input_mode = InputMode::Prompt(PromptType::DeleteConfirmation { permanent, typed });
                         self.prompt_index = 1;
+                        self.prompt_buffer.clear();
+                        self.error_message = None;
+                    }
+                } else if event_str == self.config.keybindings.add_bookmark {
+                    self.add_bookmark();
+                } else if event_str == self.config.keybindings.bookmarks {
+                    self.bookmark_index = 0;
+                    self.error_message = None;
+                    self.input_mode = InputMode::Bookmarks;
+                } else if event_str == self.config.keybindings.recent_dirs {
+                    // The first entry is where we are; start on the one before it
+                    self.recent_index = usize::from(self.config.recent_dirs.len() > 1);
+                    self.error_message = None;
+                    self.input_mode = InputMode::RecentDirs;
+                } else if event_str == self.config.keybindings.toggle_dual_pane {
+                    self.toggle_dual_pane();
+                } else if event_str == self.config.keybindings.toggle_details {
+                    self.show_details = !self.show_details;
+                } else if event_str == self.config.keybindings.switch_pane {
+                    self.switch_pane();
+                } else if event_str == self.config.keybindings.new_tab {
+                    self.new_tab();
+                } else if event_str == self.config.keybindings.close_tab {
+                    self.close_tab();
+                } else if event_str == self.config.keybindings.next_tab {
+                    self.switch_tab(true);
+                } else if event_str == self.config.keybindings.prev_tab {
+                    self.switch_tab(false);
+                } else if event_str == self.config.keybindings.open_with {
+                    if self.filtered_entries.get(self.selected_index).is_some() {
+                        self.open_with_index = 0;
+                        self.error_message = None;
+                        self.input_mode = InputMode::OpenWith;
                     }
+                } else if event_str == self.config.keybindings.shell_command {
+                    if self.command_rx.is_none() {
+                        self.prompt_buffer.clear();
+                        self.error_message = None;
+                        self.input_mode = InputMode::Command;
+                    } else {
+                        self.error_message = Some("A command is already running".to_string());
+                    }
+                } else if event_str == self.config.keybindings.breadcrumbs {
+                    // Start on the parent, the most likely target
+                    self.breadcrumb_index = self.manager.breadcrumbs().len().saturating_sub(2);
+                    self.input_mode = InputMode::Breadcrumbs;
+                } else if event_str == self.config.keybindings.go_to_path {
+                    self.prompt_buffer.clear();
+                    self.error_message = None;
+                    self.input_mode = InputMode::Prompt(PromptType::GoToPath);
+                } else if event_str == self.config.keybindings.reveal {
+                    if let Some(entry) = self.filtered_entries.get(self.selected_index)
+                        && entry.name != ".." && entry.name != "."
+                        && (self.showing_search_results || entry.path.parent() != Some(self.manager.current_path()))
+                    {
+                        let path = entry.path.clone();
+                        self.reveal(&path);
+                    }
+                } else if event_str == self.config.keybindings.jump_to_name {
+                    self.type_ahead = Some((String::new(), Instant::now()));
+                } else if event_str == self.config.keybindings.open_shell {
+                    self.shell_requested = true;
                 } else if event_str == self.config.keybindings.help {
                     self.input_mode = InputMode::Help;
                 } else if event_str == self.config.keybindings.quit {
-                    // Logic handled in main.rs loop
+                    self.request_quit();
+                } else if code == KeyCode::Esc && self.is_searching {
+                    // First Esc only stops the scan so partial results stay browsable
+                    self.cancel_search();
+                } else if code == KeyCode::Esc && self.size_rx.is_some() {
+                    self.cancel_size();
                 } else if code == KeyCode::Esc {
+                    self.selection_anchor = None;
                     self.selected_paths.clear();
                     self.search_query.clear();
+                    self.leave_search_results();
                     self.apply_filter();
                 }
+                if self.selection_anchor.is_some() {
+                    self.extend_range_selection();
+                }
                 self.list_state.select(Some(self.selected_index));
             },
             InputMode::Prompt(prompt_type) => match prompt_type {
@@ -510,17 +1873,85 @@ impl App {
                         self.input_mode = InputMode::Normal;
                         self.prompt_buffer.clear();
                     }
+                    KeyCode::Tab => self.complete_prompt(true),
+                    KeyCode::Char(c) => self.prompt_buffer.push(c),
+                    KeyCode::Backspace => {
+                        self.prompt_buffer.pop();
+                    }
+                    _ => {}
+                },
+                PromptType::NewFile => match code {
+                    KeyCode::Enter => {
+                        match self.create_from_prompt() {
+                            Ok(_) => {
+                                self.input_mode = InputMode::Normal;
+                                self.prompt_buffer.clear();
+                                self.error_message = None;
+                            }
+                            Err(e) => {
+                                self.error_message = Some(e);
+                            }
+                        }
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.prompt_buffer.clear();
+                        self.error_message = None;
+                    }
+                    KeyCode::Tab => self.complete_prompt(false),
+                    KeyCode::Char(c) => self.prompt_buffer.push(c),
+                    KeyCode::Backspace => {
+                        self.prompt_buffer.pop();
+                    }
+                    _ => {}
+                },
+                PromptType::PasteConflict => match code {
+                    KeyCode::Enter => {
+                        let choice = ConflictChoice::ALL[self.prompt_index];
+                        self.input_mode = InputMode::Normal;
+                        self.resolve_paste_conflict(choice);
+                    }
+                    KeyCode::Esc => {
+                        // Abandon the whole paste; nothing has been touched yet
+                        self.pending_paste = None;
+                        self.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Left => {
+                        self.prompt_index = (self.prompt_index + ConflictChoice::ALL.len() - 1) % ConflictChoice::ALL.len();
+                    }
+                    KeyCode::Right | KeyCode::Tab => {
+                        self.prompt_index = (self.prompt_index + 1) % ConflictChoice::ALL.len();
+                    }
+                    _ => {}
+                },
+                PromptType::DeleteConfirmation { permanent, typed: Some(expected) } => match code {
+                    KeyCode::Enter => {
+                        if self.prompt_buffer.trim() == expected {
+                            let permanent = *permanent;
+                            self.delete_selected(permanent);
+                            self.input_mode = InputMode::Normal;
+                            self.prompt_buffer.clear();
+                        } else {
+                            self.error_message = Some(format!("Type {} to confirm", expected));
+                        }
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.prompt_buffer.clear();
+                        self.error_message = None;
+                    }
                     KeyCode::Char(c) => self.prompt_buffer.push(c),
                     KeyCode::Backspace => {
                         self.prompt_buffer.pop();
                     }
                     _ => {}
                 },
-                PromptType::DeleteConfirmation => match code {
+                PromptType::DeleteConfirmation { permanent, typed: None } => match code {
                     KeyCode::Enter => {
                         if self.prompt_index == 0 {
                             // OK selected
-                            self.delete_selected();
+                            let permanent = *permanent;
+                            self.delete_selected(permanent);
                         }
                         self.input_mode = InputMode::Normal;
                     }
@@ -532,74 +1963,493 @@ impl App {
                     }
                     _ => {}
                 },
-            },
-            InputMode::Help => {
-                if code == KeyCode::Esc || code == KeyCode::F(1) || event_str == self.config.keybindings.help {
-                    self.input_mode = InputMode::Normal;
-                }
-            },
-            InputMode::Editing => match code {
-                KeyCode::F(2) => {
-                    // Save on F2
-                    if let Some(entry) = self.filtered_entries.get(self.selected_index) {
-                        let _ = crate::metadata::set_description(&entry.path, &self.edit_buffer);
+                PromptType::ResetKeybindings => match code {
+                    KeyCode::Enter => {
+                        if self.prompt_index == 0 {
+                            self.config.keybindings = Keybindings::default();
+                            self.error_message = self.config.save()
+                                .err()
+                                .map(|e| format!("Failed to save config: {}", e));
+                        }
+                        self.input_mode = InputMode::Config;
                     }
-                    self.input_mode = InputMode::Normal;
-                    self.refresh();
-                }
-                KeyCode::Enter => {
-                    // Always newline on Enter in multiline editor
-                    self.edit_buffer.push('\n');
-                }
-                KeyCode::Esc => {
-                    self.input_mode = InputMode::Normal;
-                }
-                KeyCode::Char(c) => {
-                    self.edit_buffer.push(c);
-                }
-                KeyCode::Backspace => {
-                    self.edit_buffer.pop();
-                }
-                _ => {}
-            },
-            InputMode::Search => match code {
-                KeyCode::Enter => {
-                    self.input_mode = InputMode::Normal;
-                    // Trigger deep search if query is not empty
-                    if !self.search_query.is_empty() {
-                        self.trigger_deep_search();
+                    KeyCode::Esc => self.input_mode = InputMode::Config,
+                    KeyCode::Left | KeyCode::Right | KeyCode::Tab => {
+                        self.prompt_index = 1 - self.prompt_index;
                     }
-                }
-                KeyCode::Esc => {
-                    self.input_mode = InputMode::Normal;
-                }
-                KeyCode::Char(c) => {
-                    self.search_query.push(c);
-                    self.apply_filter();
-                }
-                KeyCode::Backspace => {
-                    self.search_query.pop();
-                    self.apply_filter();
-                }
-                _ => {}
-            },
-            InputMode::Config => match code {
-                KeyCode::Up | KeyCode::Char('k') => {
-                    if self.config_index > 0 {
-                        self.config_index -= 1;
+                    _ => {}
+                },
+                PromptType::QuitConfirmation => match code {
+                    KeyCode::Enter => {
+                        self.input_mode = InputMode::Normal;
+                        if self.prompt_index == 0 {
+                            self.cancel_and_quit();
+                        }
                     }
-                }
-                KeyCode::Down | KeyCode::Char('j') => {
-                    if self.config_index < self.config.get_actions().len().saturating_sub(1) {
-                        self.config_index += 1;
+                    KeyCode::Esc => self.input_mode = InputMode::Normal,
+                    KeyCode::Left | KeyCode::Right | KeyCode::Tab => {
+                        self.prompt_index = 1 - self.prompt_index;
                     }
-                }
-                KeyCode::Enter => {
-                    if let Some((action, _)) = self.config.get_actions().get(self.config_index) {
-                        self.input_mode = InputMode::Remapping(action.to_string());
+                    _ => {}
+                },
+                PromptType::OpenWith => match code {
+                    KeyCode::Enter => {
+                        let command = self.prompt_buffer.clone();
+                        match self.open_with(&command) {
+                            Ok(_) => {
+                                self.input_mode = InputMode::Normal;
+                                self.prompt_buffer.clear();
+                                self.error_message = None;
+                            }
+                            Err(e) => {
+                                self.error_message = Some(e);
+                            }
+                        }
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.prompt_buffer.clear();
+                        self.error_message = None;
+                    }
+                    KeyCode::Char(c) => self.prompt_buffer.push(c),
+                    KeyCode::Backspace => {
+                        self.prompt_buffer.pop();
+                    }
+                    _ => {}
+                },
+                PromptType::GoToPath => match code {
+                    KeyCode::Enter => match self.go_to_input_path() {
+                        Ok(_) => {
+                            self.input_mode = InputMode::Normal;
+                            self.prompt_buffer.clear();
+                            self.error_message = None;
+                        }
+                        Err(e) => self.error_message = Some(e),
+                    },
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.prompt_buffer.clear();
+                        self.error_message = None;
+                    }
+                    KeyCode::Tab => self.complete_prompt(true),
+                    KeyCode::Char(c) => self.prompt_buffer.push(c),
+                    KeyCode::Backspace => {
+                        self.prompt_buffer.pop();
+                    }
+                    _ => {}
+                },
+                PromptType::Filter => match code {
+                    KeyCode::Enter => {
+                        let pattern = self.prompt_buffer.trim();
+                        let filter = if pattern.is_empty() { Ok(None) } else { NameFilter::new(pattern).map(Some) };
+                        match filter {
+                            Ok(filter) => {
+                                self.name_filter = filter;
+                                self.input_mode = InputMode::Normal;
+                                self.prompt_buffer.clear();
+                                self.error_message = None;
+                                self.apply_filter();
+                            }
+                            Err(_) => self.error_message = Some("Invalid pattern".to_string()),
+                        }
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.prompt_buffer.clear();
+                        self.error_message = None;
+                    }
+                    KeyCode::Char(c) => self.prompt_buffer.push(c),
+                    KeyCode::Backspace => {
+                        self.prompt_buffer.pop();
+                    }
+                    _ => {}
+                },
+                PromptType::Archive => match code {
+                    KeyCode::Enter => match self.start_archive() {
+                        Ok(_) => {
+                            self.input_mode = InputMode::Normal;
+                            self.prompt_buffer.clear();
+                            self.error_message = None;
+                        }
+                        Err(e) => self.error_message = Some(e),
+                    },
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.prompt_buffer.clear();
+                        self.archive_paths.clear();
+                        self.error_message = None;
+                    }
+                    KeyCode::Char(c) => self.prompt_buffer.push(c),
+                    KeyCode::Backspace => {
+                        self.prompt_buffer.pop();
+                    }
+                    _ => {}
+                },
+                PromptType::Symlink => match code {
+                    KeyCode::Enter => match self.create_symlink_from_prompt() {
+                        Ok(_) => {
+                            self.input_mode = InputMode::Normal;
+                            self.prompt_buffer.clear();
+                            self.symlink_target = None;
+                            self.error_message = None;
+                        }
+                        Err(e) => self.error_message = Some(e),
+                    },
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.prompt_buffer.clear();
+                        self.symlink_target = None;
+                        self.error_message = None;
+                    }
+                    KeyCode::Char(c) => self.prompt_buffer.push(c),
+                    KeyCode::Backspace => {
+                        self.prompt_buffer.pop();
+                    }
+                    _ => {}
+                },
+                PromptType::Rename => match code {
+                    KeyCode::Enter => {
+                        match self.rename_selected() {
+                            Ok(_) => {
+                                self.input_mode = InputMode::Normal;
+                                self.prompt_buffer.clear();
+                                self.error_message = None;
+                            }
+                            Err(e) => {
+                                // Keep the prompt open so the name can be corrected
+                                self.error_message = Some(e);
+                            }
+                        }
+                    }
+                    KeyCode::Esc => {
+                        self.input_mode = InputMode::Normal;
+                        self.prompt_buffer.clear();
+                        self.error_message = None;
+                    }
+                    KeyCode::Tab => {
+                        // A new name can't contain a separator, so drop the one completion adds to directories
+                        self.complete_prompt(false);
+                        let trimmed = self.prompt_buffer.trim_end_matches(['/', std::path::MAIN_SEPARATOR]).len();
+                        self.prompt_buffer.truncate(trimmed);
+                    }
+                    KeyCode::Char(c) => self.prompt_buffer.push(c),
+                    KeyCode::Backspace => {
+                        self.prompt_buffer.pop();
+                    }
+                    _ => {}
+                },
+            },
+            InputMode::OpenWith => match code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.open_with_index = self.open_with_index.saturating_sub(1);
+                }
+                // The extra last row is "Other command..."
+                KeyCode::Down | KeyCode::Char('j') if self.open_with_index < self.open_with_candidates().len() => {
+                    self.open_with_index += 1;
+                }
+                KeyCode::Enter => {
+                    match self.open_with_candidates().get(self.open_with_index).cloned() {
+                        Some(command) => match self.open_with(&command) {
+                            Ok(_) => self.input_mode = InputMode::Normal,
+                            Err(e) => self.error_message = Some(e),
+                        },
+                        None => {
+                            self.prompt_buffer.clear();
+                            self.error_message = None;
+                            self.input_mode = InputMode::Prompt(PromptType::OpenWith);
+                        }
+                    }
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.error_message = None;
+                }
+                _ => {}
+            },
+            InputMode::Bookmarks => match code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.bookmark_index = self.bookmark_index.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    if self.bookmark_index < self.config.bookmarks.len().saturating_sub(1) {
+                        self.bookmark_index += 1;
+                    }
+                }
+                KeyCode::Enter => self.open_bookmark(),
+                KeyCode::Delete | KeyCode::Char('d') => self.remove_bookmark(),
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.error_message = None;
+                }
+                _ => {
+                    if event_str == self.config.keybindings.bookmarks {
+                        self.input_mode = InputMode::Normal;
+                    }
+                }
+            },
+            InputMode::RecentDirs => match code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.recent_index = self.recent_index.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    if self.recent_index < self.config.recent_dirs.len().saturating_sub(1) {
+                        self.recent_index += 1;
+                    }
+                }
+                KeyCode::Enter => self.open_recent_dir(),
+                KeyCode::Delete | KeyCode::Char('d') => self.remove_recent_dir(),
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.error_message = None;
+                }
+                _ => {
+                    if event_str == self.config.keybindings.recent_dirs {
+                        self.input_mode = InputMode::Normal;
+                    }
+                }
+            },
+            InputMode::Command => match code {
+                KeyCode::Enter => match self.run_shell_command() {
+                    Ok(_) => {
+                        self.command_scroll = 0;
+                        self.input_mode = InputMode::CommandOutput;
+                    }
+                    Err(e) => self.error_message = Some(e),
+                },
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.error_message = None;
+                }
+                KeyCode::Char(c) => self.prompt_buffer.push(c),
+                KeyCode::Backspace => {
+                    self.prompt_buffer.pop();
+                }
+                _ => {}
+            },
+            InputMode::CommandOutput => match code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.command_scroll = self.command_scroll.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.command_scroll = self.command_scroll.saturating_add(1);
+                }
+                KeyCode::Esc | KeyCode::Enter => {
+                    // A command still running keeps going; its output just isn't shown
+                    self.input_mode = InputMode::Normal;
+                }
+                _ => {}
+            },
+            InputMode::Permissions => match code {
+                KeyCode::Left | KeyCode::Char('h') => {
+                    self.permission_cursor = self.permission_cursor.saturating_sub(1);
+                }
+                KeyCode::Right | KeyCode::Char('l') => {
+                    self.permission_cursor = (self.permission_cursor + 1).min(8);
+                }
+                KeyCode::Char(' ') => {
+                    self.permission_mode ^= 1 << (8 - self.permission_cursor);
+                }
+                // Typing digits shifts them in from the right, so "755" ends up as 0o755
+                KeyCode::Char(c @ '0'..='7') => {
+                    let digit = c as u32 - '0' as u32;
+                    let bits = ((self.permission_mode << 3) | digit) & 0o777;
+                    self.permission_mode = (self.permission_mode & !0o777) | bits;
+                }
+                KeyCode::Enter => match self.apply_permissions() {
+                    Ok(_) => {
+                        self.input_mode = InputMode::Normal;
+                        self.error_message = None;
+                    }
+                    Err(e) => self.error_message = Some(e),
+                },
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.error_message = None;
+                }
+                _ => {}
+            },
+            InputMode::BatchRename => match code {
+                KeyCode::Enter => match self.apply_batch_rename() {
+                    Ok(_) => {
+                        self.input_mode = InputMode::Normal;
+                        self.prompt_buffer.clear();
+                        self.error_message = None;
+                    }
+                    Err(e) => self.error_message = Some(e),
+                },
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.prompt_buffer.clear();
+                    self.error_message = None;
+                }
+                KeyCode::Char(c) => {
+                    self.prompt_buffer.push(c);
+                    self.error_message = None;
+                }
+                KeyCode::Backspace => {
+                    self.prompt_buffer.pop();
+                    self.error_message = None;
+                }
+                _ => {}
+            },
+            InputMode::Breadcrumbs => match code {
+                KeyCode::Left | KeyCode::Char('h') => {
+                    self.breadcrumb_index = self.breadcrumb_index.saturating_sub(1);
+                }
+                KeyCode::Right | KeyCode::Char('l')
+                    if self.breadcrumb_index + 1 < self.manager.breadcrumbs().len() =>
+                {
+                    self.breadcrumb_index += 1;
+                }
+                KeyCode::Enter => {
+                    self.input_mode = InputMode::Normal;
+                    if let Some((_, path)) = self.manager.breadcrumbs().get(self.breadcrumb_index) {
+                        let path = path.clone();
+                        self.jump_to(&path);
+                    }
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                _ => {
+                    if event_str == self.config.keybindings.breadcrumbs {
+                        self.input_mode = InputMode::Normal;
+                    }
+                }
+            },
+            InputMode::Help => {
+                if code == KeyCode::Esc || code == KeyCode::F(1) || event_str == self.config.keybindings.help {
+                    self.input_mode = InputMode::Normal;
+                }
+            },
+            InputMode::Editing => match code {
+                KeyCode::F(2) => {
+                    // Save on F2; saving an empty description removes it instead of storing ""
+                    let result = if self.edit_buffer.trim().is_empty() {
+                        crate::metadata::clear_description(&self.edit_target)
+                    } else {
+                        crate::metadata::set_description(&self.edit_target, &self.edit_buffer)
+                    };
+                    if let Err(e) = result {
+                        // Stay in the editor so the text isn't lost
+                        self.error_message = Some(format!("Failed to save description: {}", e));
+                        return;
+                    }
+                    self.error_message = None;
+                    self.input_mode = InputMode::Normal;
+                    self.refresh();
+                }
+                KeyCode::Enter => {
+                    // Always newline on Enter in multiline editor
+                    self.edit_buffer.insert(self.edit_cursor, '\n');
+                    self.edit_cursor += 1;
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.error_message = None;
+                }
+                KeyCode::Char(c) => {
+                    self.edit_buffer.insert(self.edit_cursor, c);
+                    self.edit_cursor += c.len_utf8();
+                }
+                KeyCode::Backspace => {
+                    let prev = prev_char_boundary(&self.edit_buffer, self.edit_cursor);
+                    self.edit_buffer.replace_range(prev..self.edit_cursor, "");
+                    self.edit_cursor = prev;
+                }
+                KeyCode::Delete => {
+                    let next = next_char_boundary(&self.edit_buffer, self.edit_cursor);
+                    self.edit_buffer.replace_range(self.edit_cursor..next, "");
+                }
+                KeyCode::Left => {
+                    self.edit_cursor = prev_char_boundary(&self.edit_buffer, self.edit_cursor);
+                }
+                KeyCode::Right => {
+                    self.edit_cursor = next_char_boundary(&self.edit_buffer, self.edit_cursor);
+                }
+                KeyCode::Up => {
+                    self.edit_cursor = move_vertically(&self.edit_buffer, self.edit_cursor, true);
+                }
+                KeyCode::Down => {
+                    self.edit_cursor = move_vertically(&self.edit_buffer, self.edit_cursor, false);
+                }
+                KeyCode::Home => {
+                    self.edit_cursor = line_start(&self.edit_buffer, self.edit_cursor);
+                }
+                KeyCode::End => {
+                    self.edit_cursor = line_end(&self.edit_buffer, self.edit_cursor);
+                }
+                _ => {}
+            },
+            InputMode::Search if event_str == self.config.keybindings.cycle_search_mode => {
+                self.search_mode = self.search_mode.next();
+                self.apply_filter();
+            }
+            InputMode::Search if event_str == self.config.keybindings.toggle_content_search => {
+                self.search_contents = !self.search_contents;
+            }
+            InputMode::Search if event_str == self.config.keybindings.toggle_search_scope => {
+                self.search_scope = self.search_scope.toggle();
+            }
+            InputMode::Search => match code {
+                KeyCode::Enter if self.search_invalid => {}
+                KeyCode::Enter => {
+                    self.finish_search_input();
+                    // Trigger deep search if query is not empty
+                    if !self.search_query.is_empty() {
+                        self.trigger_deep_search();
+                    }
+                }
+                KeyCode::Esc => {
+                    self.finish_search_input();
+                }
+                KeyCode::Up => self.recall_search(true),
+                KeyCode::Down => self.recall_search(false),
+                KeyCode::Char(c) => {
+                    if std::mem::take(&mut self.search_prefilled) {
+                        self.search_query.clear();
+                    }
+                    self.search_query.push(c);
+                    self.apply_filter();
+                }
+                KeyCode::Backspace => {
+                    if std::mem::take(&mut self.search_prefilled) {
+                        self.search_query.clear();
+                    } else {
+                        self.search_query.pop();
+                    }
+                    self.apply_filter();
+                }
+                _ => {}
+            },
+            InputMode::Config => match code {
+                KeyCode::Up | KeyCode::Char('k') if self.config_index > 0 => {
+                    self.config_index -= 1;
+                }
+                KeyCode::Down | KeyCode::Char('j')
+                    if self.config_index < self.config.get_actions().len().saturating_sub(1) =>
+                {
+                    self.config_index += 1;
+                }
+                KeyCode::Enter | KeyCode::Char('a') => {
+                    if let Some((action, _)) = self.config.get_actions().get(self.config_index) {
+                        self.remap_adding = code == KeyCode::Char('a');
+                        self.input_mode = InputMode::Remapping(action.to_string());
                         self.error_message = None;
                     }
                 }
+                KeyCode::Char('r') => {
+                    // Default to Cancel; this throws away every custom binding
+                    self.prompt_index = 1;
+                    self.error_message = None;
+                    self.input_mode = InputMode::Prompt(PromptType::ResetKeybindings);
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    if let Some((action, _)) = self.config.get_actions().get(self.config_index) {
+                        self.error_message = match self.config.remove_key(action) {
+                            Ok(_) => self.config.save().err().map(|e| format!("Failed to save config: {}", e)),
+                            Err(e) => Some(e.to_string()),
+                        };
+                    }
+                }
                 KeyCode::Esc => {
                     self.input_mode = InputMode::Normal;
                     self.error_message = None;
@@ -607,19 +2457,26 @@ impl App {
                 _ => {}
             },
             InputMode::Remapping(action) => {
-                if !event_str.is_empty() && code != KeyCode::Esc {
+                if code != KeyCode::Esc {
                     let action_clone = action.clone();
-                    match self.config.set_key(&action_clone, event_str) {
-                        Ok(_) => {
-                            let _ = self.config.save();
+                    let result = if self.remap_adding {
+                        self.config.add_key(&action_clone, event_str)
+                    } else {
+                        self.config.set_key(&action_clone, event_str)
+                    };
+                    match result {
+                        Ok(warning) => {
                             self.input_mode = InputMode::Config;
-                            self.error_message = None;
+                            self.error_message = self.config.save()
+                                .err()
+                                .map(|e| format!("Failed to save config: {}", e))
+                                .or(warning.map(str::to_string));
                         }
                         Err(e) => {
                             self.error_message = Some(e.to_string());
                         }
                     }
-                } else if code == KeyCode::Esc {
+                } else {
                     self.input_mode = InputMode::Config;
                     self.error_message = None;
                 }
@@ -627,69 +2484,1091 @@ impl App {
         }
     }
 
-    pub fn trigger_deep_search(&mut self) {
-        self.is_searching = true;
-        // Start deep search from root "/" instead of current path to be "Global"
-        let root = if cfg!(windows) { "C:\\" } else { "/" };
-        let results = self.manager.search_recursive(root, &self.search_query);
-        self.filtered_entries = results;
-        self.is_searching = false;
-        self.selected_index = 0;
+    pub fn on_mouse(&mut self, event: MouseEvent) {
+        self.dirty = true;
+        if !matches!(self.input_mode, InputMode::Normal) {
+            return;
+        }
+
+        match event.kind {
+            MouseEventKind::ScrollUp => {
+                self.selected_index = self.selected_index.saturating_sub(1);
+            }
+            MouseEventKind::ScrollDown => {
+                self.selected_index = (self.selected_index + 1).min(self.filtered_entries.len().saturating_sub(1));
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                let crumb = self.breadcrumb_areas.iter()
+                    .find(|(area, _)| area.contains(ratatui::layout::Position::new(event.column, event.row)))
+                    .map(|(_, path)| path.clone());
+                if let Some(path) = crumb {
+                    self.jump_to(&path);
+                } else if let Some(index) = self.list_index_at(event.column, event.row) {
+                    self.terminal_focused = false;
+                    let now = Instant::now();
+                    let is_double = self.last_click
+                        .map(|(i, at)| i == index && now.duration_since(at) < DOUBLE_CLICK_INTERVAL)
+                        .unwrap_or(false);
+                    if event.modifiers.contains(crossterm::event::KeyModifiers::SHIFT) {
+                        // Shift-click adds everything from the cursor to the clicked row
+                        self.select_range(self.selected_index, index);
+                        self.selected_index = index;
+                        self.last_click = None;
+                        self.list_state.select(Some(self.selected_index));
+                        return;
+                    }
+                    self.selected_index = index;
+                    if is_double {
+                        self.last_click = None;
+                        self.open_selected();
+                    } else {
+                        self.last_click = Some((index, now));
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.list_state.select(Some(self.selected_index));
+    }
+
+    /// Flip whether each listed entry is selected. Selected paths that aren't listed, e.g.
+    /// because a filter hides them, stay selected.
+    pub fn invert_selection(&mut self) {
+        for entry in &self.filtered_entries {
+            if entry.name != ".." && entry.name != "." && !self.selected_paths.remove(&entry.path) {
+                self.selected_paths.insert(entry.path.clone());
+            }
+        }
+    }
+
+    /// Open the description editor on `path`, starting from its current description.
+    fn begin_editing(&mut self, path: PathBuf, description: Option<String>) {
+        self.edit_buffer = description.unwrap_or_default();
+        self.edit_cursor = self.edit_buffer.len();
+        self.edit_scroll = (0, 0);
+        self.edit_target = path;
+        self.error_message = None;
+        self.input_mode = InputMode::Editing;
+    }
+
+    /// Add every entry between `from` and `to`, in either order, to the selection.
+    fn select_range(&mut self, from: usize, to: usize) {
+        let (start, end) = if from <= to { (from, to) } else { (to, from) };
+        for entry in self.filtered_entries.iter().take(end + 1).skip(start) {
+            if entry.name != ".." && entry.name != "." {
+                self.selected_paths.insert(entry.path.clone());
+            }
+        }
+    }
+
+    /// Select the range from the anchor to the cursor on top of what was selected before it.
+    fn extend_range_selection(&mut self) {
+        if let Some(anchor) = self.selection_anchor {
+            self.selected_paths = self.range_base.clone();
+            self.select_range(anchor, self.selected_index);
+        }
+    }
+
+    /// Map a screen position to an index in filtered_entries, if it lands on a list row.
+    /// list_area covers only the rows, without the border and column header.
+    fn list_index_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.list_area;
+        let inside_x = column >= area.x && column < area.x + area.width;
+        let inside_y = row >= area.y && row < area.y + area.height;
+        if !inside_x || !inside_y {
+            return None;
+        }
+        let index = match self.list_separator() {
+            Some(boundary) => {
+                let row = self.list_row_offset(boundary) + (row - area.y) as usize;
+                match row.cmp(&boundary) {
+                    std::cmp::Ordering::Less => row,
+                    std::cmp::Ordering::Equal => return None,
+                    std::cmp::Ordering::Greater => row - 1,
+                }
+            }
+            None => self.list_state.offset() + (row - area.y) as usize,
+        };
+        (index < self.filtered_entries.len()).then_some(index)
+    }
+
+    /// Enter a directory or open a file with the system default application.
+    fn open_selected(&mut self) {
+        if let Some(entry) = self.filtered_entries.get(self.selected_index) {
+            if entry.is_dir {
+                let path = entry.path.clone();
+                match self.manager.navigate_to(&path) {
+                    Ok(_) => self.on_directory_changed(),
+                    Err(e) => self.error_message = Some(format!("Cannot open {}: {}", path.display(), e)),
+                }
+            } else if self.showing_search_results {
+                // Results come from all over, so show the file where it lives first
+                let path = entry.path.clone();
+                self.reveal(&path);
+            } else if let Err(e) = opener::open(&entry.path) {
+                self.error_message = Some(format!("Cannot open {}: {}", entry.path.display(), e));
+            }
+        }
+    }
+
+    /// Browse the folder `path` is in with the cursor on it, leaving any search results.
+    fn reveal(&mut self, path: &Path) {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else { return };
+        match self.manager.navigate_to(parent) {
+            Ok(_) => {
+                self.on_directory_changed();
+                // The listing has the canonical path, which a result found through a link lacks
+                let target = self.manager.current_path().join(name);
+                self.select_path(&target);
+            }
+            Err(e) => self.error_message = Some(format!("Cannot open {}: {}", parent.display(), e)),
+        }
+    }
+
+    /// Row and column (in chars) of the editor cursor, for placing the terminal cursor.
+    pub fn edit_cursor_position(&self) -> (usize, usize) {
+        let before = &self.edit_buffer[..self.edit_cursor];
+        let row = before.matches('\n').count();
+        let column = before[line_start(before, before.len())..].chars().count();
+        (row, column)
+    }
+
+    /// Scroll the editor as little as needed to keep the cursor inside a text area of `height`
+    /// lines and `width` columns.
+    pub fn scroll_editor_to_cursor(&mut self, height: usize, width: usize) {
+        let (row, column) = self.edit_cursor_position();
+        let (top, left) = &mut self.edit_scroll;
+        for (offset, position, size) in [(top, row, height.max(1)), (left, column, width.max(1))] {
+            if position < *offset {
+                *offset = position;
+            } else if position >= *offset + size {
+                *offset = position + 1 - size;
+            }
+        }
+    }
+
+    /// Navigate to a directory picked from the path bar, unless it's where we already are.
+    fn jump_to(&mut self, path: &Path) {
+        if path == self.manager.current_path() {
+            return;
+        }
+        match self.manager.navigate_to(path) {
+            Ok(_) => self.on_directory_changed(),
+            Err(e) => self.error_message = Some(format!("Cannot open {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Reset per-directory state after the manager moved to a new directory.
+    fn on_directory_changed(&mut self) {
+        if !self.showing_search_results
+            && let (Some(dir), Some(entry)) = (&self.listed_dir, self.filtered_entries.get(self.selected_index))
+        {
+            self.dir_positions.insert(dir.clone(), (entry.path.clone(), self.list_state.offset()));
+        }
+        self.selection_anchor = None;
+        self.clear_selection_if_needed();
+        self.search_query.clear();
+        self.leave_search_results();
+        self.refresh();
+        self.remember_dir();
+        self.selected_index = 0;
+        self.list_state.select(Some(self.selected_index));
+        if let Some((path, offset)) = self.dir_positions.get(self.manager.current_path()).cloned() {
+            self.select_path(&path);
+            *self.list_state.offset_mut() = offset;
+        }
+    }
+
+    fn current_tab(&self) -> Tab {
+        Tab {
+            manager: self.manager.clone(),
+            selected_index: self.selected_index,
+            list_state: self.list_state,
+        }
+    }
+
+    /// Open a new tab on the current directory, right after the active one.
+    fn new_tab(&mut self) {
+        let tab = self.current_tab();
+        self.tabs[self.active_tab] = tab.clone();
+        self.active_tab += 1;
+        self.tabs.insert(self.active_tab, tab);
+    }
+
+    fn close_tab(&mut self) {
+        if self.tabs.len() <= 1 {
+            return;
+        }
+        self.tabs.remove(self.active_tab);
+        self.active_tab = self.active_tab.min(self.tabs.len() - 1);
+        self.load_tab();
+    }
+
+    fn switch_tab(&mut self, forward: bool) {
+        let count = self.tabs.len();
+        if count <= 1 {
+            return;
+        }
+        self.tabs[self.active_tab] = self.current_tab();
+        self.active_tab = if forward {
+            (self.active_tab + 1) % count
+        } else {
+            (self.active_tab + count - 1) % count
+        };
+        self.load_tab();
+    }
+
+    /// Make the stored state of `active_tab` the live state.
+    fn load_tab(&mut self) {
+        let tab = self.tabs[self.active_tab].clone();
+        self.manager = tab.manager;
+        self.selection_anchor = None;
+        self.clear_selection_if_needed();
+        self.search_query.clear();
+        self.leave_search_results();
+        self.refresh();
+        self.selected_index = tab.selected_index.min(self.filtered_entries.len().saturating_sub(1));
+        self.list_state = tab.list_state;
+        self.list_state.select(Some(self.selected_index));
+    }
+
+    /// Directory names for the tab bar, using live state for the active tab.
+    pub fn tab_titles(&self) -> Vec<String> {
+        self.tabs.iter().enumerate().map(|(i, tab)| {
+            let path = if i == self.active_tab { self.manager.current_path() } else { tab.manager.current_path() };
+            path.file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string())
+        }).collect()
+    }
+
+    /// Run the prompt's command through the shell on a background task. `{}` is replaced by the
+    /// quoted paths of the selected entries (or the current one); without it they are appended.
+    fn run_shell_command(&mut self) -> Result<(), String> {
+        let template = self.prompt_buffer.trim().to_string();
+        if template.is_empty() {
+            return Err("Command cannot be empty".to_string());
+        }
+        let mut paths: Vec<PathBuf> = self.selected_paths.iter().cloned().collect();
+        paths.sort();
+        if paths.is_empty()
+            && let Some(entry) = self.filtered_entries.get(self.selected_index)
+        {
+            paths.push(entry.path.clone());
+        }
+        let quoted = paths.iter().map(|p| shell_quote(&p.to_string_lossy())).collect::<Vec<_>>().join(" ");
+        let command = if template.contains("{}") {
+            template.replace("{}", &quoted)
+        } else {
+            format!("{} {}", template, quoted)
+        };
+
+        let mut shell = if cfg!(windows) {
+            let mut cmd = std::process::Command::new("cmd");
+            cmd.arg("/C");
+            cmd
+        } else {
+            let mut cmd = std::process::Command::new("sh");
+            cmd.arg("-c");
+            cmd
+        };
+        // Its own process group, so stopping it also stops whatever it started
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            shell.process_group(0);
+        }
+        // Spawned here rather than on the worker so the pid is known while it runs
+        let child = shell.arg(&command)
+            .current_dir(self.manager.current_path())
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start shell: {}", e))?;
+        self.command_pid = Some(child.id());
+
+        let (tx, rx) = channel();
+        tokio::task::spawn_blocking(move || {
+            let text = match child.wait_with_output() {
+                Ok(output) => format!(
+                    "$ {}\n{}{}\n[{}]",
+                    command,
+                    String::from_utf8_lossy(&output.stdout),
+                    String::from_utf8_lossy(&output.stderr),
+                    output.status
+                ),
+                Err(e) => format!("$ {}\nFailed to run shell: {}", command, e),
+            };
+            let _ = tx.send(text);
+        });
+
+        self.command_output = None;
+        self.command_rx = Some(rx);
+        self.prompt_buffer.clear();
+        Ok(())
+    }
+
+    /// Commands configured for the selected entry's extension, followed by the "*" catch-all ones.
+    pub fn open_with_candidates(&self) -> Vec<String> {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else { return Vec::new() };
+        let extension = if entry.is_dir {
+            String::new()
+        } else {
+            entry.path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default()
+        };
+        let mut candidates: Vec<String> = Vec::new();
+        for key in [extension.as_str(), "*"] {
+            for command in self.config.open_with.get(key).into_iter().flatten() {
+                if !candidates.contains(command) {
+                    candidates.push(command.clone());
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Launch `command` (program plus optional arguments) with the selected entry's path appended.
+    fn open_with(&mut self, command: &str) -> Result<(), String> {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else {
+            return Err("Nothing selected".to_string());
+        };
+        let mut parts = command.split_whitespace();
+        let Some(program) = parts.next() else {
+            return Err("Command cannot be empty".to_string());
+        };
+        // Detach from the TUI so the child can't scribble over the screen
+        std::process::Command::new(program)
+            .args(parts)
+            .arg(&entry.path)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("Cannot run {}: {}", program, e))
+    }
+
+    fn add_bookmark(&mut self) {
+        let path = self.manager.current_path().to_string_lossy().to_string();
+        if self.config.bookmarks.contains(&path) {
+            self.error_message = Some(format!("{} is already bookmarked", path));
+            return;
+        }
+        self.config.bookmarks.push(path.clone());
+        match self.config.save() {
+            Ok(()) => self.notify(format!("Bookmarked {}", path)),
+            Err(e) => self.error_message = Some(format!("Failed to save bookmarks: {}", e)),
+        }
+    }
+
+    /// Back up every description below the current folder into a new file inside it.
+    fn export_descriptions(&mut self) {
+        let dir = self.manager.current_path().to_path_buf();
+        let file = FileSystemManager::unique_destination(&dir, OsStr::new(crate::metadata::EXPORT_FILE_NAME));
+        match crate::metadata::export_descriptions(&dir, &file) {
+            Ok(count) => {
+                let name = file.file_name().unwrap_or_default().to_string_lossy();
+                self.notify(format!("Exported {} to {}", count_label(count, "description"), name));
+            }
+            Err(e) => self.error_message = Some(format!("Failed to export descriptions: {}", e)),
+        }
+        self.refresh();
+    }
+
+    /// Restore descriptions from the highlighted export file onto the folder holding it.
+    fn import_descriptions(&mut self) {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else { return };
+        if entry.is_dir {
+            self.error_message = Some("Select an exported descriptions file to import".to_string());
+            return;
+        }
+        // Relative to the file's own folder, which differs from the listing in search results
+        let file = entry.path.clone();
+        let dir = file.parent().unwrap_or(self.manager.current_path()).to_path_buf();
+        match crate::metadata::import_descriptions(&dir, &file) {
+            Ok((imported, 0)) => self.notify(format!("Imported {}", count_label(imported, "description"))),
+            Ok((imported, missing)) => self.notify(format!(
+                "Imported {}, {} not found here",
+                count_label(imported, "description"),
+                count_label(missing, "path"),
+            )),
+            Err(e) => self.error_message = Some(format!("Failed to import {}: {}", entry.name, e)),
+        }
+        self.refresh();
+    }
+
+    fn open_bookmark(&mut self) {
+        let Some(bookmark) = self.config.bookmarks.get(self.bookmark_index) else { return };
+        let path = PathBuf::from(bookmark);
+        match self.manager.navigate_to(&path) {
+            Ok(_) => {
+                self.input_mode = InputMode::Normal;
+                self.on_directory_changed();
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Cannot open {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    fn open_recent_dir(&mut self) {
+        let Some(recent) = self.config.recent_dirs.get(self.recent_index) else { return };
+        let path = recent.path.clone();
+        match self.manager.navigate_to(&path) {
+            Ok(_) => {
+                self.input_mode = InputMode::Normal;
+                self.on_directory_changed();
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Cannot open {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    /// Move the current directory to the top of the recent list. The list is saved with the
+    /// rest of the config when Xplore quits.
+    fn remember_dir(&mut self) {
+        let path = self.manager.current_path().to_path_buf();
+        let recent = &mut self.config.recent_dirs;
+        recent.retain(|r| r.path != path);
+        recent.insert(0, RecentDir { path, visited: chrono::Local::now().timestamp() });
+        recent.truncate(self.config.recent_dirs_limit);
+    }
+
+    fn remove_bookmark(&mut self) {
+        if self.bookmark_index < self.config.bookmarks.len() {
+            self.config.bookmarks.remove(self.bookmark_index);
+            self.bookmark_index = self.bookmark_index.min(self.config.bookmarks.len().saturating_sub(1));
+            if let Err(e) = self.config.save() {
+                self.error_message = Some(format!("Failed to save bookmarks: {}", e));
+            }
+        }
+    }
+
+    fn remove_recent_dir(&mut self) {
+        if self.recent_index < self.config.recent_dirs.len() {
+            self.config.recent_dirs.remove(self.recent_index);
+            self.recent_index = self.recent_index.min(self.config.recent_dirs.len().saturating_sub(1));
+            if let Err(e) = self.config.save() {
+                self.error_message = Some(format!("Failed to save recent folders: {}", e));
+            }
+        }
+    }
+
+    /// Open the search box. An empty one starts out holding the last query, which Enter reuses
+    /// and typing replaces; a query still filtering the listing is kept for editing instead.
+    fn start_search_input(&mut self) {
+        self.search_history_index = None;
+        if self.search_query.is_empty()
+            && let Some(last) = self.search_history.last()
+        {
+            self.search_query = last.clone();
+            self.search_prefilled = true;
+            self.apply_filter();
+        }
+        self.input_mode = InputMode::Search;
+    }
+
+    /// Leave the search box, remembering the query in the history.
+    fn finish_search_input(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.search_prefilled = false;
+        self.search_history_index = None;
+        if self.search_query.is_empty() {
+            return;
+        }
+        self.search_history.retain(|query| *query != self.search_query);
+        self.search_history.push(self.search_query.clone());
+        if self.search_history.len() > SEARCH_HISTORY_LIMIT {
+            self.search_history.remove(0);
+        }
+    }
+
+    /// Step through the search history, `older` first with Up; stepping past the newest entry
+    /// empties the box.
+    fn recall_search(&mut self, older: bool) {
+        let len = self.search_history.len();
+        self.search_history_index = match (self.search_history_index, older) {
+            (_, _) if len == 0 => None,
+            (None, true) => Some(len - 1),
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (None, false) => None,
+            (Some(i), false) => (i + 1 < len).then_some(i + 1),
+        };
+        self.search_query = self.search_history_index
+            .map(|i| self.search_history[i].clone())
+            .unwrap_or_default();
+        self.search_prefilled = false;
+        self.apply_filter();
+    }
+
+    pub fn trigger_deep_search(&mut self) {
+        let Ok(matcher) = Matcher::new(&self.search_query, self.search_mode) else { return };
+        self.cancel_search();
+        self.search_ranking = (self.search_mode == SearchMode::Fuzzy).then(|| matcher.clone());
+        self.is_searching = true;
+        self.search_truncated = false;
+        self.showing_search_results = true;
+        self.filtered_entries.clear();
+        self.content_matches.clear();
+        self.selected_index = 0;
+
+        let root = self.search_root();
+        self.search_results_root = root.clone();
+        let search_contents = self.search_contents;
+        let limit = self.config.max_search_results;
+        let follow_links = self.config.search_follow_links;
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = channel();
+
+        let task_cancel = cancel.clone();
+        tokio::task::spawn_blocking(move || {
+            FileSystemManager::search_recursive(root, &matcher, search_contents, follow_links, limit, |entry, content_match| {
+                !task_cancel.load(Ordering::Relaxed) && tx.send((entry, content_match)).is_ok()
+            });
+        });
+
+        self.search_rx = Some(rx);
+        self.search_cancel = Some(cancel);
+    }
+
+    /// Sum the size of the directory at `path` in the background, replacing any calculation
+    /// already running.
+    fn calculate_size(&mut self, path: PathBuf) {
+        self.cancel_size();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = channel();
+
+        let task_path = path.clone();
+        let task_cancel = cancel.clone();
+        tokio::task::spawn_blocking(move || {
+            let _ = tx.send(FileSystemManager::directory_size(&task_path, &task_cancel));
+        });
+
+        self.sizing_path = Some(path);
+        self.size_rx = Some(rx);
+        self.size_cancel = Some(cancel);
+    }
+
+    /// Stop a running size calculation without recording anything.
+    pub fn cancel_size(&mut self) {
+        if let Some(cancel) = self.size_cancel.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        self.size_rx = None;
+        self.sizing_path = None;
+        self.dirty = true;
+    }
+
+    /// Directory a deep search walks, depending on the active scope.
+    pub fn search_root(&self) -> PathBuf {
+        match self.search_scope {
+            SearchScope::Global => PathBuf::from(if cfg!(windows) { "C:\\" } else { "/" }),
+            SearchScope::Current => self.manager.current_path().to_path_buf(),
+        }
+    }
+
+    /// Stop a running deep search, keeping whatever results have arrived so far.
+    pub fn cancel_search(&mut self) {
+        if let Some(cancel) = self.search_cancel.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        self.search_rx = None;
+        self.is_searching = false;
+    }
+
+    fn leave_search_results(&mut self) {
+        self.cancel_search();
+        self.showing_search_results = false;
+        self.content_matches.clear();
+    }
+
+    fn perform_clipboard_action(&mut self, mode: ClipboardMode) {
+        let mut paths = self.selected_paths.clone();
+        if paths.is_empty()
+            && let Some(entry) = self.filtered_entries.get(self.selected_index)
+            && entry.name != ".." && entry.name != "."
+        {
+            paths.insert(entry.path.clone());
+        }
+        if !paths.is_empty() {
+            let verb = match mode {
+                ClipboardMode::Copy => "Copied",
+                ClipboardMode::Cut => "Cut",
+                ClipboardMode::Link => "Marked to link",
+            };
+            self.notify(format!("{} {}, ready to paste", verb, count_label(paths.len(), "item")));
+            self.clipboard = Some(Clipboard { paths, mode });
+            self.selected_paths.clear();
+        }
+    }
+
+    /// Put the absolute paths of the selected entries (or the current one), one per line,
+    /// on the OS clipboard for use in other applications.
+    fn copy_paths_to_system_clipboard(&mut self) {
+        let mut paths: Vec<PathBuf> = self.selected_paths.iter().cloned().collect();
+        paths.sort();
+        if paths.is_empty()
+            && let Some(entry) = self.filtered_entries.get(self.selected_index)
+        {
+            paths.push(entry.path.clone());
+        }
+        if paths.is_empty() {
+            return;
+        }
+        let text = paths.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>().join("\n");
+
+        if self.system_clipboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.system_clipboard = Some(clipboard),
+                Err(e) => {
+                    self.error_message = Some(format!("System clipboard unavailable: {}", e));
+                    return;
+                }
+            }
+        }
+        if let Some(clipboard) = &mut self.system_clipboard {
+            match clipboard.set_text(text) {
+                Ok(()) => self.notify(format!("Copied {} to the clipboard", count_label(paths.len(), "path"))),
+                Err(e) => self.error_message = Some(format!("Failed to copy paths: {}", e)),
+            }
+        }
+    }
+
+    fn paste_clipboard(&mut self) {
+        if self.copy_rx.is_some() {
+            self.error_message = Some("A paste is already in progress".to_string());
+            return;
+        }
+        if let Some(clipboard) = self.clipboard.clone() {
+            let mut items: Vec<PathBuf> = clipboard.paths.into_iter().collect();
+            items.sort();
+            if let ClipboardMode::Cut = clipboard.mode {
+                self.clipboard = None;
+            }
+            self.pending_paste = Some(PendingPaste {
+                destination: self.paste_destination(),
+                mode: clipboard.mode,
+                items: items.into(),
+                planned: Vec::new(),
+            });
+            self.continue_paste();
+        }
+    }
+
+    /// Plan queued items until one collides with an existing destination, then ask the user.
+    /// Once every item is planned the transfer starts in the background.
+    fn continue_paste(&mut self) {
+        while let Some(pending) = &mut self.pending_paste {
+            let Some(src) = pending.items.front().cloned() else {
+                self.start_transfer();
+                return;
+            };
+            let Some(file_name) = src.file_name() else {
+                pending.items.pop_front();
+                continue;
+            };
+            let dst = pending.destination.join(file_name);
+            let planned = pending.planned.iter().any(|item| item.dst == dst);
+            if planned || dst.symlink_metadata().is_ok() {
+                self.prompt_index = 0;
+                self.input_mode = InputMode::Prompt(PromptType::PasteConflict);
+                return;
+            }
+            pending.items.pop_front();
+            pending.planned.push(TransferItem { src, dst, replace: false });
+        }
     }
 
-    fn perform_clipboard_action(&mut self, mode: ClipboardMode) {
-        let mut paths = self.selected_paths.clone();
-        if paths.is_empty() {
-            if let Some(entry) = self.filtered_entries.get(self.selected_index) {
-                if entry.name != ".." && entry.name != "." {
-                    paths.insert(entry.path.clone());
+    fn resolve_paste_conflict(&mut self, choice: ConflictChoice) {
+        let Some(pending) = &mut self.pending_paste else { return };
+        let Some(src) = pending.items.pop_front() else { return };
+        if let Some(file_name) = src.file_name() {
+            let dst = pending.destination.join(file_name);
+            match choice {
+                ConflictChoice::Skip => {}
+                // Pasting an item onto itself would destroy the source
+                ConflictChoice::Overwrite if dst == src => {}
+                ConflictChoice::Overwrite => pending.planned.push(TransferItem { src, dst, replace: true }),
+                ConflictChoice::Rename => {
+                    let dst = FileSystemManager::unique_destination(&pending.destination, file_name);
+                    pending.planned.push(TransferItem { src, dst, replace: false });
                 }
             }
         }
-        if !paths.is_empty() {
-            self.clipboard = Some(Clipboard { paths, mode });
-            self.selected_paths.clear();
+        self.continue_paste();
+    }
+
+    fn start_transfer(&mut self) {
+        let Some(pending) = self.pending_paste.take() else { return };
+        if pending.planned.is_empty() {
+            return;
         }
+        let (kind, verb) = match pending.mode {
+            ClipboardMode::Copy => (TransferKind::Copy, "Copied"),
+            ClipboardMode::Cut => (TransferKind::Move, "Moved"),
+            ClipboardMode::Link => (TransferKind::Link, "Linked"),
+        };
+        self.transfer_done = format!("{} {}", verb, count_label(pending.planned.len(), "item"));
+        let manager = self.manager.clone();
+        let use_trash = self.config.use_trash;
+        let copy_options = self.config.copy_options();
+        let (tx, rx) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let task_cancel = cancel.clone();
+        tokio::task::spawn_blocking(move || {
+            transfer::run_transfer(manager, kind, pending.planned, copy_options, use_trash, task_cancel, tx);
+        });
+        self.transfer_title = " Pasting ";
+        self.copy_rx = Some(rx);
+        self.transfer_cancel = Some(cancel);
+        self.copy_progress = Some(CopyProgress::default());
     }
 
-    fn paste_clipboard(&mut self) {
-        if let Some(clipboard) = self.clipboard.clone() {
-            for src in clipboard.paths {
-                if let Some(file_name) = src.file_name() {
-                    let dst = self.manager.current_path().join(file_name);
-                    match clipboard.mode {
-                        ClipboardMode::Copy => {
-                            let _ = self.manager.copy_recursive(&src, &dst);
-                        }
-                        ClipboardMode::Cut => {
-                            let _ = self.manager.move_entry(&src, &dst);
-                        }
-                    }
-                }
-            }
-            if let ClipboardMode::Cut = clipboard.mode {
-                self.clipboard = None;
+    /// Create a file from the prompt; a trailing slash creates a directory instead.
+    fn create_from_prompt(&mut self) -> Result<(), String> {
+        let name = self.prompt_buffer.trim();
+        let is_dir = name.ends_with('/') || name.ends_with(std::path::MAIN_SEPARATOR);
+        let name = name.trim_end_matches(['/', std::path::MAIN_SEPARATOR]);
+        if name.is_empty() {
+            return Err("Name cannot be empty".to_string());
+        }
+
+        let path = self.manager.current_path().join(name);
+        let result = if is_dir {
+            if path.exists() {
+                return Err(format!("'{}' already exists", name));
             }
-            self.refresh();
+            self.manager.create_dir(&path)
+        } else {
+            self.manager.create_file(&path)
+        };
+        result.map_err(|e| e.to_string())?;
+        self.refresh();
+        Ok(())
+    }
+
+    /// Tab-complete the prompt buffer against entries relative to the current directory.
+    fn complete_prompt(&mut self, dirs_only: bool) {
+        if let Some(completed) = self.completion.complete(self.manager.current_path(), &self.prompt_buffer, dirs_only) {
+            self.prompt_buffer = completed;
         }
     }
 
-    fn delete_selected(&mut self) {
-        let mut paths = self.selected_paths.clone();
+    fn go_to_input_path(&mut self) -> Result<(), String> {
+        let input = self.prompt_buffer.trim();
+        if input.is_empty() {
+            return Err("Path cannot be empty".to_string());
+        }
+        let path = resolve_input(self.manager.current_path(), input);
+        if !path.exists() {
+            return Err(format!("'{}' does not exist", input));
+        }
+        if !path.is_dir() {
+            return Err(format!("'{}' is not a directory", input));
+        }
+        self.manager.navigate_to(&path).map_err(|e| e.to_string())?;
+        self.on_directory_changed();
+        Ok(())
+    }
+
+    fn rename_selected(&mut self) -> Result<(), String> {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else {
+            return Err("Nothing selected".to_string());
+        };
+        let old_path = entry.path.clone();
+        let new_name = self.prompt_buffer.trim().to_string();
+
+        if new_name.is_empty() {
+            return Err("Name cannot be empty".to_string());
+        }
+        if new_name.contains(['/', std::path::MAIN_SEPARATOR]) || new_name == "." || new_name == ".." {
+            return Err(format!("Invalid name: {}", new_name));
+        }
+        if new_name == entry.name {
+            return Ok(());
+        }
+
+        // A deep search result usually lives in another folder than the one being browsed
+        let Some(folder) = old_path.parent() else {
+            return Err("Cannot rename this entry".to_string());
+        };
+        let new_path = folder.join(&new_name);
+        if new_path.symlink_metadata().is_ok() {
+            return Err(format!("'{}' already exists", new_name));
+        }
+
+        self.manager.move_entry(&old_path, &new_path).map_err(|e| e.to_string())?;
+        self.record_undo(vec![Operation::Move { from: old_path, to: new_path.clone() }]);
+        // Results aren't listed again on refresh, so point this one at its new name
+        if self.showing_search_results
+            && let Some(entry) = self.filtered_entries.get_mut(self.selected_index)
+        {
+            entry.name = new_name;
+            entry.path = new_path;
+        }
+        self.refresh();
+        Ok(())
+    }
+
+    /// Ask for the name of a zip archive of the selected entries, or the current one.
+    fn start_archive_prompt(&mut self) {
+        let mut paths: Vec<PathBuf> = self.selected_paths.iter().cloned().collect();
+        paths.sort();
+        if paths.is_empty()
+            && let Some(entry) = self.filtered_entries.get(self.selected_index)
+            && entry.name != ".." && entry.name != "."
+        {
+            paths.push(entry.path.clone());
+        }
         if paths.is_empty() {
-            if let Some(entry) = self.filtered_entries.get(self.selected_index) {
-                if entry.name != ".." && entry.name != "." {
-                    paths.insert(entry.path.clone());
+            return;
+        }
+        self.prompt_buffer = match paths.as_slice() {
+            [only] => format!("{}.zip", only.file_stem().unwrap_or_default().to_string_lossy()),
+            _ => "archive.zip".to_string(),
+        };
+        self.archive_paths = paths;
+        self.error_message = None;
+        self.input_mode = InputMode::Prompt(PromptType::Archive);
+    }
+
+    /// Start zipping the entries picked for the Archive prompt into the current directory,
+    /// on a background task that reports progress like a paste.
+    fn start_archive(&mut self) -> Result<(), String> {
+        if self.copy_rx.is_some() {
+            return Err("Wait for the running paste to finish".to_string());
+        }
+        let mut name = self.prompt_buffer.trim().to_string();
+        if name.is_empty() {
+            return Err("Name cannot be empty".to_string());
+        }
+        if name.contains(['/', std::path::MAIN_SEPARATOR]) || name == "." || name == ".." {
+            return Err(format!("Invalid name: {}", name));
+        }
+        if !name.to_lowercase().ends_with(".zip") {
+            name.push_str(".zip");
+        }
+        let dst = self.manager.current_path().join(&name);
+        if dst.symlink_metadata().is_ok() {
+            return Err(format!("'{}' already exists", name));
+        }
+
+        let manager = self.manager.clone();
+        let paths = std::mem::take(&mut self.archive_paths);
+        let (tx, rx) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let task_cancel = cancel.clone();
+        tokio::task::spawn_blocking(move || {
+            transfer::run_archive(manager, paths, dst, task_cancel, tx);
+        });
+        self.selected_paths.clear();
+        self.transfer_title = " Compressing ";
+        self.transfer_done = format!("Created {}", name);
+        self.copy_rx = Some(rx);
+        self.transfer_cancel = Some(cancel);
+        self.copy_progress = Some(CopyProgress::default());
+        Ok(())
+    }
+
+    /// Unpack the selected archive into a new folder named after it, in the background.
+    fn start_extract(&mut self) -> Result<(), String> {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else { return Ok(()) };
+        let Some(format) = archive::Format::detect(&entry.path).filter(|_| !entry.is_dir) else {
+            return Err(format!("{} is not a zip or tar archive", entry.name));
+        };
+        if self.copy_rx.is_some() {
+            return Err("Wait for the running paste to finish".to_string());
+        }
+        let folder = format.strip_extension(&entry.name);
+        let dst = FileSystemManager::unique_destination(self.manager.current_path(), OsStr::new(&folder));
+        let path = entry.path.clone();
+        self.transfer_done = format!("Extracted into {}", dst.file_name().unwrap_or_default().to_string_lossy());
+
+        let manager = self.manager.clone();
+        let (tx, rx) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let task_cancel = cancel.clone();
+        tokio::task::spawn_blocking(move || {
+            transfer::run_extract(manager, path, dst, task_cancel, tx);
+        });
+        self.transfer_title = " Extracting ";
+        self.copy_rx = Some(rx);
+        self.transfer_cancel = Some(cancel);
+        self.copy_progress = Some(CopyProgress::default());
+        Ok(())
+    }
+
+    /// Create a link named after the prompt in the current directory, pointing to the entry
+    /// the prompt was opened on. The link can be undone like a newly created file.
+    fn create_symlink_from_prompt(&mut self) -> Result<(), String> {
+        let Some(target) = self.symlink_target.clone() else {
+            return Err("Nothing selected".to_string());
+        };
+        let name = self.prompt_buffer.trim();
+        if name.is_empty() {
+            return Err("Name cannot be empty".to_string());
+        }
+        if name.contains(['/', std::path::MAIN_SEPARATOR]) || name == "." || name == ".." {
+            return Err(format!("Invalid name: {}", name));
+        }
+        let link = self.manager.current_path().join(name);
+        if link.symlink_metadata().is_ok() {
+            return Err(format!("'{}' already exists", name));
+        }
+
+        self.manager.create_symlink(&target, &link).map_err(|e| e.to_string())?;
+        self.record_undo(vec![Operation::Create { path: link.clone() }]);
+        self.refresh();
+        self.select_path(&link);
+        Ok(())
+    }
+
+    fn start_permissions_edit(&mut self) {
+        let Some(entry) = self.filtered_entries.get(self.selected_index) else { return };
+        if entry.name == ".." {
+            return;
+        }
+        let path = entry.path.clone();
+        match self.manager.permissions_mode(&path) {
+            Ok(mode) => {
+                self.permission_target = Some(path);
+                self.permission_mode = mode;
+                self.permission_cursor = 0;
+                self.error_message = None;
+                self.input_mode = InputMode::Permissions;
+            }
+            Err(e) => self.error_message = Some(format!("Cannot read permissions: {}", e)),
+        }
+    }
+
+    fn apply_permissions(&mut self) -> Result<(), String> {
+        let Some(path) = self.permission_target.clone() else {
+            return Err("Nothing selected".to_string());
+        };
+        self.manager.set_permissions_mode(&path, self.permission_mode).map_err(|e| e.to_string())?;
+        self.refresh();
+        Ok(())
+    }
+
+    fn start_batch_rename(&mut self) {
+        // Number the entries in the order they're listed; any not in view go last
+        let mut paths: Vec<PathBuf> = self.filtered_entries.iter()
+            .filter(|e| self.selected_paths.contains(&e.path) && e.name != "." && e.name != "..")
+            .map(|e| e.path.clone())
+            .collect();
+        let mut hidden: Vec<PathBuf> = self.selected_paths.iter()
+            .filter(|p| !paths.contains(p))
+            .cloned()
+            .collect();
+        hidden.sort();
+        paths.extend(hidden);
+
+        self.batch_rename_paths = paths;
+        self.prompt_buffer = "{name}.{ext}".to_string();
+        self.error_message = None;
+        self.input_mode = InputMode::BatchRename;
+    }
+
+    /// Preview of the batch rename for the current pattern.
+    pub fn batch_rename_plan(&self) -> Vec<(PathBuf, Result<PathBuf, String>)> {
+        plan_batch_rename(&self.batch_rename_paths, &self.prompt_buffer)
+    }
+
+    fn apply_batch_rename(&mut self) -> Result<(), String> {
+        let mut plan = Vec::new();
+        for (src, result) in self.batch_rename_plan() {
+            match result {
+                Ok(dst) => plan.push((src, dst)),
+                Err(e) => {
+                    let name = src.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                    return Err(format!("{}: {}", name, e));
                 }
             }
         }
+        let (operations, error) = execute_batch_rename(&self.manager, &plan);
+        self.record_undo(operations);
+        self.selected_paths.clear();
+        self.refresh();
+        error.map_or(Ok(()), Err)
+    }
+
+    /// What a delete would remove: the selection, or else the current entry. Sorted so the
+    /// confirmation lists it in a stable order.
+    pub fn delete_targets(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.selected_paths.iter().cloned().collect();
+        if paths.is_empty()
+            && let Some(entry) = self.filtered_entries.get(self.selected_index)
+            && entry.name != ".." && entry.name != "."
+        {
+            paths.push(entry.path.clone());
+        }
+        paths.sort();
+        paths
+    }
+
+    fn delete_selected(&mut self, permanent: bool) {
+        let paths = self.delete_targets();
+        let mut operations = Vec::new();
+        let mut deleted = 0;
         for path in paths {
-            let _ = self.manager.delete_recursive(&path);
+            let result = if permanent {
+                self.manager.delete_recursive(&path)
+            } else {
+                self.manager.trash(&path).map(|trashed| {
+                    operations.push(Operation::Trash { original: path.clone(), trashed });
+                })
+            };
+            match result {
+                Ok(()) => deleted += 1,
+                Err(e) => self.error_message = Some(format!("Failed to delete {}: {}", path.display(), e)),
+            }
         }
+        if deleted > 0 {
+            let items = count_label(deleted, "item");
+            self.notify(if permanent { format!("Deleted {}", items) } else { format!("Moved {} to the trash", items) });
+        }
+        self.record_undo(operations);
         self.selected_paths.clear();
         self.refresh();
     }
 
+    /// Remember a finished action so it can be undone; the oldest ones fall off past UNDO_LIMIT.
+    fn record_undo(&mut self, operations: Vec<Operation>) {
+        if operations.is_empty() {
+            return;
+        }
+        if self.undo_stack.len() == UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(operations);
+    }
+
+    /// Reverse the most recent move, rename, trash or paste.
+    pub fn undo(&mut self) {
+        if self.copy_rx.is_some() {
+            self.error_message = Some("Wait for the paste to finish before undoing".to_string());
+            return;
+        }
+        let Some(operations) = self.undo_stack.pop() else {
+            self.error_message = Some("Nothing to undo".to_string());
+            return;
+        };
+        // Later steps may depend on earlier ones, e.g. a paste that trashed what it replaced
+        let mut failed = false;
+        for operation in operations.iter().rev() {
+            if let Err(e) = operation.undo(&self.manager, self.config.use_trash) {
+                self.error_message = Some(format!("Undo failed: {}", e));
+                failed = true;
+            }
+        }
+        if !failed {
+            self.notify(format!("Undid {}", count_label(operations.len(), "change")));
+        }
+        self.refresh();
+    }
+
     fn clear_selection_if_needed(&mut self) {
         // Only clear if the selected items are NOT in the clipboard.
         // If they ARE in the clipboard, the user might want to navigate to paste them.
@@ -709,3 +3588,648 @@ impl App {
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    const NONE: KeyModifiers = KeyModifiers::NONE;
+
+    /// An app in a new folder holding `names`, where a trailing '/' makes a folder. It starts
+    /// from the default config, so the tests never read or write the user's own.
+    fn test_app(names: &[&str]) -> (tempfile::TempDir, App) {
+        let dir = tempfile::tempdir().unwrap();
+        for name in names {
+            let path = dir.path().join(name);
+            if name.ends_with('/') {
+                std::fs::create_dir_all(path).unwrap();
+            } else {
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(path, "").unwrap();
+            }
+        }
+        let app = App::with_config(Some(dir.path().to_path_buf()), Config::default());
+        (dir, app)
+    }
+
+    #[test]
+    fn test_editor_cursor_movement() {
+        let text = "first line\nab\nthird";
+        // Column 5 on the first line clamps to the end of the short second line
+        let down = move_vertically(text, 5, false);
+        assert_eq!(down, 13);
+        assert_eq!(move_vertically(text, down, false), 16);
+        assert_eq!(move_vertically(text, 16, true), 13);
+        assert_eq!(move_vertically(text, 3, true), 3);
+        assert_eq!(line_start(text, 13), 11);
+        assert_eq!(line_end(text, 11), 13);
+
+        let wide = "añb";
+        assert_eq!(next_char_boundary(wide, 1), 3);
+        assert_eq!(prev_char_boundary(wide, 3), 1);
+        assert_eq!(prev_char_boundary(wide, 0), 0);
+    }
+
+    #[tokio::test]
+    async fn test_calc_size_runs_in_the_background() {
+        let (dir, mut app) = test_app(&["sub/"]);
+        std::fs::write(dir.path().join("sub/a"), "12345").unwrap();
+        let sub = dir.path().join("sub");
+        app.selected_index = app.filtered_entries.iter().position(|e| e.path == sub).unwrap();
+
+        app.on_key(KeyCode::Char('z'), NONE);
+        assert_eq!(app.sizing_path.as_ref(), Some(&sub));
+        assert!(app.is_busy());
+        let started = Instant::now();
+        while app.size_rx.is_some() && started.elapsed() < Duration::from_secs(5) {
+            app.tick();
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(app.dir_sizes.get(&sub), Some(&5));
+        assert_eq!(app.sizing_path, None);
+
+        // Esc drops a running calculation and leaves the filter alone
+        app.dir_sizes.clear();
+        app.search_query = "s".to_string();
+        app.on_key(KeyCode::Char('z'), NONE);
+        app.on_key(KeyCode::Esc, NONE);
+        assert!(app.size_rx.is_none() && app.sizing_path.is_none());
+        assert_eq!(app.search_query, "s");
+        app.tick();
+        assert!(app.dir_sizes.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/tmp/a b"), "'/tmp/a b'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn test_count_prefix_repeats_movement() {
+        let (_dir, mut app) = test_app(&["file0", "file1", "file2", "file3", "file4", "file5", "file6", "file7", "file8", "file9"]);
+
+        app.on_key(KeyCode::Char('1'), NONE);
+        app.on_key(KeyCode::Char('0'), NONE);
+        assert_eq!(app.pending_count, Some(10));
+        app.on_key(KeyCode::Char('j'), NONE);
+        assert_eq!((app.selected_index, app.pending_count), (10, None));
+        app.on_key(KeyCode::Char('3'), NONE);
+        app.on_key(KeyCode::Up, NONE);
+        assert_eq!(app.selected_index, 7);
+
+        // Counts past the end stop at the last entry, and Esc drops a count
+        app.on_key(KeyCode::Char('9'), NONE);
+        app.on_key(KeyCode::Char('j'), NONE);
+        assert_eq!(app.selected_index, app.filtered_entries.len() - 1);
+        app.on_key(KeyCode::Char('4'), NONE);
+        app.on_key(KeyCode::Esc, NONE);
+        app.on_key(KeyCode::Char('k'), NONE);
+        assert_eq!(app.selected_index, app.filtered_entries.len() - 2);
+    }
+
+    #[test]
+    fn test_key_sequences() {
+        let (_dir, mut app) = test_app(&["a", "b", "c", "d", "e"]);
+
+        app.on_key(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(app.selected_index, app.filtered_entries.len() - 1);
+        app.on_key(KeyCode::Char('g'), NONE);
+        assert_eq!(app.pending_keys.as_deref(), Some("g"));
+        app.on_key(KeyCode::Char('g'), NONE);
+        assert_eq!((app.selected_index, app.pending_keys.as_deref()), (0, None));
+
+        // An unbound second key drops the sequence without doing anything
+        app.on_key(KeyCode::Char('g'), NONE);
+        app.on_key(KeyCode::Char('j'), NONE);
+        assert_eq!((app.selected_index, app.pending_keys.as_deref()), (0, None));
+    }
+
+    #[test]
+    fn test_type_ahead() {
+        let (_dir, mut app) = test_app(&["alpha", "apple", "Apricot", "banana"]);
+        let selected = |app: &App| app.filtered_entries[app.selected_index].name.clone();
+
+        // Letters keep their bindings until the jump key is pressed
+        app.on_key(KeyCode::Char('j'), NONE);
+        assert_eq!(app.selected_index, 1);
+        app.on_key(KeyCode::Char('a'), NONE);
+        assert_eq!((app.selected_index, app.type_ahead_text()), (1, None));
+
+        app.on_key(KeyCode::Char('f'), NONE);
+        assert_eq!(app.type_ahead_text(), Some(""));
+        app.on_key(KeyCode::Char('a'), NONE);
+        assert_eq!(selected(&app), "alpha");
+        // "p" is bound, but extends the text while it is fresh
+        app.on_key(KeyCode::Char('p'), NONE);
+        app.on_key(KeyCode::Char('r'), NONE);
+        assert_eq!(selected(&app), "Apricot");
+        assert_eq!(app.type_ahead_text(), Some("apr"));
+
+        // After Esc, the same letter typed again steps through the names, wrapping around
+        app.on_key(KeyCode::Esc, NONE);
+        app.on_key(KeyCode::Char('f'), NONE);
+        app.on_key(KeyCode::Char('a'), NONE);
+        assert_eq!(selected(&app), "alpha");
+        app.on_key(KeyCode::Char('a'), NONE);
+        assert_eq!(selected(&app), "apple");
+    }
+
+    #[test]
+    fn test_refresh_keeps_the_selected_entry() {
+        let (dir, mut app) = test_app(&["b", "c", "d"]);
+        app.select_path(&dir.path().join("c"));
+
+        // A new entry sorting ahead of the selection shifts it down by one
+        std::fs::write(dir.path().join("a"), "").unwrap();
+        app.refresh();
+        assert_eq!(app.filtered_entries[app.selected_index].name, "c");
+
+        // Once it's gone the position is kept instead
+        let index = app.selected_index;
+        std::fs::remove_file(dir.path().join("c")).unwrap();
+        app.refresh();
+        assert_eq!(app.selected_index, index);
+        assert_eq!(app.filtered_entries[app.selected_index].name, "d");
+    }
+
+    #[test]
+    fn test_range_selection_follows_the_cursor() {
+        let (_dir, mut app) = test_app(&["a", "b", "c", "d"]);
+        let selected = |app: &App| {
+            let mut names: Vec<String> = app.selected_paths.iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        // Ranges never pick up "." and ".."
+        app.on_key(KeyCode::Char('v'), NONE);
+        app.on_key(KeyCode::Char('2'), NONE);
+        app.on_key(KeyCode::Char('j'), NONE);
+        assert_eq!(selected(&app), ["a"]);
+        app.on_key(KeyCode::Char('2'), NONE);
+        app.on_key(KeyCode::Char('j'), NONE);
+        assert_eq!(selected(&app), ["a", "b", "c"]);
+        // Moving back shrinks the range, and ending it keeps what's selected
+        app.on_key(KeyCode::Char('k'), NONE);
+        app.on_key(KeyCode::Char('v'), NONE);
+        app.on_key(KeyCode::Char('j'), NONE);
+        app.on_key(KeyCode::Char('j'), NONE);
+        assert_eq!(selected(&app), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_describing_the_current_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("project")).unwrap();
+        let mut app = App::with_config(Some(dir.path().join("project")), Config::default());
+
+        app.on_key(KeyCode::Char('E'), KeyModifiers::SHIFT);
+        for c in "notes".chars() {
+            app.on_key(KeyCode::Char(c), NONE);
+        }
+        app.on_key(KeyCode::F(2), NONE);
+        assert_eq!(app.filtered_entries[0].description.as_deref(), Some("notes"));
+
+        app.on_key(KeyCode::Backspace, NONE);
+        let entry = app.filtered_entries.iter().find(|e| e.name == "project").unwrap();
+        assert_eq!(entry.description.as_deref(), Some("notes"));
+    }
+
+    #[test]
+    fn test_fuzzy_filter_ranks_by_score() {
+        let (_dir, mut app) = test_app(&["domain_roots", "main.rs", "notes.txt"]);
+        app.search_mode = SearchMode::Fuzzy;
+        app.search_query = "mainr".to_string();
+        app.apply_filter();
+        let names: Vec<&str> = app.filtered_entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["main.rs", "domain_roots"]);
+    }
+
+    #[test]
+    fn test_search_history() {
+        let (_dir, mut app) = test_app(&[]);
+        let search = |app: &mut App, query: &str| {
+            app.on_key(KeyCode::F(3), NONE);
+            for c in query.chars() {
+                app.on_key(KeyCode::Char(c), NONE);
+            }
+            app.on_key(KeyCode::Esc, NONE);
+            app.on_key(KeyCode::Esc, NONE);
+        };
+        search(&mut app, "one");
+        search(&mut app, "two");
+        search(&mut app, "one");
+        assert_eq!(app.search_history, ["two", "one"]);
+
+        // The last query comes back as a suggestion that typing replaces
+        app.on_key(KeyCode::F(3), NONE);
+        assert_eq!((app.search_query.as_str(), app.search_prefilled), ("one", true));
+        app.on_key(KeyCode::Char('x'), NONE);
+        assert_eq!(app.search_query, "x");
+
+        app.on_key(KeyCode::Up, NONE);
+        app.on_key(KeyCode::Up, NONE);
+        assert_eq!(app.search_query, "two");
+        app.on_key(KeyCode::Down, NONE);
+        app.on_key(KeyCode::Down, NONE);
+        assert_eq!(app.search_query, "");
+    }
+
+    #[test]
+    fn test_header_crumbs_fold_the_middle() {
+        let dir = tempfile::tempdir().unwrap();
+        let deep = std::fs::canonicalize(dir.path()).unwrap().join("a/b/c/d");
+        std::fs::create_dir_all(&deep).unwrap();
+        let mut app = App::with_config(Some(deep.clone()), Config::default());
+        app.config.abbreviate_home = false;
+
+        assert_eq!(app.header_crumbs(1000), app.manager.breadcrumbs());
+        let crumbs = app.header_crumbs(8);
+        let labels: Vec<&str> = crumbs.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels[1..], ["…", "c", "d"]);
+        assert_eq!(crumbs[1].1, deep.parent().unwrap().parent().unwrap());
+    }
+
+    #[test]
+    fn test_wrap_navigation() {
+        let (_dir, mut app) = test_app(&["a", "b"]);
+        let last = app.filtered_entries.len() - 1;
+
+        app.on_key(KeyCode::Up, NONE);
+        assert_eq!(app.selected_index, 0);
+        app.config.wrap_navigation = true;
+        app.on_key(KeyCode::Up, NONE);
+        assert_eq!(app.selected_index, last);
+        app.on_key(KeyCode::Down, NONE);
+        assert_eq!(app.selected_index, 0);
+        // A count still stops at the end before wrapping
+        app.on_key(KeyCode::Char('9'), NONE);
+        app.on_key(KeyCode::Down, NONE);
+        assert_eq!(app.selected_index, last);
+    }
+
+    #[test]
+    fn test_idle_ticks_do_not_redraw() {
+        let (_dir, mut app) = test_app(&["a"]);
+        assert!(app.dirty);
+
+        app.dirty = false;
+        app.tick();
+        app.tick();
+        assert!(!app.dirty);
+        app.on_key(KeyCode::Down, NONE);
+        assert!(app.dirty);
+        app.dirty = false;
+        app.notify("done".to_string());
+        assert!(app.dirty);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_finish_external_edit() {
+        use std::os::unix::process::ExitStatusExt;
+        let (dir, mut app) = test_app(&["a"]);
+        let target = dir.path().join("a");
+        let file = dir.path().join("edited.txt");
+        app.edit_target = target.clone();
+        app.edit_buffer.clear();
+
+        // The editor's trailing newline is dropped
+        std::fs::write(&file, "first line\nsecond\n").unwrap();
+        app.finish_external_edit(&file, Ok(std::process::ExitStatus::from_raw(0)));
+        assert_eq!(crate::metadata::get_description(&target).as_deref(), Some("first line\nsecond"));
+        assert!(!file.exists());
+
+        // A failing editor changes nothing
+        std::fs::write(&file, "discarded").unwrap();
+        app.finish_external_edit(&file, Ok(std::process::ExitStatus::from_raw(1 << 8)));
+        assert_eq!(crate::metadata::get_description(&target).as_deref(), Some("first line\nsecond"));
+    }
+
+    #[test]
+    fn test_search_results_show_and_reveal_their_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(root.join("sub")).unwrap();
+        for name in ["sub/a.txt", "sub/b.txt", "top.txt"] {
+            std::fs::write(root.join(name), "").unwrap();
+        }
+        let mut app = App::with_config(Some(root.clone()), Config::default());
+        let found = |dir: &Path, name: &str| FileSystemManager::new(dir).list_directory().unwrap()
+            .into_iter().find(|e| e.name == name).unwrap();
+
+        app.showing_search_results = true;
+        app.search_results_root = root.clone();
+        app.filtered_entries = vec![found(&root, "top.txt"), found(&root.join("sub"), "b.txt")];
+        assert_eq!(app.result_location(&app.filtered_entries[0]).as_deref(), Some("./"));
+        assert_eq!(app.result_location(&app.filtered_entries[1]).as_deref(), Some("sub/"));
+
+        app.selected_index = 1;
+        app.on_key(KeyCode::Enter, NONE);
+        assert!(!app.showing_search_results);
+        assert_eq!(app.manager.current_path(), root.join("sub"));
+        assert_eq!(app.filtered_entries[app.selected_index].name, "b.txt");
+        assert_eq!(app.result_location(&app.filtered_entries[app.selected_index]), None);
+    }
+
+    #[test]
+    fn test_rename_search_result_in_its_own_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/a.txt"), "").unwrap();
+        let mut app = App::with_config(Some(root.clone()), Config::default());
+        app.showing_search_results = true;
+        app.search_results_root = root.clone();
+        app.filtered_entries = FileSystemManager::new(root.join("sub")).list_directory().unwrap()
+            .into_iter().filter(|e| e.name == "a.txt").collect();
+        app.selected_index = 0;
+
+        app.prompt_buffer = "x/y.txt".to_string();
+        assert!(app.rename_selected().is_err());
+
+        app.prompt_buffer = "b.txt".to_string();
+        app.rename_selected().unwrap();
+        assert!(root.join("sub/b.txt").exists());
+        assert!(!root.join("b.txt").exists());
+        assert_eq!(app.filtered_entries[0].path, root.join("sub/b.txt"));
+    }
+
+    #[test]
+    fn test_editor_scrolls_to_the_cursor() {
+        let (_dir, mut app) = test_app(&[]);
+        app.edit_buffer = (1..=20).map(|n| format!("line {}", n)).collect::<Vec<_>>().join("\n");
+        app.edit_cursor = app.edit_buffer.len();
+        app.scroll_editor_to_cursor(5, 4);
+        assert_eq!(app.edit_scroll, (15, 4));
+
+        // Moving up inside the visible lines doesn't scroll
+        app.edit_cursor = app.edit_buffer.find("line 17").unwrap();
+        app.scroll_editor_to_cursor(5, 4);
+        assert_eq!(app.edit_scroll, (15, 0));
+        app.edit_cursor = 0;
+        app.scroll_editor_to_cursor(5, 4);
+        assert_eq!(app.edit_scroll, (0, 0));
+    }
+
+    #[test]
+    fn test_recent_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        for name in ["a", "b", "c"] {
+            std::fs::create_dir(root.join(name)).unwrap();
+        }
+        let mut app = App::with_config(Some(root.clone()), Config::default());
+        app.config.recent_dirs_limit = 3;
+        let visit = |app: &mut App, name: &str| {
+            app.manager.navigate_to(root.join(name)).unwrap();
+            app.on_directory_changed();
+        };
+        let recent = |app: &App| app.config.recent_dirs.iter().map(|r| r.path.clone()).collect::<Vec<_>>();
+
+        for name in ["a", "b", "c", "a"] {
+            visit(&mut app, name);
+        }
+        // Revisiting moves a folder back to the top instead of listing it twice
+        assert_eq!(recent(&app), [root.join("a"), root.join("c"), root.join("b")]);
+        app.config.recent_dirs_limit = 2;
+        visit(&mut app, "b");
+        assert_eq!(recent(&app), [root.join("b"), root.join("a")]);
+
+        // The picker starts on the previous folder
+        app.on_key(KeyCode::Char('H'), KeyModifiers::SHIFT);
+        assert!(matches!(app.input_mode, InputMode::RecentDirs));
+        app.on_key(KeyCode::Enter, NONE);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert_eq!(app.manager.current_path(), root.join("a"));
+    }
+
+    #[test]
+    fn test_folder_results_open_and_reveal() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir_all(root.join("deep/inside")).unwrap();
+        std::fs::create_dir(root.join("elsewhere")).unwrap();
+        let mut app = App::with_config(Some(root.join("elsewhere")), Config::default());
+        let result = FileSystemManager::new(root.join("deep")).list_directory().unwrap()
+            .into_iter().find(|e| e.name == "inside").unwrap();
+
+        // The result lies outside the folder being browsed
+        app.showing_search_results = true;
+        app.search_query = "inside".to_string();
+        app.filtered_entries = vec![result];
+        app.selected_index = 0;
+        app.on_key(KeyCode::Enter, NONE);
+        assert_eq!(app.manager.current_path(), root.join("deep/inside"));
+        assert!(!app.showing_search_results);
+        assert!(app.search_query.is_empty());
+
+        // Revealing it instead lands next to it
+        let result = FileSystemManager::new(root.join("deep")).list_directory().unwrap()
+            .into_iter().find(|e| e.name == "inside").unwrap();
+        app.showing_search_results = true;
+        app.filtered_entries = vec![result];
+        app.selected_index = 0;
+        app.on_key(KeyCode::Char('R'), KeyModifiers::SHIFT);
+        assert_eq!(app.manager.current_path(), root.join("deep"));
+        assert_eq!(app.filtered_entries[app.selected_index].name, "inside");
+    }
+
+    #[test]
+    fn test_search_results_follow_search_sort() {
+        let (dir, mut app) = test_app(&["sub/a.txt", "b.txt", "c.txt"]);
+        let manager = FileSystemManager::new(dir.path());
+        let mut results: Vec<FileEntry> = manager.list_directory().unwrap().into_iter()
+            .filter(|e| e.name.ends_with(".txt"))
+            .collect();
+        results.extend(FileSystemManager::new(dir.path().join("sub")).list_directory().unwrap().into_iter()
+            .filter(|e| e.name.ends_with(".txt")));
+        let names = |app: &App| app.filtered_entries.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+
+        app.showing_search_results = true;
+        app.filtered_entries = results;
+        app.sort_key = SortKey::Name;
+        app.sort_order = SortOrder::Asc;
+        app.config.search_sort = SearchSort::Listing;
+        app.apply_sort();
+        assert_eq!(names(&app), ["a.txt", "b.txt", "c.txt"]);
+        app.config.search_sort = SearchSort::Depth;
+        app.apply_sort();
+        assert_eq!(names(&app), ["b.txt", "c.txt", "a.txt"]);
+
+        // Fuzzy searches rank by how well the query matches first
+        app.search_ranking = Some(Matcher::new("ct", SearchMode::Fuzzy).unwrap());
+        app.apply_sort();
+        assert_eq!(names(&app)[0], "c.txt");
+    }
+
+    #[test]
+    fn test_empty_list_message() {
+        let (dir, mut app) = test_app(&[]);
+        assert_eq!(app.empty_list_message().as_deref(), Some("Empty directory"));
+
+        std::fs::write(dir.path().join(".hidden"), "").unwrap();
+        app.show_hidden = false;
+        app.refresh();
+        assert_eq!(app.empty_list_message().as_deref(), Some("Only hidden files"));
+
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+        app.refresh();
+        assert_eq!(app.empty_list_message(), None);
+        app.search_query = "zzz".to_string();
+        app.apply_filter();
+        assert_eq!(app.empty_list_message().as_deref(), Some("No matches for 'zzz'"));
+
+        app.listing_error = Some("Permission denied (os error 13)".to_string());
+        app.filtered_entries.clear();
+        assert_eq!(app.empty_list_message().as_deref(), Some("Permission denied"));
+    }
+
+    #[test]
+    fn test_list_separator_rows() {
+        let (dir, mut app) = test_app(&["a"]);
+        app.config.dirs_first = true;
+        app.config.group_separator = true;
+        // Only "." and ".." come before the file
+        assert_eq!(app.list_separator(), None);
+
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        app.refresh();
+        assert_eq!(app.list_separator(), Some(3));
+        app.list_area = Rect { x: 0, y: 0, width: 20, height: 10 };
+        assert_eq!(app.list_index_at(1, 2), Some(2));
+        assert_eq!(app.list_index_at(1, 3), None);
+        assert_eq!(app.list_index_at(1, 4), Some(3));
+
+        app.config.group_separator = false;
+        assert_eq!(app.list_index_at(1, 3), Some(3));
+    }
+
+    #[test]
+    fn test_position_label() {
+        let (_dir, mut app) = test_app(&["a.rs", "b.rs", "c.txt", ".hidden.rs"]);
+        app.show_hidden = false;
+        app.apply_filter();
+        app.selected_index = 2;
+        assert_eq!(app.position_label().as_deref(), Some("3/5"));
+
+        app.search_query = "rs".to_string();
+        app.apply_filter();
+        app.selected_index = 0;
+        assert_eq!(app.position_label().as_deref(), Some("1/2 of 5"));
+    }
+
+    #[test]
+    fn test_invert_selection() {
+        let (dir, mut app) = test_app(&["a", "b", "c"]);
+        let hidden = dir.path().join("elsewhere");
+        app.selected_paths.insert(dir.path().join("b"));
+        app.selected_paths.insert(hidden.clone());
+
+        app.invert_selection();
+        let mut expected: HashSet<PathBuf> = ["a", "c"].iter().map(|n| dir.path().join(n)).collect();
+        expected.insert(hidden);
+        assert_eq!(app.selected_paths, expected);
+    }
+
+    #[test]
+    fn test_returning_restores_the_position() {
+        let (dir, mut app) = test_app(&[
+            "file0", "file1", "file2", "file3", "file4",
+            "sub/inner0", "sub/inner1", "sub/inner2", "sub/inner3", "sub/inner4",
+        ]);
+        app.select_path(&dir.path().join("file3"));
+        let index = app.selected_index;
+
+        app.manager.navigate_to(dir.path().join("sub")).unwrap();
+        app.on_directory_changed();
+        assert_eq!(app.selected_index, 0);
+        app.select_path(&dir.path().join("sub/inner2"));
+
+        app.manager.navigate_up();
+        app.on_directory_changed();
+        assert_eq!(app.selected_index, index);
+        app.manager.navigate_to(dir.path().join("sub")).unwrap();
+        app.on_directory_changed();
+        assert_eq!(app.filtered_entries[app.selected_index].name, "inner2");
+    }
+
+    #[test]
+    fn test_status_message_clears_itself() {
+        let (dir, mut app) = test_app(&["a"]);
+        app.select_path(&dir.path().join("a"));
+        app.delete_selected(true);
+        assert_eq!(app.status_message.as_ref().map(|(m, _)| m.as_str()), Some("Deleted 1 item"));
+
+        app.status_message.as_mut().unwrap().1 -= STATUS_TIMEOUT;
+        app.tick();
+        assert!(app.status_message.is_none());
+    }
+
+    #[test]
+    fn test_deleting_a_folder_needs_its_name() {
+        let (dir, mut app) = test_app(&["photos/a.jpg"]);
+        app.select_path(&dir.path().join("photos"));
+
+        app.on_key(KeyCode::Delete, KeyModifiers::CONTROL);
+        assert!(matches!(&app.input_mode, InputMode::Prompt(PromptType::DeleteConfirmation { typed: Some(name), .. }) if name == "photos"));
+        for c in "photo".chars() {
+            app.on_key(KeyCode::Char(c), NONE);
+        }
+        app.on_key(KeyCode::Enter, NONE);
+        assert!(dir.path().join("photos").exists());
+        app.on_key(KeyCode::Char('s'), NONE);
+        app.on_key(KeyCode::Enter, NONE);
+        assert!(!dir.path().join("photos").exists());
+
+        let files = [dir.path().join("a"), dir.path().join("b")];
+        assert_eq!(delete_confirmation_text(&files), None);
+    }
+
+    #[test]
+    fn test_selection_summary() {
+        let (dir, mut app) = test_app(&["sub/"]);
+        std::fs::write(dir.path().join("a"), "12345").unwrap();
+        std::fs::write(dir.path().join("b"), "123").unwrap();
+        std::fs::write(dir.path().join("sub/c"), "1234567").unwrap();
+        app.refresh();
+        for name in ["a", "b", "sub"] {
+            app.selected_paths.insert(dir.path().join(name));
+        }
+        assert_eq!(app.selection_summary(), (3, 8, 1));
+
+        let sub = dir.path().join("sub");
+        let size = FileSystemManager::directory_size(&sub, &AtomicBool::new(false)).unwrap();
+        app.dir_sizes.insert(sub, size);
+        assert_eq!(app.selection_summary(), (3, 8 + size, 0));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_quit_confirms_while_a_command_runs() {
+        let (_dir, mut app) = test_app(&[]);
+        app.request_quit();
+        assert!(app.should_quit);
+
+        app.should_quit = false;
+        app.prompt_buffer = "sleep 30 #".to_string();
+        app.run_shell_command().unwrap();
+        app.request_quit();
+        assert!(!app.should_quit);
+        assert!(matches!(app.input_mode, InputMode::Prompt(PromptType::QuitConfirmation)));
+
+        // Cancel is preselected
+        app.on_key(KeyCode::Enter, NONE);
+        assert!(!app.should_quit && app.command_rx.is_some());
+        app.request_quit();
+        app.on_key(KeyCode::Left, NONE);
+        app.on_key(KeyCode::Enter, NONE);
+        assert!(app.should_quit);
+        assert!(app.command_rx.is_none());
+    }
+}
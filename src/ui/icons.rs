@@ -0,0 +1,43 @@
+use crate::filesystem::FileEntry;
+
+const FOLDER: &str = "\u{f07b}";
+const LINK: &str = "\u{f0c1}";
+const FILE: &str = "\u{f15b}";
+
+/// Nerd Font glyph for an entry, chosen by type and then by extension.
+pub fn icon_for(entry: &FileEntry) -> &'static str {
+    if entry.is_dir {
+        return FOLDER;
+    }
+    if entry.is_symlink {
+        return LINK;
+    }
+    let extension = entry.path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "rs" => "\u{e7a8}",
+        "py" => "\u{e73c}",
+        "js" | "mjs" | "cjs" => "\u{e74e}",
+        "ts" | "tsx" => "\u{e628}",
+        "go" => "\u{e627}",
+        "c" | "h" => "\u{e61e}",
+        "cpp" | "cc" | "hpp" => "\u{e61d}",
+        "java" => "\u{e738}",
+        "html" | "htm" => "\u{e736}",
+        "css" | "scss" => "\u{e749}",
+        "md" => "\u{e73e}",
+        "json" => "\u{e60b}",
+        "toml" | "yaml" | "yml" | "ini" | "cfg" | "conf" => "\u{e615}",
+        "sh" | "bash" | "zsh" | "fish" => "\u{f489}",
+        "lock" => "\u{f023}",
+        "txt" | "log" => "\u{f15c}",
+        "pdf" => "\u{f1c1}",
+        "png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "bmp" | "ico" => "\u{f1c5}",
+        "mp3" | "flac" | "wav" | "ogg" | "m4a" => "\u{f1c7}",
+        "mp4" | "mkv" | "avi" | "mov" | "webm" => "\u{f1c8}",
+        "zip" | "tar" | "gz" | "xz" | "bz2" | "7z" | "rar" | "zst" => "\u{f1c6}",
+        _ => FILE,
+    }
+}
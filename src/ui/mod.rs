@@ -1,2 +1,4 @@
 pub mod app;
+pub mod icons;
+#[allow(clippy::module_inception)]
 pub mod ui;
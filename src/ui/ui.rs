@@ -1,14 +1,40 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Gauge, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Tabs},
+    text::{Line, Span},
     Frame,
 };
-use crate::ui::app::{App, InputMode};
+use crate::config::SearchScope;
+use crate::filesystem::{format_relative, format_size, FileEntry, FileKind, Matcher, SortKey};
+use std::ops::Range;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use crate::ui::app::{App, InputMode, PromptType};
+use crate::ui::icons::icon_for;
+
+const HIGHLIGHT_SYMBOL: &str = ">> ";
+const SIZE_COLUMN_WIDTH: usize = 10;
+/// Below this many name chars the Modified column is dropped to make room
+const MIN_NAME_WIDTH: usize = 20;
+/// Room a description summary needs after the name before it's worth showing
+const MIN_SUMMARY_WIDTH: usize = 8;
+/// Smallest size of the description editor, borders included
+const EDIT_POPUP_MIN: (u16, u16) = (40, 5);
+
+/// Column layout of a file list, derived from the width of its pane.
+struct ListColumns {
+    name_width: usize,
+    show_date: bool,
+    date_width: usize,
+}
 
 pub fn render(f: &mut Frame, app: &mut App) {
     match &app.input_mode {
         InputMode::Config | InputMode::Remapping(_) => render_config(f, app),
+        InputMode::Prompt(PromptType::ResetKeybindings) => {
+            render_config(f, app);
+            render_prompt(f, app);
+        }
         InputMode::Prompt(_) => {
             render_main(f, app);
             render_prompt(f, app);
@@ -17,11 +43,54 @@ pub fn render(f: &mut Frame, app: &mut App) {
             render_main(f, app);
             render_help(f, app);
         }
+        InputMode::Bookmarks => {
+            render_main(f, app);
+            render_bookmarks(f, app);
+        }
+        InputMode::RecentDirs => {
+            render_main(f, app);
+            render_recent_dirs(f, app);
+        }
+        InputMode::OpenWith => {
+            render_main(f, app);
+            render_open_with(f, app);
+        }
+        InputMode::Command | InputMode::CommandOutput => {
+            render_main(f, app);
+            render_command(f, app);
+        }
+        InputMode::BatchRename => {
+            render_main(f, app);
+            render_batch_rename(f, app);
+        }
+        InputMode::Permissions => {
+            render_main(f, app);
+            render_permissions(f, app);
+        }
         _ => render_main(f, app),
     }
 }
 
 fn render_main(f: &mut Frame, app: &mut App) {
+    let theme = &app.config.theme;
+    let (highlight_bg, search_active) = (theme.highlight_bg(), theme.search_active());
+    let border_style = Style::default().fg(theme.border());
+
+    // Tab bar, only shown once a second tab is open
+    let area = if app.tabs.len() > 1 {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(f.area());
+        let tabs = Tabs::new(app.tab_titles())
+            .select(app.active_tab)
+            .highlight_style(Style::default().fg(search_active).add_modifier(Modifier::BOLD));
+        f.render_widget(tabs, split[0]);
+        split[1]
+    } else {
+        f.area()
+    };
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -29,7 +98,7 @@ fn render_main(f: &mut Frame, app: &mut App) {
             Constraint::Min(0),    // Main area
             Constraint::Length(3), // Footer
         ])
-        .split(f.area());
+        .split(area);
 
     // Header (Path + Search)
     let header_chunks = Layout::default()
@@ -40,23 +109,84 @@ fn render_main(f: &mut Frame, app: &mut App) {
         ])
         .split(chunks[0]);
 
-    let header = Paragraph::new(format!(" Xplore - {}", app.manager.current_path().display()))
-        .block(Block::default().borders(Borders::ALL).title("Path"));
+    // Path bar as breadcrumb segments; their positions are kept so clicks can jump to them
+    let mut header_block = Block::default().borders(Borders::ALL).title("Path").border_style(border_style);
+    if let Some((total, available)) = app.disk_usage {
+        let usage = format!(" {} free of {} ", format_size(available), format_size(total));
+        header_block = header_block.title(Line::from(usage).right_aligned());
+    }
+    let header_inner = header_block.inner(header_chunks[0]);
+    let picking = matches!(app.input_mode, InputMode::Breadcrumbs);
+    let mut spans = vec![Span::raw(" Xplore - ")];
+    // Picking goes by position in the full path, so it shows every crumb
+    let crumbs = if picking {
+        app.manager.breadcrumbs()
+    } else {
+        app.header_crumbs((header_inner.width as usize).saturating_sub(spans[0].width()))
+    };
+    let mut x = header_inner.x + spans[0].width() as u16;
+    app.breadcrumb_areas.clear();
+    for (i, (label, path)) in crumbs.iter().enumerate() {
+        // The root label already ends with a separator
+        if i > 0 && !crumbs[i - 1].0.ends_with(std::path::MAIN_SEPARATOR) {
+            let separator = Span::raw(std::path::MAIN_SEPARATOR.to_string());
+            x += separator.width() as u16;
+            spans.push(separator);
+        }
+        let style = if picking && i == app.breadcrumb_index {
+            Style::default().fg(search_active).add_modifier(Modifier::REVERSED | Modifier::BOLD)
+        } else if i + 1 == crumbs.len() {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let span = Span::styled(label.clone(), style);
+        let width = span.width() as u16;
+        let end = (x + width).min(header_inner.x + header_inner.width);
+        if end > x {
+            app.breadcrumb_areas.push((Rect { x, y: header_inner.y, width: end - x, height: 1 }, path.clone()));
+        }
+        x += width;
+        spans.push(span);
+    }
+    let header = Paragraph::new(Line::from(spans)).block(header_block);
     f.render_widget(header, header_chunks[0]);
 
-    let search_title = if app.is_searching {
-        " Searching... ".to_string()
+    let search_root = app.search_root();
+    let scope_label = match app.search_scope {
+        SearchScope::Global => search_root.display().to_string(),
+        SearchScope::Current => search_root.file_name()
+            .map(|n| format!("./{}", n.to_string_lossy()))
+            .unwrap_or_else(|| search_root.display().to_string()),
+    };
+    let search_title = if app.search_invalid {
+        " invalid regex ".to_string()
+    } else if app.is_searching {
+        format!(" Searching... {} found (Esc to stop) ", app.filtered_entries.len())
+    } else if app.showing_search_results && app.search_truncated {
+        format!(" First {} results, may be more ", app.filtered_entries.len())
+    } else if app.showing_search_results {
+        format!(" {} results ", app.filtered_entries.len())
     } else if let InputMode::Search = app.input_mode {
-        " Global Search (Enter to scan /) ".to_string()
+        format!(" Search (Enter to scan {}) ", scope_label)
     } else {
-        format!(" Global Search {} ", app.config.keybindings.search)
+        format!(" Search {} {} ", scope_label, app.config.keybindings.search)
     };
-    let search_border_style = if let InputMode::Search = app.input_mode {
-        Style::default().fg(Color::Yellow)
+    let search_title = if app.search_contents {
+        format!("{}[{}, contents] ", search_title, app.search_mode.label())
     } else {
-        Style::default()
+        format!("{}[{}] ", search_title, app.search_mode.label())
     };
-    let search_bar = Paragraph::new(format!(" {}", app.search_query))
+    let search_border_style = if app.search_invalid {
+        Style::default().fg(Color::Red)
+    } else if let InputMode::Search = app.input_mode {
+        Style::default().fg(search_active)
+    } else {
+        border_style
+    };
+    // A suggested query looks selected, since typing replaces it
+    let query_style = if app.search_prefilled { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+    let search_bar = Paragraph::new(Line::from(vec![Span::raw(" "), Span::styled(app.search_query.clone(), query_style)]))
         .block(Block::default()
             .borders(Borders::ALL)
             .title(search_title)
@@ -84,97 +214,289 @@ fn render_main(f: &mut Frame, app: &mut App) {
     let main_ranks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Percentage(50),
-            Constraint::Percentage(50),
+            Constraint::Percentage(app.config.list_width),
+            Constraint::Percentage(100 - app.config.list_width),
         ])
         .split(main_area);
 
-    // File List
-    let items: Vec<ListItem> = app.filtered_entries.iter().map(|e| {
-        let prefix = if e.is_dir { "[DIR] " } else { "      " };
-        let mut style = if e.is_dir { 
-            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD) 
-        } else { 
-            Style::default() 
-        };
-        
-        if app.is_selected(&e.path) {
-            style = style.bg(Color::Rgb(50, 50, 50)).add_modifier(Modifier::ITALIC);
-        }
-
-        let name = if app.is_selected(&e.path) {
-            format!("* {}", e.name)
-        } else {
-            e.name.clone()
-        };
+    // In dual-pane mode the focused listing can sit on either side and replaces the Details panel.
+    // A hidden panel still comes back for a listing error, since the list has nothing to show then.
+    let details_visible = app.dual_pane || app.show_details || app.listing_error.is_some();
+    let (list_rect, other_rect) = if !details_visible {
+        (main_area, Rect::default())
+    } else if app.dual_pane && !app.active_pane_left {
+        (main_ranks[1], main_ranks[0])
+    } else {
+        (main_ranks[0], main_ranks[1])
+    };
 
-        ListItem::new(format!("{}{}", prefix, name)).style(style)
-    }).collect();
+    // File List
+    let mut list_title = if app.dual_pane {
+        format!("{} ({})", app.display_path(app.manager.current_path()), app.sort_title())
+    } else {
+        format!("Files ({})", app.sort_title())
+    };
+    if let Some(filter) = &app.name_filter {
+        list_title.push_str(&format!(" [{}]", filter.pattern()));
+    }
+    if app.selection_anchor.is_some() {
+        list_title.push_str(" -- RANGE --");
+    }
+    if let Some((loaded, total)) = app.listing_progress() {
+        const SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+        let frame = SPINNER[(app.tick_count % SPINNER.len() as u64) as usize];
+        list_title.push_str(&format!(" {} Loading {}/{}", frame, loaded, total));
+    }
+    let mut list_block = Block::default().borders(Borders::ALL).title(list_title).border_style(border_style);
+    if let Some(position) = app.position_label() {
+        list_block = list_block.title(Line::from(format!(" {} ", position)).right_aligned());
+    }
+    // Show what has been typed so far of a count or key sequence
+    if app.pending_count.is_some() || app.pending_keys.is_some() {
+        let count = app.pending_count.map(|n| n.to_string()).unwrap_or_default();
+        let keys = app.pending_keys.as_deref().unwrap_or_default();
+        list_block = list_block.title_bottom(Line::from(format!(" {}{} ", count, keys)).right_aligned());
+    } else if let Some(text) = app.type_ahead_text() {
+        list_block = list_block.title_bottom(Line::from(format!(" Jump: {} ", text)).right_aligned());
+    }
+    let (rows_rect, columns) = render_column_header(f, app, list_block.inner(list_rect), HIGHLIGHT_SYMBOL.len());
+    f.render_widget(list_block, list_rect);
+    let matcher = app.query_matcher();
+    let mut items = file_list_items(app, &app.filtered_entries, &columns, matcher.as_ref());
+    // The divider is a row of its own, so the list widget sees indices shifted past it
+    let separator = app.list_separator();
+    let mut list_state = app.list_state;
+    if let Some(boundary) = separator {
+        let width = rows_rect.width.saturating_sub(HIGHLIGHT_SYMBOL.len() as u16) as usize;
+        items.insert(boundary, ListItem::new(Line::styled("╌".repeat(width), Style::default().fg(Color::DarkGray))));
+        list_state = list_state
+            .with_offset(app.list_row_offset(boundary))
+            .with_selected(app.list_state.selected().map(|index| index + usize::from(index >= boundary)));
+    }
 
     // Track list height for Home/End/Page calculation
-    app.list_height = main_ranks[0].height.saturating_sub(2); // Subtract borders
+    app.list_height = rows_rect.height;
+    app.list_area = rows_rect; // For mapping mouse clicks to rows
 
+    let items_len = items.len();
     let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Files"))
-        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
-        .highlight_symbol(">> ");
+        .highlight_style(Style::default().bg(highlight_bg).add_modifier(Modifier::BOLD))
+        .highlight_symbol(HIGHLIGHT_SYMBOL);
+
+    f.render_stateful_widget(list, rows_rect, &mut list_state);
+    let offset = list_state.offset();
+    app.separator_at_top = separator == Some(offset);
+    *app.list_state.offset_mut() = match separator {
+        Some(boundary) => offset - usize::from(offset > boundary),
+        None => offset,
+    };
 
-    f.render_stateful_widget(list, main_ranks[0], &mut app.list_state);
+    // Say why the list is blank below whatever rows it still has
+    if let Some(message) = app.empty_list_message() {
+        let rows = items_len as u16;
+        let free = Rect { y: rows_rect.y + rows, height: rows_rect.height.saturating_sub(rows), ..rows_rect };
+        if free.height > 0 {
+            let line = Rect { y: free.y + free.height / 2, height: 1, ..free };
+            let placeholder = Paragraph::new(message)
+                .style(Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC))
+                .alignment(ratatui::layout::Alignment::Center);
+            f.render_widget(placeholder, line);
+        }
+    }
+
+    // Scrollbar on the right border, only when the list doesn't fit
+    let total = app.filtered_entries.len() + usize::from(separator.is_some());
+    let visible = app.list_height as usize;
+    if total > visible {
+        let track = Rect {
+            x: list_rect.x + list_rect.width.saturating_sub(1),
+            y: rows_rect.y,
+            width: 1,
+            height: rows_rect.height,
+        };
+        let mut state = ScrollbarState::new(total - visible)
+            .position(offset)
+            .viewport_content_length(visible);
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight).begin_symbol(None).end_symbol(None),
+            track,
+            &mut state,
+        );
+    }
 
     // Details Panel
-    if let Some(entry) = app.filtered_entries.get(app.selected_index) {
+    let preview_lines = app.config.preview_lines;
+    let preview = details_visible.then(|| app.selected_preview()).flatten()
+        .map(|text| text.lines().take(preview_lines).collect::<Vec<_>>().join("\n"));
+    if !details_visible {
+        // The list's own size and modified columns stand in for the panel
+    } else if app.dual_pane {
+        let other_block = Block::default()
+            .borders(Borders::ALL)
+            .title(app.display_path(app.other_pane.manager.current_path()))
+            .border_style(border_style.add_modifier(Modifier::DIM));
+        let (rows_rect, columns) = render_column_header(f, app, other_block.inner(other_rect), 0);
+        f.render_widget(other_block, other_rect);
+        let items = file_list_items(app, &app.other_entries, &columns, None);
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED | Modifier::DIM));
+        let mut state = app.other_pane.list_state;
+        f.render_stateful_widget(list, rows_rect, &mut state);
+    } else if let Some(entry) = app.filtered_entries.get(app.selected_index) {
         let desc = entry.description.as_deref().unwrap_or("No description");
+        let size_text = match (entry.is_dir, app.dir_sizes.get(&entry.path)) {
+            (true, _) if app.sizing_path.as_ref() == Some(&entry.path) => "calculating… (Esc to cancel)".to_string(),
+            (true, Some(total)) => format!("{} ({} bytes)", format_size(*total), total),
+            (true, None) => format!("--- ({} to calculate)", app.config.keybindings.calc_size),
+            (false, _) => format!("{} ({} bytes)", entry.human_size(), entry.size),
+        };
+        let kind = match (entry.is_symlink, entry.kind) {
+            (true, FileKind::Symlink) => "Symlink (broken)".to_string(),
+            (true, kind) => format!("Symlink to {}", kind.label().to_lowercase()),
+            (false, kind) => kind.label().to_string(),
+        };
         let details_text = format!(
-            "Name: {}\nPath: {}\nSize: {} ({})\nModified: {}\n\n--- Metadata ---\nInode: {}\nPermissions: {}\nOwner: {}\nGroup: {}\n\n--- Description ---\n{}",
+            "Name: {}\nPath: {}\nType: {}{}\nSize: {}\nModified: {} ({})\n\n--- Metadata ---\nInode: {}\nPermissions: {}\nOwner: {}\nGroup: {}\n\n--- Description ---\n{}",
             entry.name,
             entry.path.display(),
-            entry.human_size(),
-            format!("{} bytes", entry.size),
-            entry.mod_time.format("%Y-%m-%d %H:%M:%S"),
+            kind,
+            entry.link_target.as_ref().map(|t| format!("\nTarget: {}", t.display())).unwrap_or_default(),
+            size_text,
+            entry.mod_time.format(&app.config.date_format),
+            entry.relative_mod_time(),
             entry.inode,
             entry.permissions,
             entry.owner,
             entry.group,
             desc
         );
+        let details_text = match app.content_matches.get(&entry.path) {
+            Some(found) => format!("{}\n\n--- Content Match (line {}) ---\n{}", details_text, found.line_number, found.line),
+            None => details_text,
+        };
+        let details_text = match &preview {
+            Some(text) => format!("{}\n\n--- Preview ---\n{}", details_text, text),
+            None => details_text,
+        };
+        let details_title = if app.show_hidden { "Details (showing hidden)" } else { "Details" };
         let details = Paragraph::new(details_text)
-            .block(Block::default().borders(Borders::ALL).title("Details"))
+            .block(Block::default().borders(Borders::ALL).title(details_title).border_style(border_style))
+            .wrap(ratatui::widgets::Wrap { trim: false });
+        f.render_widget(details, main_ranks[1]);
+    } else if let Some(err) = &app.listing_error {
+        let details = Paragraph::new(format!("Cannot read {}:\n{}", app.manager.current_path().display(), err))
+            .style(Style::default().fg(Color::Red))
+            .block(Block::default().borders(Borders::ALL).title("Details").border_style(border_style))
             .wrap(ratatui::widgets::Wrap { trim: false });
         f.render_widget(details, main_ranks[1]);
     }
 
     // Edit Mask (Popup)
     if let InputMode::Editing = app.input_mode {
-        let area = centered_rect(80, 60, f.area());
+        let (title, border_color) = match &app.error_message {
+            Some(err) => (format!(" Edit Description - {} ", err), Color::Red),
+            None => (" Edit Description (F2: Save, Esc: Cancel) ".to_string(), Color::Yellow),
+        };
+        // Grow with the text, plus a column for the cursor after the longest line, between a
+        // size that keeps the title readable and most of the screen
+        let screen = f.area();
+        let lines = app.edit_buffer.split('\n').collect::<Vec<_>>();
+        let longest = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        let max_width = (screen.width * 4 / 5).max(EDIT_POPUP_MIN.0);
+        let max_height = (screen.height * 4 / 5).max(EDIT_POPUP_MIN.1);
+        let min_width = EDIT_POPUP_MIN.0.max(title.width() as u16 + 2).min(max_width);
+        let width = u16::try_from(longest + 3).unwrap_or(u16::MAX).clamp(min_width, max_width);
+        let height = u16::try_from(lines.len() + 2).unwrap_or(u16::MAX).clamp(EDIT_POPUP_MIN.1, max_height);
+        let area = centered_fixed(width, height, screen);
+
+        // Text that doesn't fit scrolls, following the cursor
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        app.scroll_editor_to_cursor(inner.height as usize, inner.width as usize);
+        let (row, column) = app.edit_cursor_position();
+        let (top, left) = app.edit_scroll;
         let edit_block = Paragraph::new(app.edit_buffer.as_str())
+            .scroll((top as u16, left as u16))
             .block(Block::default()
                 .borders(Borders::ALL)
-                .title(" Edit Description (F2: Save, Esc: Cancel) ")
-                .border_style(Style::default().fg(Color::Yellow)));
+                .title(title)
+                .border_style(Style::default().fg(border_color)));
         f.render_widget(ratatui::widgets::Clear, area); // Clear the background
         f.render_widget(edit_block, area);
+        f.set_cursor_position((inner.x + (column - left) as u16, inner.y + (row - top) as u16));
     }
 
     // Footer
     let footer_text = match app.input_mode {
-        InputMode::Editing => " [Enter] Newline | [F2] Save | [Esc] Cancel ".to_string(),
-        InputMode::Search => " [Chars] Query | [Enter] DEEP GLOBAL SEARCH | [Esc] Cancel ".to_string(),
-        InputMode::Prompt(_) => " [Chars] Input | [Enter] OK | [Esc] Cancel ".to_string(),
+        InputMode::Editing => " [Enter] Newline | [Arrows/Home/End] Move | [F2] Save | [Esc] Cancel ".to_string(),
+        InputMode::Search => format!(
+            " [Chars] Query | [Up/Down] History | [Enter] DEEP SEARCH | {} | {} | {} | [Esc] Cancel ",
+            app.config.get_hint("cycle_search_mode"),
+            app.config.get_hint("toggle_content_search"),
+            app.config.get_hint("toggle_search_scope")
+        ),
+        InputMode::Prompt(PromptType::NewFolder | PromptType::NewFile | PromptType::Rename | PromptType::GoToPath) => {
+            " [Chars] Input | [Tab] Complete | [Enter] OK | [Esc] Cancel ".to_string()
+        }
+        InputMode::Prompt(_) | InputMode::Command => " [Chars] Input | [Enter] OK | [Esc] Cancel ".to_string(),
+        InputMode::Breadcrumbs => " [Left/Right] Select | [Enter] Go | [Esc] Cancel ".to_string(),
+        InputMode::BatchRename => " [Chars] Pattern | [Enter] Rename All | [Esc] Cancel ".to_string(),
         _ => {
             format!(
-                " {} | {} | {} | {} | {} | {} ",
+                " {} | {} | {} | {} | {} | {} | {}: {} ",
                 app.config.get_hint("help"),
                 app.config.get_hint("toggle_terminal"),
                 app.config.get_hint("new_folder"),
                 app.config.get_hint("page_up"),
                 app.config.get_hint("search"),
-                app.config.get_hint("settings")
+                app.config.get_hint("settings"),
+                app.config.get_hint("toggle_hidden"),
+                if app.show_hidden { "shown" } else { "off" }
             )
         }
     };
-    let footer = Paragraph::new(footer_text)
-        .block(Block::default().borders(Borders::ALL));
-    f.render_widget(footer, chunks[2]);
+    if let Some(progress) = &app.copy_progress {
+        let current = progress.current_file.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let label = format!(
+            "{} - {}/{} files, {} / {}",
+            current,
+            progress.files_done,
+            progress.files_total,
+            format_size(progress.bytes_done),
+            format_size(progress.bytes_total)
+        );
+        let title = if app.quit_pending { " Finishing the current item before quitting " } else { app.transfer_title };
+        let gauge = Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title(title).border_style(border_style))
+            .gauge_style(Style::default().fg(Color::Green))
+            .ratio(progress.ratio())
+            .label(label);
+        f.render_widget(gauge, chunks[2]);
+    } else if let (InputMode::Normal, Some(err)) = (&app.input_mode, &app.error_message) {
+        let footer = Paragraph::new(format!(" Error: {} (any key to dismiss) ", err))
+            .style(Style::default().fg(Color::Red))
+            .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Red)));
+        f.render_widget(footer, chunks[2]);
+    } else if let (InputMode::Normal, Some((message, _))) = (&app.input_mode, &app.status_message) {
+        let footer = Paragraph::new(format!(" {} ", message))
+            .style(Style::default().fg(Color::Green))
+            .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Green)));
+        f.render_widget(footer, chunks[2]);
+    } else {
+        let mut footer_block = Block::default().borders(Borders::ALL).border_style(border_style);
+        if !app.selected_paths.is_empty() {
+            let (count, total, uncounted) = app.selection_summary();
+            let mut summary = format!(" {} selected, {}", count, format_size(total));
+            if uncounted > 0 {
+                summary.push_str(&format!(" + {} folder{} not sized", uncounted, if uncounted == 1 { "" } else { "s" }));
+            }
+            summary.push(' ');
+            footer_block = footer_block.title(Line::from(summary).right_aligned());
+        }
+        let footer = Paragraph::new(footer_text).block(footer_block);
+        f.render_widget(footer, chunks[2]);
+    }
 }
 
 fn render_config(f: &mut Frame, app: &mut App) {
@@ -219,7 +541,8 @@ fn render_config(f: &mut Frame, app: &mut App) {
         let text = if let Some(err) = &app.error_message {
             format!(" ERROR: {}\n\n Press ANY KEY for [{}] ", err, action)
         } else {
-            format!(" Press NEW KEY for [{}] \n\n (Press Esc to cancel) ", action)
+            let prompt = if app.remap_adding { "an EXTRA KEY" } else { "NEW KEY" };
+            format!(" Press {} for [{}] \n\n (Press Esc to cancel) ", prompt, action)
         };
 
         let block = Paragraph::new(text)
@@ -229,11 +552,187 @@ fn render_config(f: &mut Frame, app: &mut App) {
         f.render_widget(block, area);
     }
 
-    let footer = Paragraph::new(" [Enter] Remap | [Esc] Back to Files ")
-        .block(Block::default().borders(Borders::ALL));
+    let footer = match (&app.input_mode, &app.error_message) {
+        (InputMode::Config, Some(err)) => Paragraph::new(format!(" {} ", err))
+            .style(Style::default().fg(Color::Red))
+            .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Red))),
+        _ => Paragraph::new(" [Enter] Remap | [a] Add Key | [d] Remove Key | [r] Reset All | [Esc] Back to Files ")
+            .block(Block::default().borders(Borders::ALL)),
+    };
     f.render_widget(footer, chunks[2]);
 }
 
+/// Rows for a file listing, styled by type and selection, with what `matcher` matched in
+/// each name highlighted.
+fn file_list_items(app: &App, entries: &[FileEntry], columns: &ListColumns, matcher: Option<&Matcher>) -> Vec<ListItem<'static>> {
+    let theme = &app.config.theme;
+    let (dir_fg, symlink_fg, selected_bg) = (theme.dir_fg(), theme.symlink_fg(), theme.selected_bg());
+    entries.iter().map(|e| {
+        let prefix = if app.config.use_icons {
+            format!("{} ", icon_for(e))
+        } else if e.is_dir {
+            "[DIR] ".to_string()
+        } else {
+            "      ".to_string()
+        };
+        let mut style = if e.is_dir { 
+            Style::default().fg(dir_fg).add_modifier(Modifier::BOLD) 
+        } else { 
+            Style::default() 
+        };
+        if e.is_symlink {
+            style = style.fg(symlink_fg);
+        }
+        
+        if app.is_selected(&e.path) {
+            style = style.bg(selected_bg).add_modifier(Modifier::ITALIC);
+        }
+
+        let mut name = if app.is_selected(&e.path) {
+            format!("* {}", e.name)
+        } else {
+            e.name.clone()
+        };
+        if let Some(target) = &e.link_target {
+            name = format!("{} -> {}", name, target.display());
+        }
+        if let Some(found) = app.content_matches.get(&e.path) {
+            name = format!("{}:{}", name, found.line_number);
+        }
+
+        // Match ranges are within the entry name, which starts after the prefix and marker
+        let name_start = prefix.chars().count() + if app.is_selected(&e.path) { 2 } else { 0 };
+        let highlights: Vec<Range<usize>> = matcher
+            .map(|m| m.match_ranges(&e.name).into_iter().map(|r| r.start + name_start..r.end + name_start).collect())
+            .unwrap_or_default();
+
+        // The description's first line fills the rest of the name column when there's room,
+        // after the folder of a search result that tells same-named results apart
+        let name = format!("{}{}", prefix, name);
+        let name_len = name.width();
+        let trailing = match (app.result_location(e), e.description_summary()) {
+            (Some(location), Some(summary)) => Some(format!("{}  {}", location, summary)),
+            (location, summary) => location.or(summary),
+        };
+        let mut spans = match trailing {
+            Some(summary) if name_len + 2 + MIN_SUMMARY_WIDTH <= columns.name_width => {
+                let mut spans = highlight_spans(name, &highlights);
+                spans.push(Span::styled(
+                    fit(&format!("  {}", summary), columns.name_width - name_len),
+                    Style::default().fg(Color::DarkGray).remove_modifier(Modifier::BOLD),
+                ));
+                spans
+            }
+            _ => highlight_spans(fit(&name, columns.name_width), &highlights),
+        };
+        let mut rest = format!(" {:>size_width$}", e.human_size(), size_width = SIZE_COLUMN_WIDTH);
+        if columns.show_date {
+            rest = format!("{} {}", rest, fit(&e.mod_time.format(&app.config.date_format).to_string(), columns.date_width));
+        }
+        spans.push(Span::raw(rest));
+        ListItem::new(Line::from(spans)).style(style)
+    }).collect()
+}
+
+/// Draw the Name/Size/Modified header at the top of a list area, marking the active sort column.
+/// Returns the area left for rows and the column layout the rows should use.
+fn render_column_header(f: &mut Frame, app: &App, area: Rect, indent: usize) -> (Rect, ListColumns) {
+    let split = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(area);
+    let without_date = (area.width as usize).saturating_sub(indent + SIZE_COLUMN_WIDTH + 1);
+    let date_width = date_column_width(&app.config.date_format);
+    let show_date = without_date > MIN_NAME_WIDTH + date_width;
+    let name_width = if show_date { without_date - date_width - 1 } else { without_date };
+
+    let arrow = |key: SortKey| if app.sort_key == key { app.sort_order.arrow() } else { "" };
+    let name_label = if app.sort_key == SortKey::Extension {
+        format!("Name (ext){}", app.sort_order.arrow())
+    } else {
+        format!("Name{}", arrow(SortKey::Name))
+    };
+    let size_label = format!("Size{}", arrow(SortKey::Size));
+    let mut header = format!(
+        "{}{} {:>size_width$}",
+        " ".repeat(indent),
+        fit(&name_label, name_width),
+        size_label,
+        size_width = SIZE_COLUMN_WIDTH
+    );
+    if show_date {
+        header = format!("{} Modified{}", header, arrow(SortKey::ModTime));
+    }
+    let header = Paragraph::new(header).style(Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED));
+    f.render_widget(header, split[0]);
+    (split[1], ListColumns { name_width, show_date, date_width })
+}
+
+/// Columns the Modified column needs for dates in `pattern`, measured on a Wednesday in
+/// September at 23:59:59 so month and weekday names come out at their longest.
+fn date_column_width(pattern: &str) -> usize {
+    use chrono::TimeZone;
+    let sample = chrono::Local.with_ymd_and_hms(2000, 9, 27, 23, 59, 59).earliest().unwrap_or_else(chrono::Local::now);
+    sample.format(pattern).to_string().width()
+}
+
+/// Split `text` into spans with the chars at the indices in `ranges` in bold yellow.
+fn highlight_spans(text: String, ranges: &[Range<usize>]) -> Vec<Span<'static>> {
+    if ranges.is_empty() {
+        return vec![Span::raw(text)];
+    }
+    let highlight = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_highlighted = false;
+    for (i, c) in text.chars().enumerate() {
+        let highlighted = ranges.iter().any(|r| r.contains(&i));
+        if highlighted != run_highlighted && !run.is_empty() {
+            let part = std::mem::take(&mut run);
+            spans.push(if run_highlighted { Span::styled(part, highlight) } else { Span::raw(part) });
+        }
+        run_highlighted = highlighted;
+        run.push(c);
+    }
+    spans.push(if run_highlighted { Span::styled(run, highlight) } else { Span::raw(run) });
+    spans
+}
+
+/// Pad or truncate `text` to exactly `width` terminal columns, marking truncation with an
+/// ellipsis. Wide characters such as CJK and most emoji take two columns.
+fn fit(text: &str, width: usize) -> String {
+    let text_width = text.width();
+    if text_width <= width {
+        return format!("{}{}", text, " ".repeat(width - text_width));
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut cut = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width > width - 1 {
+            break;
+        }
+        cut.push(c);
+        used += char_width;
+    }
+    // A wide character that didn't fit leaves a column to pad
+    format!("{}…{}", cut, " ".repeat(width - 1 - used))
+}
+
+/// A `width` by `height` rectangle in the middle of `r`, shrunk to fit inside it.
+fn centered_fixed(width: u16, height: u16, r: Rect) -> Rect {
+    let (width, height) = (width.min(r.width), height.min(r.height));
+    Rect {
+        x: r.x + (r.width - width) / 2,
+        y: r.y + (r.height - height) / 2,
+        width,
+        height,
+    }
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -256,17 +755,46 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 
 fn render_prompt(f: &mut Frame, app: &mut App) {
     if let InputMode::Prompt(prompt_type) = &app.input_mode {
-        let area = centered_rect(60, 20, f.area());
+        // The delete confirmation needs room to list what it removes
+        let height = if matches!(prompt_type, crate::ui::app::PromptType::DeleteConfirmation { .. }) { 40 } else { 20 };
+        let area = centered_rect(60, height, f.area());
         f.render_widget(ratatui::widgets::Clear, area);
 
         match prompt_type {
-            crate::ui::app::PromptType::NewFolder => {
-                let block = Paragraph::new(app.prompt_buffer.as_str())
-                    .block(Block::default().borders(Borders::ALL).title(" New Folder Name ").border_style(Style::default().fg(Color::Yellow)));
-                f.render_widget(block, area);
+            crate::ui::app::PromptType::NewFolder => render_input_prompt(f, area, "New Folder Name", "", app),
+            crate::ui::app::PromptType::NewFile => {
+                render_input_prompt(f, area, "New File Name", "(end with / for a folder)", app);
+            }
+            crate::ui::app::PromptType::OpenWith => {
+                render_input_prompt(f, area, "Open With", "(command, the path is appended)", app);
+            }
+            crate::ui::app::PromptType::GoToPath => {
+                render_input_prompt(f, area, "Go To", "(absolute, relative or ~, Tab completes)", app);
+            }
+            crate::ui::app::PromptType::Filter => {
+                render_input_prompt(f, area, "Show Only", "(globs or extensions, empty clears)", app);
+            }
+            crate::ui::app::PromptType::Archive => {
+                let count = app.archive_paths.len();
+                let hint = format!("({} {})", count, if count == 1 { "entry" } else { "entries" });
+                render_input_prompt(f, area, "Zip Archive Name", &hint, app);
             }
-            crate::ui::app::PromptType::DeleteConfirmation => {
-                let block = Block::default().borders(Borders::ALL).title(" Delete Confirmation ").border_style(Style::default().fg(Color::Red));
+            crate::ui::app::PromptType::Symlink => {
+                let target = app.symlink_target.as_ref()
+                    .and_then(|p| p.file_name())
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                render_input_prompt(f, area, "Link Name", &format!("(to {})", target), app);
+            }
+            crate::ui::app::PromptType::Rename => render_input_prompt(f, area, "Rename", "", app),
+            crate::ui::app::PromptType::PasteConflict => {
+                let name = app.pending_paste.as_ref()
+                    .and_then(|p| p.items.front())
+                    .and_then(|src| src.file_name())
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                let block = Block::default().borders(Borders::ALL).title(" File Exists ").border_style(Style::default().fg(Color::Yellow));
                 let inner = area.inner(ratatui::layout::Margin { vertical: 1, horizontal: 1 });
                 let chunks = Layout::default()
                     .direction(Direction::Vertical)
@@ -277,44 +805,169 @@ fn render_prompt(f: &mut Frame, app: &mut App) {
                     ])
                     .split(inner);
 
-                let question = Paragraph::new("Are you sure you want to delete?")
+                let question = Paragraph::new(format!("'{}' already exists here", name))
                     .alignment(ratatui::layout::Alignment::Center);
 
                 let buttons_layout = Layout::default()
                     .direction(Direction::Horizontal)
                     .constraints([
-                        Constraint::Percentage(50),
-                        Constraint::Percentage(50),
+                        Constraint::Ratio(1, 3),
+                        Constraint::Ratio(1, 3),
+                        Constraint::Ratio(1, 3),
                     ])
                     .split(chunks[2]);
 
-                let ok_style = if app.prompt_index == 0 {
-                    Style::default().bg(Color::Red).fg(Color::White).add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(Color::Red)
-                };
-                let ok_btn = Paragraph::new("OK")
-                    .alignment(ratatui::layout::Alignment::Center)
-                    .block(Block::default().borders(Borders::ALL).border_style(ok_style));
-
-                let cancel_style = if app.prompt_index == 1 {
-                    Style::default().bg(Color::DarkGray).fg(Color::White).add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default()
-                };
-                let cancel_btn = Paragraph::new("Cancel")
-                    .alignment(ratatui::layout::Alignment::Center)
-                    .block(Block::default().borders(Borders::ALL).border_style(cancel_style));
-
                 f.render_widget(block, area);
                 f.render_widget(question, chunks[0]);
-                f.render_widget(ok_btn, buttons_layout[0]);
-                f.render_widget(cancel_btn, buttons_layout[1]);
+                for (i, choice) in crate::ui::app::ConflictChoice::ALL.iter().enumerate() {
+                    let style = if app.prompt_index == i {
+                        Style::default().bg(Color::Yellow).fg(Color::Black).add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default()
+                    };
+                    let btn = Paragraph::new(choice.label())
+                        .alignment(ratatui::layout::Alignment::Center)
+                        .block(Block::default().borders(Borders::ALL).border_style(style));
+                    f.render_widget(btn, buttons_layout[i]);
+                }
+            }
+            crate::ui::app::PromptType::DeleteConfirmation { permanent, typed } => {
+                let targets = app.delete_targets();
+                let items = if targets.len() == 1 { "1 item".to_string() } else { format!("{} items", targets.len()) };
+                let (title, question) = if *permanent {
+                    (" Permanent Delete ", format!("Permanently delete {}? This cannot be undone.", items))
+                } else {
+                    (" Move to Trash ", format!("Move {} to trash?", items))
+                };
+                let names: Vec<String> = targets.iter()
+                    .map(|path| {
+                        let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+                        if path.is_dir() { format!("{}/", name) } else { name.to_string() }
+                    })
+                    .collect();
+                match typed {
+                    Some(expected) => render_typed_confirmation(f, area, title, &question, &names, expected, app),
+                    None => render_confirmation(f, area, title, &question, &names, app.prompt_index),
+                }
+            }
+            crate::ui::app::PromptType::ResetKeybindings => {
+                render_confirmation(f, area, " Reset Keybindings ", "Replace all keybindings with the defaults?", &[], app.prompt_index);
+            }
+            crate::ui::app::PromptType::QuitConfirmation => {
+                let question = format!("Still running: {}. Stop and quit?", app.running_operations().join(", "));
+                render_confirmation(f, area, " Quit ", &question, &[], app.prompt_index);
             }
         }
     }
 }
 
+/// A one-line text prompt titled `label`. The title explains the input with `hint` until
+/// there is an error to show in its place.
+fn render_input_prompt(f: &mut Frame, area: Rect, label: &str, hint: &str, app: &App) {
+    let (title, border_color) = match &app.error_message {
+        Some(err) => (format!(" {}: {} ", label, err), Color::Red),
+        None if hint.is_empty() => (format!(" {} ", label), Color::Yellow),
+        None => (format!(" {} {} ", label, hint), Color::Yellow),
+    };
+    let block = Paragraph::new(app.prompt_buffer.as_str())
+        .block(Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(border_color)));
+    f.render_widget(block, area);
+}
+
+/// An OK/Cancel dialog; `selected` is 0 for OK and 1 for Cancel. `details` are listed under
+/// the question, as many as fit.
+fn render_confirmation(f: &mut Frame, area: Rect, title: &str, question: &str, details: &[String], selected: usize) {
+    let block = Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(Color::Red));
+    let inner = area.inner(ratatui::layout::Margin { vertical: 1, horizontal: 1 });
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Question
+            Constraint::Min(0),    // Details
+            Constraint::Length(3), // Buttons
+        ])
+        .split(inner);
+
+    let question = Paragraph::new(question)
+        .alignment(ratatui::layout::Alignment::Center);
+
+    let details = detail_lines(details, chunks[1].height);
+
+    let buttons_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(50),
+            Constraint::Percentage(50),
+        ])
+        .split(chunks[2]);
+
+    let ok_style = if selected == 0 {
+        Style::default().bg(Color::Red).fg(Color::White).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Red)
+    };
+    let ok_btn = Paragraph::new("OK")
+        .alignment(ratatui::layout::Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(ok_style));
+
+    let cancel_style = if selected == 1 {
+        Style::default().bg(Color::DarkGray).fg(Color::White).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    let cancel_btn = Paragraph::new("Cancel")
+        .alignment(ratatui::layout::Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(cancel_style));
+
+    f.render_widget(block, area);
+    f.render_widget(question, chunks[0]);
+    f.render_widget(details, chunks[1]);
+    f.render_widget(ok_btn, buttons_layout[0]);
+    f.render_widget(cancel_btn, buttons_layout[1]);
+}
+
+/// As many of `details` as fit in `height` rows, ending in a count of the rest if they don't.
+fn detail_lines(details: &[String], height: u16) -> Paragraph<'static> {
+    let room = height as usize;
+    let mut lines: Vec<Line> = Vec::new();
+    if details.len() > room {
+        let shown = room.saturating_sub(1);
+        lines.extend(details[..shown].iter().map(|d| Line::from(format!("  {}", d))));
+        lines.push(Line::from(format!("  … and {} more", details.len() - shown)).style(Style::default().fg(Color::DarkGray)));
+    } else {
+        lines.extend(details.iter().map(|d| Line::from(format!("  {}", d))));
+    }
+    Paragraph::new(lines)
+}
+
+/// A confirmation that only goes ahead once `expected` has been typed into the prompt buffer.
+fn render_typed_confirmation(f: &mut Frame, area: Rect, title: &str, question: &str, details: &[String], expected: &str, app: &App) {
+    let block = Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(Color::Red));
+    let inner = area.inner(ratatui::layout::Margin { vertical: 1, horizontal: 1 });
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Question
+            Constraint::Min(0),    // Details
+            Constraint::Length(3), // Input
+        ])
+        .split(inner);
+
+    let question = Paragraph::new(question).alignment(ratatui::layout::Alignment::Center);
+    let details = detail_lines(details, chunks[1].height);
+    let input_title = match &app.error_message {
+        Some(err) => format!(" {} ", err),
+        None => format!(" Type {} to confirm ", expected),
+    };
+    let input = Paragraph::new(app.prompt_buffer.as_str())
+        .block(Block::default().borders(Borders::ALL).title(input_title).border_style(Style::default().fg(Color::Red)));
+
+    f.render_widget(block, area);
+    f.render_widget(question, chunks[0]);
+    f.render_widget(details, chunks[1]);
+    f.render_widget(input, chunks[2]);
+}
+
 fn render_help(f: &mut Frame, app: &mut App) {
     let area = centered_rect(80, 80, f.area());
     let mut help_text = String::from(" --- Xplore Help ---\n\n");
@@ -336,6 +989,188 @@ fn render_help(f: &mut Frame, app: &mut App) {
     f.render_widget(block, area);
 }
 
+fn render_bookmarks(f: &mut Frame, app: &mut App) {
+    let area = centered_rect(60, 60, f.area());
+
+    let items: Vec<ListItem> = if app.config.bookmarks.is_empty() {
+        vec![ListItem::new(format!(" No bookmarks yet. Press {} in a directory to add one. ", app.config.keybindings.add_bookmark))]
+    } else {
+        app.config.bookmarks.iter().enumerate().map(|(i, bookmark)| {
+            // Bookmarks whose directory is gone stay listed, greyed out, until removed
+            let mut style = if std::path::Path::new(bookmark).is_dir() {
+                Style::default()
+            } else {
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT)
+            };
+            if i == app.bookmark_index {
+                style = style.bg(Color::Blue).add_modifier(Modifier::BOLD);
+            }
+            ListItem::new(format!(" {} ", bookmark)).style(style)
+        }).collect()
+    };
+
+    let title = match &app.error_message {
+        Some(err) => format!(" Bookmarks: {} ", err),
+        None => " Bookmarks ([Enter] Open | [d] Remove | [Esc] Close) ".to_string(),
+    };
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(Color::Yellow)));
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(list, area);
+}
+
+fn render_recent_dirs(f: &mut Frame, app: &mut App) {
+    let area = centered_rect(60, 60, f.area());
+
+    let now = chrono::Local::now();
+    let items: Vec<ListItem> = if app.config.recent_dirs.is_empty() {
+        vec![ListItem::new(" No recent folders. ")]
+    } else {
+        app.config.recent_dirs.iter().map(|recent| {
+            let visited = chrono::DateTime::from_timestamp(recent.visited, 0)
+                .map(|time| format_relative(time.with_timezone(&chrono::Local), now))
+                .unwrap_or_default();
+            // Folders that are gone stay listed, greyed out, until removed
+            let style = if recent.path.is_dir() {
+                Style::default()
+            } else {
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT)
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!(" {} ", app.display_path(&recent.path))),
+                Span::styled(visited, Style::default().fg(Color::DarkGray)),
+            ])).style(style)
+        }).collect()
+    };
+
+    let title = match &app.error_message {
+        Some(err) => format!(" Recent Folders: {} ", err),
+        None => " Recent Folders ([Enter] Open | [d] Remove | [Esc] Close) ".to_string(),
+    };
+    // Unlike bookmarks the list can outgrow the popup, so let it scroll
+    let selected = (!app.config.recent_dirs.is_empty()).then_some(app.recent_index);
+    let mut state = ratatui::widgets::ListState::default().with_selected(selected);
+    let list = List::new(items)
+        .highlight_style(Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD))
+        .block(Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(Color::Yellow)));
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+fn render_batch_rename(f: &mut Frame, app: &mut App) {
+    let area = centered_rect(70, 60, f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+
+    let (title, border_color) = match &app.error_message {
+        Some(err) => (format!(" Batch Rename: {} ", err), Color::Red),
+        None => (" Batch Rename ({n} number, {name} name, {ext} extension) ".to_string(), Color::Yellow),
+    };
+    let input = Paragraph::new(app.prompt_buffer.as_str())
+        .block(Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(border_color)));
+    f.render_widget(input, chunks[0]);
+
+    let name_of = |path: &std::path::Path| path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let items: Vec<ListItem> = app.batch_rename_plan().into_iter().map(|(src, result)| match result {
+        Ok(dst) => ListItem::new(format!(" {} -> {} ", name_of(&src), name_of(&dst))),
+        Err(e) => ListItem::new(format!(" {} -> {} ", name_of(&src), e)).style(Style::default().fg(Color::Red)),
+    }).collect();
+    let preview = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(" Preview ([Enter] Apply | [Esc] Cancel) ").border_style(Style::default().fg(Color::Yellow)));
+    f.render_widget(preview, chunks[1]);
+}
+
+fn render_permissions(f: &mut Frame, app: &mut App) {
+    let area = centered_rect(50, 40, f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
+
+    let name = app.permission_target.as_ref()
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (title, border_color) = match &app.error_message {
+        Some(err) => (format!(" Permissions: {} ", err), Color::Red),
+        None => (format!(" Permissions: {} ", name), Color::Yellow),
+    };
+
+    let mut bits = vec![Span::raw(" ")];
+    for i in 0..9 {
+        let letter = ["r", "w", "x"][i % 3];
+        let set = app.permission_mode & (1 << (8 - i)) != 0;
+        let mut style = if set { Style::default().fg(Color::Green) } else { Style::default().fg(Color::DarkGray) };
+        if i == app.permission_cursor {
+            style = style.add_modifier(Modifier::REVERSED | Modifier::BOLD);
+        }
+        bits.push(Span::styled(if set { letter } else { "-" }, style));
+        bits.push(Span::raw(if i % 3 == 2 { "  " } else { " " }));
+    }
+    let text = vec![
+        Line::from(" Owner  Group  Other"),
+        Line::from(bits),
+        Line::from(format!(" Octal: {:04o}", app.permission_mode)),
+        Line::from(""),
+        Line::from(" [Left/Right] Move | [Space] Toggle"),
+        Line::from(" [0-7] Type octal | [Enter] Apply | [Esc] Cancel"),
+    ];
+    let paragraph = Paragraph::new(text)
+        .block(Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(border_color)));
+    f.render_widget(paragraph, area);
+}
+
+fn render_open_with(f: &mut Frame, app: &mut App) {
+    let area = centered_rect(50, 40, f.area());
+
+    let mut commands = app.open_with_candidates();
+    commands.push("Other command...".to_string());
+    let items: Vec<ListItem> = commands.iter().enumerate().map(|(i, command)| {
+        let style = if i == app.open_with_index {
+            Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        ListItem::new(format!(" {} ", command)).style(style)
+    }).collect();
+
+    let title = match &app.error_message {
+        Some(err) => format!(" Open With: {} ", err),
+        None => " Open With ([Enter] Run | [Esc] Close) ".to_string(),
+    };
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(Color::Yellow)));
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(list, area);
+}
+
+fn render_command(f: &mut Frame, app: &mut App) {
+    if let InputMode::Command = app.input_mode {
+        let area = centered_rect(60, 20, f.area());
+        let (title, border_color) = match &app.error_message {
+            Some(err) => (format!(" Shell Command: {} ", err), Color::Red),
+            None => (" Shell Command ({} = selected paths) ".to_string(), Color::Yellow),
+        };
+        let block = Paragraph::new(app.prompt_buffer.as_str())
+            .block(Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(border_color)));
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(block, area);
+        return;
+    }
+
+    let area = centered_rect(80, 70, f.area());
+    let text = app.command_output.as_deref().unwrap_or("Running...");
+    let block = Paragraph::new(text)
+        .scroll((app.command_scroll, 0))
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .title(" Command Output ([j/k] Scroll | [Esc] Close) ")
+            .border_style(Style::default().fg(Color::Yellow)))
+        .wrap(ratatui::widgets::Wrap { trim: false });
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(block, area);
+}
+
 fn render_terminal(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -412,3 +1247,26 @@ fn map_color(c: vt100::Color) -> Color {
         vt100::Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_counts_columns() {
+        assert_eq!(fit("notes.txt", 12), "notes.txt   ");
+        assert_eq!(fit("notes.txt", 6), "notes…");
+        // Each of these takes two columns
+        assert_eq!(fit("日本語.txt", 10), "日本語.txt");
+        assert_eq!(fit("日本語.txt", 6), "日本… ");
+        assert_eq!(fit("日本語.txt", 6).width(), 6);
+        assert_eq!(fit("anything", 0), "");
+    }
+
+    #[test]
+    fn test_date_column_width() {
+        assert_eq!(date_column_width("%Y-%m-%d %H:%M"), 16);
+        assert_eq!(date_column_width("%Y-%m-%d %H:%M:%S"), 19);
+        assert_eq!(date_column_width("%A %-d %B"), "Wednesday 27 September".len());
+    }
+}
@@ -0,0 +1,231 @@
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+use xplore_cli::filesystem::{CopyOptions, FileSystemManager, Matcher, SearchMode};
+use xplore_cli::metadata;
+
+/// Every file and folder below `root` as sorted relative paths, folders ending in "/".
+fn tree(root: &Path) -> Vec<String> {
+    let mut paths: Vec<String> = walkdir::WalkDir::new(root)
+        .min_depth(1)
+        .into_iter()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            let relative = entry.path().strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+            if entry.file_type().is_dir() { format!("{}/", relative) } else { relative }
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn make_tree(root: &Path) {
+    fs::create_dir_all(root.join("src/nested/deeper")).unwrap();
+    fs::create_dir_all(root.join("src/empty")).unwrap();
+    fs::write(root.join("src/top.txt"), "top").unwrap();
+    fs::write(root.join("src/nested/deeper/leaf.txt"), "leaf").unwrap();
+}
+
+#[test]
+fn copy_recursive_reproduces_nested_and_empty_folders() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    make_tree(root);
+    let manager = FileSystemManager::new(root);
+
+    manager.copy_recursive(&root.join("src"), &root.join("copy")).unwrap();
+    assert_eq!(tree(&root.join("copy")), tree(&root.join("src")));
+    assert_eq!(fs::read_to_string(root.join("copy/nested/deeper/leaf.txt")).unwrap(), "leaf");
+
+    // An empty folder on its own copies too
+    manager.copy_recursive(&root.join("src/empty"), &root.join("empty copy")).unwrap();
+    assert!(root.join("empty copy").is_dir());
+}
+
+#[test]
+fn copy_recursive_keeps_descriptions() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    make_tree(root);
+    let manager = FileSystemManager::new(root);
+    metadata::set_description(root.join("src"), "the folder").unwrap();
+    metadata::set_description(root.join("src/nested/deeper/leaf.txt"), "the leaf").unwrap();
+
+    manager.copy_recursive(&root.join("src"), &root.join("copy")).unwrap();
+    assert_eq!(metadata::get_description(root.join("copy")).as_deref(), Some("the folder"));
+    assert_eq!(metadata::get_description(root.join("copy/nested/deeper/leaf.txt")).as_deref(), Some("the leaf"));
+    // The original keeps its own
+    assert_eq!(metadata::get_description(root.join("src")).as_deref(), Some("the folder"));
+}
+
+#[test]
+fn copy_recursive_with_keeps_timestamps_and_permissions_on_request() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    make_tree(root);
+    let manager = FileSystemManager::new(root);
+    let old = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    filetime::set_file_mtime(root.join("src/top.txt"), old).unwrap();
+    filetime::set_file_mtime(root.join("src/nested"), old).unwrap();
+    let mut readonly = fs::metadata(root.join("src/top.txt")).unwrap().permissions();
+    readonly.set_readonly(true);
+    fs::set_permissions(root.join("src/top.txt"), readonly).unwrap();
+    let mtime = |path: &Path| filetime::FileTime::from_last_modification_time(&fs::metadata(path).unwrap());
+
+    manager.copy_recursive_with(&root.join("src"), &root.join("kept"), CopyOptions::default(), &mut |_| {}).unwrap();
+    assert_eq!(mtime(&root.join("kept/top.txt")), old);
+    assert_eq!(mtime(&root.join("kept/nested")), old);
+    assert!(fs::metadata(root.join("kept/top.txt")).unwrap().permissions().readonly());
+
+    let plain = CopyOptions { preserve_timestamps: false, preserve_perms: false, preserve_xattrs: false };
+    manager.copy_recursive_with(&root.join("src"), &root.join("plain"), plain, &mut |_| {}).unwrap();
+    assert_ne!(mtime(&root.join("plain/top.txt")), old);
+    assert!(!fs::metadata(root.join("plain/top.txt")).unwrap().permissions().readonly());
+}
+
+#[test]
+fn copy_recursive_refuses_to_copy_a_folder_into_itself() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    make_tree(root);
+    let manager = FileSystemManager::new(root);
+
+    let err = manager.copy_recursive(&root.join("src"), &root.join("src/nested/src")).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(!root.join("src/nested/src").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn copy_recursive_keeps_symlinks_as_links() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    make_tree(root);
+    // A link back up the tree would recurse forever if it were followed
+    std::os::unix::fs::symlink("..", root.join("src/nested/up")).unwrap();
+    let manager = FileSystemManager::new(root);
+
+    manager.copy_recursive(&root.join("src"), &root.join("copy")).unwrap();
+    let copied = root.join("copy/nested/up");
+    assert!(copied.is_symlink());
+    assert_eq!(fs::read_link(copied).unwrap(), Path::new(".."));
+}
+
+#[test]
+fn move_entry_keeps_descriptions() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    make_tree(root);
+    let manager = FileSystemManager::new(root);
+    metadata::set_description(root.join("src/top.txt"), "kept on move").unwrap();
+
+    manager.move_entry(&root.join("src/top.txt"), &root.join("moved.txt")).unwrap();
+    assert!(!root.join("src/top.txt").exists());
+    assert_eq!(metadata::get_description(root.join("moved.txt")).as_deref(), Some("kept on move"));
+
+    // Whole folders move with their contents
+    manager.move_entry(&root.join("src"), &root.join("renamed")).unwrap();
+    assert!(root.join("renamed/nested/deeper/leaf.txt").exists());
+    assert!(root.join("renamed/empty").is_dir());
+}
+
+#[test]
+fn delete_recursive_removes_trees_but_not_link_targets() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    make_tree(root);
+    let manager = FileSystemManager::new(root);
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(root.join("src"), root.join("link")).unwrap();
+        manager.delete_recursive(&root.join("link")).unwrap();
+        assert!(!root.join("link").exists());
+        assert!(root.join("src/top.txt").exists());
+    }
+
+    manager.delete_recursive(&root.join("src/empty")).unwrap();
+    manager.delete_recursive(&root.join("src")).unwrap();
+    assert!(tree(root).is_empty());
+}
+
+#[test]
+fn search_recursive_matches_names_and_contents() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    make_tree(root);
+    fs::write(root.join("src/nested/notes.md"), "mentions a leaf here").unwrap();
+
+    let collect = |query: &str, contents: bool, limit: usize| {
+        let matcher = Matcher::new(query, SearchMode::Substring).unwrap();
+        let mut found = Vec::new();
+        FileSystemManager::search_recursive(root, &matcher, contents, false, limit, |entry, content| {
+            found.push((entry.name, content.map(|c| c.line_number)));
+            true
+        });
+        found.sort();
+        found
+    };
+
+    assert_eq!(collect("LEAF", false, 100), [("leaf.txt".to_string(), None)]);
+    assert_eq!(
+        collect("leaf", true, 100),
+        [("leaf.txt".to_string(), None), ("notes.md".to_string(), Some(1))]
+    );
+    assert_eq!(collect("e", false, 2).len(), 2);
+    assert!(collect("missing", true, 100).is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn search_recursive_follows_links_once() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    make_tree(root);
+    fs::create_dir(root.join("elsewhere")).unwrap();
+    fs::write(root.join("elsewhere/linked.txt"), "").unwrap();
+    std::os::unix::fs::symlink(root.join("elsewhere"), root.join("src/first")).unwrap();
+    std::os::unix::fs::symlink(root.join("elsewhere"), root.join("src/second")).unwrap();
+    // A loop back to the top of the search
+    std::os::unix::fs::symlink(root.join("src"), root.join("src/nested/loop")).unwrap();
+
+    let search = |follow_links: bool| {
+        let matcher = Matcher::new("", SearchMode::Substring).unwrap();
+        let mut found = Vec::new();
+        FileSystemManager::search_recursive(root.join("src"), &matcher, false, follow_links, 1000, |entry, _| {
+            found.push(entry.name);
+            true
+        });
+        found
+    };
+
+    assert!(!search(false).contains(&"linked.txt".to_string()));
+    let followed = search(true);
+    assert_eq!(followed.iter().filter(|name| *name == "linked.txt").count(), 1);
+    assert_eq!(followed.iter().filter(|name| *name == "leaf.txt").count(), 1);
+}
+
+#[test]
+fn navigation_moves_through_history() {
+    let dir = tempdir().unwrap();
+    let root = fs::canonicalize(dir.path()).unwrap();
+    make_tree(&root);
+    let mut manager = FileSystemManager::new(&root);
+
+    manager.navigate_to("src/nested").unwrap();
+    assert_eq!(manager.current_path(), root.join("src/nested"));
+    assert!(manager.navigate_to("deeper/leaf.txt").is_err());
+    assert!(manager.navigate_to("does-not-exist").is_err());
+
+    assert!(manager.navigate_up());
+    assert_eq!(manager.current_path(), root.join("src"));
+    assert!(manager.go_back());
+    assert_eq!(manager.current_path(), root.join("src/nested"));
+    assert!(manager.go_forward());
+    assert!(!manager.go_forward());
+
+    // An empty folder lists only its "." and ".." rows
+    manager.navigate_to("empty").unwrap();
+    let names: Vec<String> = manager.list_directory().unwrap().into_iter().map(|e| e.name).collect();
+    assert_eq!(names, [".", ".."]);
+}